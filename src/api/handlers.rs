@@ -1,44 +1,101 @@
 use axum::{
     Json,
-    extract::{Path, Query},
+    extract::{Path, Query, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
-use chrono::Local;
+use chrono::{Local, NaiveDate};
+use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::storage::file::{load_todo_list, save_todo_list};
-use crate::todo::TodoItem;
+use crate::storage::search::DEFAULT_SEARCH_LIMIT;
+use crate::storage::sync::conflict_message;
+use crate::storage::TodoStore;
+use crate::storage::trash;
+use crate::utils::paths::validate_list_name;
+use crate::todo::{StateRegistry, TodoItem, TodoList, normalize_tags};
 
 use super::models::{
-    CreateTodoRequest, DateQuery, ErrorResponse, TodoListResponse, TodoResponse,
-    UpdateTodoRequest, parse_state,
+    AddCommentRequest, CommentListResponse, CommentResponse, CreateTodoRequest, DateQuery,
+    ListNamesResponse, ErrorResponse, ListQuery, PurgeQuery, PurgeResponse, SearchQuery,
+    SearchResponse, SearchResultResponse, TodoHistoryResponse, TodoListResponse,
+    TodoOccurrence, TodoResponse, TrashResponse, TrashedTodoResponse, UpdateTodoRequest,
+    parse_state,
 };
 
-pub async fn list_todos(Query(query): Query<DateQuery>) -> impl IntoResponse {
-    let date = query.date.unwrap_or_else(|| Local::now().date_naive());
+pub async fn list_todos(
+    State(store): State<Arc<dyn TodoStore>>,
+    Query(query): Query<ListQuery>,
+) -> impl IntoResponse {
+    let list = match load_requested_list(store.as_ref(), query.date, query.list.as_deref()) {
+        Ok(l) => l,
+        Err(response) => return *response,
+    };
 
-    match load_todo_list(date) {
-        Ok(list) => {
-            let response = TodoListResponse {
-                date: list.date,
-                items: list.items.iter().map(TodoResponse::from).collect(),
-            };
-            (StatusCode::OK, Json(response)).into_response()
+    let blocked = match store.find_blocked_indices(&list) {
+        Ok(b) => b,
+        Err(e) => return ErrorResponse::internal(e),
+    };
+
+    let response = TodoListResponse {
+        date: list.date,
+        list: list.name.clone(),
+        items: list
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| query.tag.as_ref().is_none_or(|tag| item.has_tag(tag)))
+            .map(|(idx, item)| TodoResponse {
+                blocked: blocked.contains(&idx),
+                ..TodoResponse::from(item)
+            })
+            .collect(),
+        conflict: list.file_conflict.then(|| conflict_message(list.date)),
+    };
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+/// Load the project list named in the request, or the daily list for `date` (default today).
+fn load_requested_list(
+    store: &dyn TodoStore,
+    date: Option<NaiveDate>,
+    name: Option<&str>,
+) -> Result<TodoList, Box<Response>> {
+    let result = match name {
+        Some(name) => {
+            validate_list_name(name)
+                .map_err(|e| Box::new(ErrorResponse::bad_request(e.to_string())))?;
+            store.load_named_list(name)
         }
+        None => store.load_todo_list(date.unwrap_or_else(|| Local::now().date_naive())),
+    };
+    result.map_err(|e| Box::new(ErrorResponse::internal(e)))
+}
+
+pub async fn list_names(State(store): State<Arc<dyn TodoStore>>) -> impl IntoResponse {
+    match store.list_names() {
+        Ok(lists) => (StatusCode::OK, Json(ListNamesResponse { lists })).into_response(),
         Err(e) => ErrorResponse::internal(e),
     }
 }
 
+/// Response for the item at `index`, including whether it is currently blocked.
+fn item_response(store: &dyn TodoStore, list: &TodoList, index: usize) -> anyhow::Result<TodoResponse> {
+    let blocked = store.find_blocked_indices(list)?;
+    Ok(TodoResponse {
+        blocked: blocked.contains(&index),
+        ..TodoResponse::from(&list.items[index])
+    })
+}
+
 pub async fn create_todo(
+    State(store): State<Arc<dyn TodoStore>>,
     Query(query): Query<DateQuery>,
     Json(req): Json<CreateTodoRequest>,
 ) -> impl IntoResponse {
-    let date = query.date.unwrap_or_else(|| Local::now().date_naive());
-
-    let mut list = match load_todo_list(date) {
+    let mut list = match load_requested_list(store.as_ref(), query.date, query.list.as_deref()) {
         Ok(l) => l,
-        Err(e) => return ErrorResponse::internal(e),
+        Err(response) => return *response,
     };
 
     let (indent_level, insert_index) = if let Some(parent_id) = req.parent_id {
@@ -50,30 +107,51 @@ pub async fn create_todo(
         (0, list.items.len())
     };
 
-    let mut item = TodoItem::new(req.content, indent_level);
+    let mut item = TodoItem::new(String::new(), indent_level);
+    item.set_content(&req.content);
+    if let Some(tags) = req.tags {
+        item.tags = normalize_tags([std::mem::take(&mut item.tags), tags].concat());
+    }
     item.parent_id = req.parent_id;
     item.due_date = req.due_date;
     item.description = req.description;
+    if req.start_date.is_some() {
+        item.start_date = req.start_date;
+    }
+
+    if let Some(blocked_by) = req.blocked_by {
+        match store.resolve_blocker_ids(&list, item.id, &blocked_by) {
+            Ok(ids) => item.blocked_by = ids,
+            Err(e) => return ErrorResponse::bad_request(e.to_string()),
+        }
+    }
 
-    let response = TodoResponse::from(&item);
     list.items.insert(insert_index, item);
 
-    if let Err(e) = save_todo_list(&list) {
-        return ErrorResponse::internal(e);
+    // Respond before a future start date moves the item off this day's list
+    let response = match item_response(store.as_ref(), &list, insert_index) {
+        Ok(response) => response,
+        Err(e) => return ErrorResponse::internal(e),
+    };
+
+    let saved = store
+        .schedule_future_items(&mut list)
+        .and_then(|_| store.save_todo_list(&mut list));
+    if let Err(e) = saved {
+        return ErrorResponse::save_failed(e);
     }
 
     (StatusCode::CREATED, Json(response)).into_response()
 }
 
 pub async fn delete_todo(
+    State(store): State<Arc<dyn TodoStore>>,
     Path(id): Path<Uuid>,
     Query(query): Query<DateQuery>,
 ) -> impl IntoResponse {
-    let date = query.date.unwrap_or_else(|| Local::now().date_naive());
-
-    let mut list = match load_todo_list(date) {
+    let mut list = match load_requested_list(store.as_ref(), query.date, query.list.as_deref()) {
         Ok(l) => l,
-        Err(e) => return ErrorResponse::internal(e),
+        Err(response) => return *response,
     };
 
     let Some(idx) = list.items.iter().position(|item| item.id == id) else {
@@ -88,39 +166,58 @@ pub async fn delete_todo(
     list.items.drain(start..end);
     list.recalculate_parent_ids();
 
-    if let Err(e) = save_todo_list(&list) {
-        return ErrorResponse::internal(e);
+    if let Err(e) = store.save_todo_list(&mut list) {
+        return ErrorResponse::save_failed(e);
     }
 
     StatusCode::NO_CONTENT.into_response()
 }
 
 pub async fn update_todo(
+    State(store): State<Arc<dyn TodoStore>>,
     Path(id): Path<Uuid>,
     Query(query): Query<DateQuery>,
     Json(req): Json<UpdateTodoRequest>,
 ) -> impl IntoResponse {
-    let date = query.date.unwrap_or_else(|| Local::now().date_naive());
-
-    let mut list = match load_todo_list(date) {
+    let mut list = match load_requested_list(store.as_ref(), query.date, query.list.as_deref()) {
         Ok(l) => l,
-        Err(e) => return ErrorResponse::internal(e),
+        Err(response) => return *response,
     };
 
-    let Some(item) = list.items.iter_mut().find(|item| item.id == id) else {
+    let Some(idx) = list.items.iter().position(|item| item.id == id) else {
         return ErrorResponse::not_found("Todo not found");
     };
 
+    let blocked_by = match req.blocked_by {
+        Some(ids) => match store.resolve_blocker_ids(&list, id, &ids) {
+            Ok(resolved) => Some(resolved),
+            Err(e) => return ErrorResponse::bad_request(e.to_string()),
+        },
+        None => None,
+    };
+
+    let item = &mut list.items[idx];
+
+    if let Some(blocked_by) = blocked_by {
+        item.blocked_by = blocked_by;
+    }
+
     if let Some(content) = req.content {
-        item.content = content;
+        item.merge_content(&content);
+    }
+
+    if let Some(tags) = req.tags {
+        item.tags = normalize_tags(tags);
     }
 
+    let state_changed = req.state.is_some();
     if let Some(state_str) = req.state {
         match parse_state(&state_str) {
-            Some(state) => item.state = state,
+            Some(state) => item.set_state(state),
             None => {
                 return ErrorResponse::bad_request(format!(
-                    "Invalid state: {state_str}. Use ' ', 'x', '?', or '!'"
+                    "Invalid state: {state_str}. Use one of {}",
+                    StateRegistry::global().describe()
                 ));
             }
         }
@@ -130,6 +227,10 @@ pub async fn update_todo(
         item.due_date = Some(due_date);
     }
 
+    if let Some(start_date) = req.start_date {
+        item.start_date = Some(start_date);
+    }
+
     if let Some(description) = req.description {
         item.description = if description.is_empty() {
             None
@@ -138,11 +239,181 @@ pub async fn update_todo(
         };
     }
 
-    let response = TodoResponse::from(&*item);
+    if state_changed {
+        list.sync_parent_states(idx);
+    }
 
-    if let Err(e) = save_todo_list(&list) {
-        return ErrorResponse::internal(e);
+    let response = match item_response(store.as_ref(), &list, idx) {
+        Ok(response) => response,
+        Err(e) => return ErrorResponse::internal(e),
+    };
+
+    let saved = store
+        .schedule_future_items(&mut list)
+        .and_then(|_| store.save_todo_list(&mut list));
+    if let Err(e) = saved {
+        return ErrorResponse::save_failed(e);
     }
 
     (StatusCode::OK, Json(response)).into_response()
 }
+
+pub async fn todo_history(
+    State(store): State<Arc<dyn TodoStore>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let history = match store.load_todo_history(id) {
+        Ok(h) => h,
+        Err(e) => return ErrorResponse::internal(e),
+    };
+
+    let Some((_, first)) = history.first() else {
+        return ErrorResponse::not_found("Todo not found");
+    };
+
+    let response = TodoHistoryResponse {
+        origin_id: first.origin(),
+        occurrences: history
+            .iter()
+            .map(|(date, item)| TodoOccurrence {
+                date: *date,
+                todo: TodoResponse::from(item),
+            })
+            .collect(),
+    };
+
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+pub async fn search(
+    State(store): State<Arc<dyn TodoStore>>,
+    Query(query): Query<SearchQuery>,
+) -> impl IntoResponse {
+    if query.q.trim().is_empty() {
+        return ErrorResponse::bad_request("Query parameter 'q' cannot be empty");
+    }
+
+    let hits = match store.search_todos(&query.q, query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT)) {
+        Ok(hits) => hits,
+        Err(e) => return ErrorResponse::internal(e),
+    };
+
+    let response = SearchResponse {
+        query: query.q,
+        results: hits.iter().map(SearchResultResponse::from).collect(),
+    };
+
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+pub async fn list_comments(
+    State(store): State<Arc<dyn TodoStore>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let comments = match store.load_comments(id) {
+        Ok(Some(c)) => c,
+        Ok(None) => return ErrorResponse::not_found("Todo not found"),
+        Err(e) => return ErrorResponse::internal(e),
+    };
+
+    let response = CommentListResponse {
+        todo_id: id,
+        comments: comments.iter().map(CommentResponse::from).collect(),
+    };
+
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+pub async fn add_comment(
+    State(store): State<Arc<dyn TodoStore>>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<AddCommentRequest>,
+) -> impl IntoResponse {
+    let body = req.body.trim();
+    if body.is_empty() {
+        return ErrorResponse::bad_request("Comment body cannot be empty");
+    }
+    let author = req
+        .author
+        .as_deref()
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .unwrap_or("api");
+
+    match store.add_comment(id, author, body) {
+        Ok(Some(comment)) => {
+            (StatusCode::CREATED, Json(CommentResponse::from(&comment))).into_response()
+        }
+        Ok(None) => ErrorResponse::not_found("Todo not found"),
+        Err(e) => ErrorResponse::internal(e),
+    }
+}
+
+pub async fn list_trash(State(store): State<Arc<dyn TodoStore>>) -> impl IntoResponse {
+    match store.load_trash() {
+        Ok(trash) => {
+            let response = TrashResponse {
+                items: trash.iter().map(TrashedTodoResponse::from).collect(),
+            };
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => ErrorResponse::internal(e),
+    }
+}
+
+/// Restore a deleted todo with the subtasks deleted along with it. Responds with the list it
+/// went back to, holding just the restored items.
+pub async fn restore_todo(
+    State(store): State<Arc<dyn TodoStore>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let (list, restored) = match store.restore_from_trash(id) {
+        Ok(Some(result)) => result,
+        Ok(None) => return ErrorResponse::not_found("Todo not found in the trash"),
+        Err(e) => return ErrorResponse::save_failed(e),
+    };
+
+    let response = TodoListResponse {
+        date: list.date,
+        list: list.name.clone(),
+        items: list.items[restored].iter().map(TodoResponse::from).collect(),
+        conflict: None,
+    };
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+/// Purge a deleted todo with the subtasks deleted along with it.
+pub async fn purge_todo(
+    State(store): State<Arc<dyn TodoStore>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let trash = match store.load_trash() {
+        Ok(trash) => trash,
+        Err(e) => return ErrorResponse::internal(e),
+    };
+    let ids: Vec<Uuid> = trash::subtree(&trash, id).iter().map(|entry| entry.item.id).collect();
+    if ids.is_empty() {
+        return ErrorResponse::not_found("Todo not found in the trash");
+    }
+
+    match store.purge_trash(&ids) {
+        Ok(purged) => (StatusCode::OK, Json(PurgeResponse { purged })).into_response(),
+        Err(e) => ErrorResponse::internal(e),
+    }
+}
+
+pub async fn purge_trash(
+    State(store): State<Arc<dyn TodoStore>>,
+    Query(query): Query<PurgeQuery>,
+) -> impl IntoResponse {
+    let purged = if query.all {
+        store.purge_trash_before(chrono::Utc::now())
+    } else {
+        store.purge_expired_trash()
+    };
+
+    match purged {
+        Ok(purged) => (StatusCode::OK, Json(PurgeResponse { purged })).into_response(),
+        Err(e) => ErrorResponse::internal(e),
+    }
+}
@@ -1,8 +1,12 @@
 use axum::{Json, body::Body, http::StatusCode, response::{IntoResponse, Response}};
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::storage::comments::Comment;
+use crate::storage::database::SaveConflict;
+use crate::storage::search::SearchHit;
+use crate::storage::trash::TrashedTodo;
 use crate::todo::{TodoItem, TodoState};
 
 #[derive(Debug, Serialize)]
@@ -10,10 +14,19 @@ pub struct TodoResponse {
     pub id: Uuid,
     pub content: String,
     pub state: String,
+    pub state_label: String,
     pub indent_level: usize,
     pub parent_id: Option<Uuid>,
     pub due_date: Option<NaiveDate>,
     pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub recurrence: Option<String>,
+    pub series_id: Option<Uuid>,
+    pub origin_id: Uuid,
+    pub carry_count: u32,
+    pub blocked_by: Vec<Uuid>,
+    pub blocked: bool,
+    pub start_date: Option<NaiveDate>,
 }
 
 impl From<&TodoItem> for TodoResponse {
@@ -22,10 +35,19 @@ impl From<&TodoItem> for TodoResponse {
             id: item.id,
             content: item.content.clone(),
             state: item.state.to_char().to_string(),
+            state_label: item.state.label(),
             indent_level: item.indent_level,
             parent_id: item.parent_id,
             due_date: item.due_date,
             description: item.description.clone(),
+            tags: item.tags.clone(),
+            recurrence: item.recurrence.as_ref().map(|r| r.to_string()),
+            series_id: item.series_id,
+            origin_id: item.origin(),
+            carry_count: item.carry_count,
+            blocked_by: item.blocked_by.clone(),
+            blocked: false,
+            start_date: item.start_date,
         }
     }
 }
@@ -33,7 +55,59 @@ impl From<&TodoItem> for TodoResponse {
 #[derive(Debug, Serialize)]
 pub struct TodoListResponse {
     pub date: NaiveDate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
     pub items: Vec<TodoResponse>,
+    /// Set when the list's markdown file conflicts with the stored list, which is returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TodoOccurrence {
+    pub date: NaiveDate,
+    #[serde(flatten)]
+    pub todo: TodoResponse,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TodoHistoryResponse {
+    pub origin_id: Uuid,
+    pub occurrences: Vec<TodoOccurrence>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommentResponse {
+    pub id: i64,
+    /// Lineage id of the todo the thread belongs to.
+    pub origin_id: Uuid,
+    pub author: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&Comment> for CommentResponse {
+    fn from(comment: &Comment) -> Self {
+        Self {
+            id: comment.id,
+            origin_id: comment.todo_id,
+            author: comment.author.clone(),
+            body: comment.body.clone(),
+            created_at: comment.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommentListResponse {
+    pub todo_id: Uuid,
+    pub comments: Vec<CommentResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddCommentRequest {
+    pub body: String,
+    pub author: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -42,6 +116,9 @@ pub struct CreateTodoRequest {
     pub parent_id: Option<Uuid>,
     pub due_date: Option<NaiveDate>,
     pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub blocked_by: Option<Vec<Uuid>>,
+    pub start_date: Option<NaiveDate>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -50,11 +127,122 @@ pub struct UpdateTodoRequest {
     pub state: Option<String>,
     pub due_date: Option<NaiveDate>,
     pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub blocked_by: Option<Vec<Uuid>>,
+    pub start_date: Option<NaiveDate>,
+}
+
+/// Answer to `GET /api/health`; `data_dir` tells clients which data the server serves.
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    pub status: &'static str,
+    pub data_dir: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListNamesResponse {
+    pub lists: Vec<String>,
 }
 
+/// Selects the daily list for `date` (default today), or the project list `list`.
 #[derive(Debug, Deserialize)]
 pub struct DateQuery {
     pub date: Option<NaiveDate>,
+    pub list: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResultResponse {
+    pub id: Uuid,
+    pub content: String,
+    pub state: String,
+    pub state_label: String,
+    /// Day the todo is (or was) on; absent for project list items.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
+    pub archived: bool,
+    pub snippet: String,
+    /// BM25 score; lower is a better match.
+    pub rank: f64,
+}
+
+impl From<&SearchHit> for SearchResultResponse {
+    fn from(hit: &SearchHit) -> Self {
+        Self {
+            id: hit.id,
+            content: hit.content.clone(),
+            state: hit.state.to_char().to_string(),
+            state_label: hit.state.label(),
+            date: hit.date,
+            list: hit.list.clone(),
+            archived: hit.archived,
+            snippet: hit.snippet.clone(),
+            rank: hit.rank,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    pub query: String,
+    pub results: Vec<SearchResultResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrashedTodoResponse {
+    #[serde(flatten)]
+    pub todo: TodoResponse,
+    /// Day the todo was deleted from; absent for project list items.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
+    pub position: usize,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl From<&TrashedTodo> for TrashedTodoResponse {
+    fn from(entry: &TrashedTodo) -> Self {
+        Self {
+            todo: TodoResponse::from(&entry.item),
+            date: entry.date,
+            list: entry.list.clone(),
+            position: entry.position,
+            deleted_at: entry.deleted_at(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrashResponse {
+    pub items: Vec<TrashedTodoResponse>,
+}
+
+/// Without `all`, only todos past the retention window are purged.
+#[derive(Debug, Deserialize)]
+pub struct PurgeQuery {
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PurgeResponse {
+    pub purged: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    pub date: Option<NaiveDate>,
+    pub list: Option<String>,
+    pub tag: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -80,6 +268,16 @@ impl ErrorResponse {
     pub fn bad_request(message: impl Into<String>) -> Response<Body> {
         (StatusCode::BAD_REQUEST, Json(Self::new(message))).into_response()
     }
+
+    /// 409 when another writer saved the list first and the edits could not be combined,
+    /// 500 for any other storage error.
+    pub fn save_failed(e: anyhow::Error) -> Response<Body> {
+        if e.is::<SaveConflict>() {
+            (StatusCode::CONFLICT, Json(Self::new(e.to_string()))).into_response()
+        } else {
+            Self::internal(e)
+        }
+    }
 }
 
 pub fn parse_state(s: &str) -> Option<TodoState> {
@@ -1,17 +1,26 @@
 use axum::{
-    Router,
+    Json, Router,
     routing::{delete, get, patch, post},
 };
 use tower_http::cors::{Any, CorsLayer};
+use std::sync::Arc;
 use tower_http::trace::TraceLayer;
 
 use super::handlers;
+use super::models::HealthResponse;
+use crate::storage::TodoStore;
+use crate::utils::paths::get_to_tui_dir;
 
-async fn health_check() -> &'static str {
-    "ok"
+async fn health_check() -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "ok",
+        data_dir: get_to_tui_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default(),
+    })
 }
 
-pub fn create_router() -> Router {
+pub fn create_router(store: Arc<dyn TodoStore>) -> Router {
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
@@ -19,10 +28,96 @@ pub fn create_router() -> Router {
 
     Router::new()
         .route("/api/health", get(health_check))
+        .route("/api/lists", get(handlers::list_names))
+        .route("/api/search", get(handlers::search))
         .route("/api/todos", get(handlers::list_todos))
         .route("/api/todos", post(handlers::create_todo))
         .route("/api/todos/{id}", delete(handlers::delete_todo))
         .route("/api/todos/{id}", patch(handlers::update_todo))
+        .route("/api/todos/{id}/history", get(handlers::todo_history))
+        .route("/api/todos/{id}/comments", get(handlers::list_comments))
+        .route("/api/todos/{id}/comments", post(handlers::add_comment))
+        .route("/api/trash", get(handlers::list_trash))
+        .route("/api/trash", delete(handlers::purge_trash))
+        .route("/api/trash/{id}", delete(handlers::purge_todo))
+        .route("/api/trash/{id}/restore", post(handlers::restore_todo))
         .layer(TraceLayer::new_for_http())
         .layer(cors)
+        .with_state(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::InMemoryStore;
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode};
+    use serde_json::{Value, json};
+    use tower::ServiceExt;
+
+    async fn send(router: &Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    // Everything below is only stored in memory, so each lookup that finds the todos shows
+    // the request went through the store rather than todos.db
+    #[tokio::test]
+    async fn test_router_works_over_an_in_memory_store() {
+        let router = create_router(Arc::new(InMemoryStore::new()));
+
+        let (status, draft) =
+            send(&router, Method::POST, "/api/todos?list=work", Some(json!({"content": "Write the draft"}))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let draft_id = draft["id"].as_str().unwrap().to_string();
+
+        let (status, send_it) = send(
+            &router,
+            Method::POST,
+            "/api/todos?list=work",
+            Some(json!({"content": "Send the draft", "blocked_by": [draft_id]})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(send_it["blocked"], json!(true));
+
+        let (_, list) = send(&router, Method::GET, "/api/todos?list=work", None).await;
+        assert_eq!(list["items"].as_array().unwrap().len(), 2);
+        assert_eq!(list["items"][1]["blocked"], json!(true));
+
+        let (status, _) = send(
+            &router,
+            Method::POST,
+            &format!("/api/todos/{draft_id}/comments"),
+            Some(json!({"body": "Outline done"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (_, comments) = send(&router, Method::GET, &format!("/api/todos/{draft_id}/comments"), None).await;
+        assert_eq!(comments["comments"][0]["body"], json!("Outline done"));
+
+        let (_, found) = send(&router, Method::GET, "/api/search?q=draft", None).await;
+        assert_eq!(found["results"].as_array().unwrap().len(), 2);
+
+        let (status, history) = send(&router, Method::GET, &format!("/api/todos/{draft_id}/history"), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(history["occurrences"].as_array().unwrap().len(), 1);
+
+        let (status, _) = send(
+            &router,
+            Method::POST,
+            &format!("/api/todos/{}/comments", uuid::Uuid::new_v4()),
+            Some(json!({"body": "Lost"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
 }
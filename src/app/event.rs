@@ -3,7 +3,11 @@ use super::state::{AppState, PluginSubState};
 use crate::clipboard::copy_to_clipboard;
 use crate::keybindings::{Action, KeyBinding, KeyLookupResult};
 use crate::plugin::PluginRegistry;
-use crate::storage::{execute_rollover, find_rollover_candidates, save_todo_list, soft_delete_todos};
+use crate::storage::events::{EventSource, set_event_source};
+use crate::storage::{execute_rollover, find_rollover_candidates};
+use crate::ui::components::todo_list::{
+    BLOCKED_BADGE, carried_badge, elapsed_badge, progress_badge,
+};
 use crate::utils::unicode::{
     next_char_boundary, next_word_boundary, prev_char_boundary, prev_word_boundary,
 };
@@ -13,7 +17,7 @@ use std::sync::mpsc;
 use std::thread;
 
 /// Total number of lines in the help content (must match render_help_overlay)
-const HELP_TOTAL_LINES: u16 = 57;
+const HELP_TOTAL_LINES: u16 = 64;
 
 pub fn handle_key_event(key: KeyEvent, state: &mut AppState) -> Result<()> {
     // Handle help overlay scrolling when help is visible
@@ -44,6 +48,8 @@ pub fn handle_key_event(key: KeyEvent, state: &mut AppState) -> Result<()> {
         }
     }
 
+    let last_save_time = state.last_save_time;
+
     match state.mode {
         Mode::Navigate => handle_navigate_mode(key, state)?,
         Mode::Visual => handle_visual_mode(key, state)?,
@@ -51,6 +57,16 @@ pub fn handle_key_event(key: KeyEvent, state: &mut AppState) -> Result<()> {
         Mode::ConfirmDelete => handle_confirm_delete_mode(key, state)?,
         Mode::Plugin => handle_plugin_mode(key, state)?,
         Mode::Rollover => handle_rollover_mode(key, state)?,
+        Mode::Scheduled => handle_scheduled_mode(key, state)?,
+        Mode::Detail => handle_detail_mode(key, state)?,
+        Mode::ListPicker => handle_list_picker_mode(key, state)?,
+        Mode::Section => handle_section_mode(key, state)?,
+        Mode::Trash => handle_trash_mode(key, state)?,
+    }
+
+    // Saving may have started or stopped time tracking on an item
+    if state.last_save_time != last_save_time {
+        state.refresh_tracked_time();
     }
     Ok(())
 }
@@ -130,6 +146,7 @@ pub fn handle_mouse_event(mouse: MouseEvent, state: &mut AppState) -> Result<()>
                         item.toggle_state();
                         state.unsaved_changes = true;
                     }
+                    state.todo_list.sync_parent_states(item_idx);
                     state.cursor_position = item_idx;
                 }
                 ClickZone::Content => {
@@ -142,9 +159,10 @@ pub fn handle_mouse_event(mouse: MouseEvent, state: &mut AppState) -> Result<()>
     }
 
     if state.unsaved_changes {
-        save_todo_list(&state.todo_list)?;
+        state.store.save_todo_list(&mut state.todo_list)?;
         state.unsaved_changes = false;
         state.last_save_time = Some(std::time::Instant::now());
+        state.refresh_tracked_time();
     }
 
     Ok(())
@@ -175,13 +193,24 @@ fn map_click_to_item(
     let mut current_visual_row = 0;
     let mut list_item_count = 0;
 
-    let hidden_indices = state.todo_list.build_hidden_indices();
+    let hidden_indices = state.hidden_indices();
 
     for (idx, item) in state.todo_list.items.iter().enumerate() {
         if hidden_indices.contains(&idx) {
             continue;
         }
 
+        // A section heading above the todo is a ListItem of one row; clicking it does nothing
+        if state.section_header(idx, &hidden_indices).is_some() {
+            if list_item_count >= scroll_offset {
+                if visual_row == current_visual_row {
+                    return None;
+                }
+                current_visual_row += 1;
+            }
+            list_item_count += 1;
+        }
+
         // Skip ListItems that are scrolled past (above viewport)
         // Each todo is 1 ListItem, plus 1 more if it has an expanded description
         if list_item_count < scroll_offset {
@@ -284,12 +313,38 @@ fn calculate_item_visual_height(
     let collapse_indicator_len = if item.collapsed && has_children {
         8 // " (X/Y)" rough estimate
     } else {
-        0
+        state
+            .todo_list
+            .subtree_progress(idx)
+            .map(|p| progress_badge(p).chars().count())
+            .unwrap_or(0)
     };
 
-    let content_len = item.content.len() + due_date_len + collapse_indicator_len;
+    let tags_len: usize = item.tags.iter().map(|t| t.len() + 2).sum(); // " #tag"
+
+    let recurrence_len = item
+        .recurrence
+        .as_ref()
+        .map(|r| r.to_string().len() + 3) // " ↻ rule"
+        .unwrap_or(0);
+
+    let carried_len = carried_badge(item.carry_count).len();
+    let blocked_len = if state.is_blocked(idx) { BLOCKED_BADGE.len() } else { 0 };
+    let elapsed_len = state
+        .elapsed(item)
+        .map(|d| elapsed_badge(d).chars().count())
+        .unwrap_or(0);
+
+    let content_len = item.content.len()
+        + tags_len
+        + recurrence_len
+        + due_date_len
+        + carried_len
+        + blocked_len
+        + elapsed_len
+        + collapse_indicator_len;
     let wrapped_lines = if content_max_width > 0 {
-        (content_len + content_max_width - 1) / content_max_width
+        content_len.div_ceil(content_max_width)
     } else {
         1
     };
@@ -319,7 +374,7 @@ fn calculate_description_visual_height(
             } else {
                 let para_len = paragraph.len();
                 let wrapped = if inner_width > 0 {
-                    (para_len + inner_width - 1) / inner_width
+                    para_len.div_ceil(inner_width)
                 } else {
                     1
                 };
@@ -361,7 +416,7 @@ fn handle_navigate_mode(key: KeyEvent, state: &mut AppState) -> Result<()> {
     }
 
     if state.unsaved_changes {
-        save_todo_list(&state.todo_list)?;
+        state.store.save_todo_list(&mut state.todo_list)?;
         state.unsaved_changes = false;
         state.last_save_time = Some(std::time::Instant::now());
     }
@@ -386,6 +441,7 @@ fn execute_navigate_action(action: Action, state: &mut AppState) -> Result<()> {
             | Action::MoveItemUp
             | Action::MoveItemDown
             | Action::ToggleCollapse
+            | Action::EditSection
             | Action::Undo
     );
 
@@ -413,24 +469,22 @@ fn execute_navigate_action(action: Action, state: &mut AppState) -> Result<()> {
         Action::CycleState => {
             state.cycle_current_item_state();
         }
-        Action::Delete => {
-            if !state.todo_list.items.is_empty() {
-                let has_children = state.todo_list.has_children(state.cursor_position);
-                if has_children {
-                    let (_, end) = state
-                        .todo_list
-                        .get_item_range(state.cursor_position)
-                        .unwrap_or((state.cursor_position, state.cursor_position + 1));
-                    let subtask_count = end - state.cursor_position - 1;
-                    state.pending_delete_subtask_count = Some(subtask_count);
-                    state.mode = Mode::ConfirmDelete;
-                } else {
-                    state.save_undo();
-                    delete_current_item(state)?;
-                    save_todo_list(&state.todo_list)?;
-                    state.unsaved_changes = false;
-                    state.last_save_time = Some(std::time::Instant::now());
-                }
+        Action::Delete if !state.todo_list.items.is_empty() => {
+            let has_children = state.todo_list.has_children(state.cursor_position);
+            if has_children {
+                let (_, end) = state
+                    .todo_list
+                    .get_item_range(state.cursor_position)
+                    .unwrap_or((state.cursor_position, state.cursor_position + 1));
+                let subtask_count = end - state.cursor_position - 1;
+                state.pending_delete_subtask_count = Some(subtask_count);
+                state.mode = Mode::ConfirmDelete;
+            } else {
+                state.save_undo();
+                delete_current_item(state)?;
+                state.store.save_todo_list(&mut state.todo_list)?;
+                state.unsaved_changes = false;
+                state.last_save_time = Some(std::time::Instant::now());
             }
         }
         Action::NewItem => {
@@ -525,19 +579,15 @@ fn execute_navigate_action(action: Action, state: &mut AppState) -> Result<()> {
         Action::CollapseOrParent => {
             state.collapse_or_move_to_parent();
         }
-        Action::Undo => {
-            if state.undo() {
-                save_todo_list(&state.todo_list)?;
-                state.last_save_time = Some(std::time::Instant::now());
-            }
+        Action::Undo if state.undo() => {
+            state.store.save_todo_list(&mut state.todo_list)?;
+            state.last_save_time = Some(std::time::Instant::now());
         }
         Action::ToggleHelp => {
             state.show_help = !state.show_help;
         }
-        Action::CloseHelp => {
-            if state.show_help {
-                state.show_help = false;
-            }
+        Action::CloseHelp if state.show_help => {
+            state.show_help = false;
         }
         Action::Quit => {
             if state.show_help {
@@ -562,12 +612,33 @@ fn execute_navigate_action(action: Action, state: &mut AppState) -> Result<()> {
             // Check if we have pending rollover data, or try to find new candidates
             if state.has_pending_rollover() {
                 state.mode = Mode::Rollover;
-            } else if let Ok(Some((source_date, items))) = find_rollover_candidates() {
+            } else if let Ok(Some((source_date, items))) = find_rollover_candidates(state.store.as_ref()) {
                 state.open_rollover_modal(source_date, items);
             } else {
                 state.set_status_message("No incomplete items to rollover".to_string());
             }
         }
+        Action::OpenScheduledView if !state.open_scheduled_view()? => {
+            state.set_status_message("Nothing is scheduled".to_string());
+        }
+        Action::OpenTrash if !state.open_trash_view()? => {
+            state.set_status_message("The trash is empty".to_string());
+        }
+        Action::OpenDetailView => {
+            state.open_detail_view()?;
+        }
+        Action::OpenListSwitcher => {
+            state.open_list_picker(false)?;
+        }
+        Action::EditSection if state.selected_item().is_some() => {
+            state.start_section_edit();
+        }
+        Action::MoveToList if state.is_readonly() => {
+            state.set_status_message("Cannot move items from an archived day".to_string());
+        }
+        Action::MoveToList if state.selected_item().is_some() => {
+            state.open_list_picker(true)?;
+        }
         Action::Yank => {
             if let Some(item) = state.selected_item() {
                 let text = item.content.clone();
@@ -587,6 +658,13 @@ fn execute_navigate_action(action: Action, state: &mut AppState) -> Result<()> {
                 }
             }
         }
+        Action::CycleTagFilter => {
+            state.cycle_tag_filter();
+            match state.tag_filter {
+                Some(ref tag) => state.set_status_message(format!("Filtering by #{tag}")),
+                None => state.set_status_message("Tag filter cleared".to_string()),
+            }
+        }
         _ => {}
     }
     Ok(())
@@ -598,7 +676,7 @@ fn handle_visual_mode(key: KeyEvent, state: &mut AppState) -> Result<()> {
     }
 
     if state.unsaved_changes {
-        save_todo_list(&state.todo_list)?;
+        state.store.save_todo_list(&mut state.todo_list)?;
         state.unsaved_changes = false;
         state.last_save_time = Some(std::time::Instant::now());
     }
@@ -622,11 +700,9 @@ fn execute_visual_action(action: Action, state: &mut AppState) -> Result<()> {
             state.clear_selection();
             state.mode = Mode::Navigate;
         }
-        Action::Undo => {
-            if state.undo() {
-                save_todo_list(&state.todo_list)?;
-                state.last_save_time = Some(std::time::Instant::now());
-            }
+        Action::Undo if state.undo() => {
+            state.store.save_todo_list(&mut state.todo_list)?;
+            state.last_save_time = Some(std::time::Instant::now());
         }
         Action::Indent => {
             if let Some((start, end)) = state.get_selection_range() {
@@ -674,7 +750,7 @@ fn handle_confirm_delete_mode(key: KeyEvent, state: &mut AppState) -> Result<()>
         KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
             state.save_undo();
             delete_current_item(state)?;
-            save_todo_list(&state.todo_list)?;
+            state.store.save_todo_list(&mut state.todo_list)?;
             state.unsaved_changes = false;
             state.last_save_time = Some(std::time::Instant::now());
             state.pending_delete_subtask_count = None;
@@ -694,8 +770,10 @@ fn handle_rollover_mode(key: KeyEvent, state: &mut AppState) -> Result<()> {
         KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
             // Execute rollover
             if let Some(pending) = state.pending_rollover.take() {
-                let new_list = execute_rollover(pending.source_date, pending.items)?;
+                let new_list = execute_rollover(state.store.as_ref(), pending.source_date, pending.items)?;
                 state.todo_list = new_list;
+                state.refresh_blockers();
+                state.refresh_tracked_time();
                 state.cursor_position = 0;
                 state.set_status_message("Rolled over incomplete items".to_string());
             }
@@ -710,6 +788,112 @@ fn handle_rollover_mode(key: KeyEvent, state: &mut AppState) -> Result<()> {
     Ok(())
 }
 
+fn handle_scheduled_mode(key: KeyEvent, state: &mut AppState) -> Result<()> {
+    if let KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('S') = key.code {
+        state.close_scheduled_view();
+    }
+    Ok(())
+}
+
+fn handle_detail_mode(key: KeyEvent, state: &mut AppState) -> Result<()> {
+    if let Some(input) = state.comment_input.as_mut() {
+        match key.code {
+            KeyCode::Esc => state.comment_input = None,
+            KeyCode::Enter => state.submit_comment()?,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    match key.code {
+        KeyCode::Char('a') if state.detail_history.is_none() => state.comment_input = Some(String::new()),
+        KeyCode::Char('h') => state.toggle_detail_history()?,
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('K') => {
+            state.close_detail_view();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_list_picker_mode(key: KeyEvent, state: &mut AppState) -> Result<()> {
+    let Some(picker) = state.list_picker.as_mut() else {
+        state.mode = Mode::Navigate;
+        return Ok(());
+    };
+
+    if let Some(input) = picker.input.as_mut() {
+        match key.code {
+            KeyCode::Esc => picker.input = None,
+            KeyCode::Enter if !input.trim().is_empty() => state.confirm_list_picker()?,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down if picker.selected < picker.names.len() => {
+            picker.selected += 1;
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            picker.selected = picker.selected.saturating_sub(1);
+        }
+        KeyCode::Char('n') => picker.input = Some(String::new()),
+        KeyCode::Enter => state.confirm_list_picker()?,
+        KeyCode::Esc | KeyCode::Char('q') => state.close_list_picker(),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_trash_mode(key: KeyEvent, state: &mut AppState) -> Result<()> {
+    let Some(view) = state.trash_view.as_mut() else {
+        state.mode = Mode::Navigate;
+        return Ok(());
+    };
+
+    match key.code {
+        KeyCode::Char('j') | KeyCode::Down if view.selected + 1 < view.rows().len() => {
+            view.selected += 1;
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            view.selected = view.selected.saturating_sub(1);
+        }
+        KeyCode::Enter | KeyCode::Char('r') => state.restore_selected_trash()?,
+        KeyCode::Char('D') => state.purge_selected_trash()?,
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('X') => state.close_trash_view(),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_section_mode(key: KeyEvent, state: &mut AppState) -> Result<()> {
+    match key.code {
+        KeyCode::Esc => state.close_section_edit(),
+        KeyCode::Enter => state.confirm_section_edit(),
+        KeyCode::Backspace => {
+            state.edit_buffer.pop();
+        }
+        KeyCode::Char(c) => state.edit_buffer.push(c),
+        _ => {}
+    }
+
+    if state.unsaved_changes {
+        state.store.save_todo_list(&mut state.todo_list)?;
+        state.unsaved_changes = false;
+        state.last_save_time = Some(std::time::Instant::now());
+    }
+    Ok(())
+}
+
 fn handle_edit_mode(key: KeyEvent, state: &mut AppState) -> Result<()> {
     if let Some(action) = state.keybindings.get_edit_action(&key) {
         match action {
@@ -721,27 +905,20 @@ fn handle_edit_mode(key: KeyEvent, state: &mut AppState) -> Result<()> {
                 save_edit_buffer(state)?;
                 new_item_at_same_level(state);
             }
-            Action::EditBackspace => {
-                if state.edit_cursor_pos > 0 {
-                    let prev_boundary =
-                        prev_char_boundary(&state.edit_buffer, state.edit_cursor_pos);
-                    state
-                        .edit_buffer
-                        .drain(prev_boundary..state.edit_cursor_pos);
-                    state.edit_cursor_pos = prev_boundary;
-                }
+            Action::EditBackspace if state.edit_cursor_pos > 0 => {
+                let prev_boundary = prev_char_boundary(&state.edit_buffer, state.edit_cursor_pos);
+                state
+                    .edit_buffer
+                    .drain(prev_boundary..state.edit_cursor_pos);
+                state.edit_cursor_pos = prev_boundary;
             }
-            Action::EditLeft => {
-                if state.edit_cursor_pos > 0 {
-                    state.edit_cursor_pos =
-                        prev_char_boundary(&state.edit_buffer, state.edit_cursor_pos);
-                }
+            Action::EditLeft if state.edit_cursor_pos > 0 => {
+                state.edit_cursor_pos =
+                    prev_char_boundary(&state.edit_buffer, state.edit_cursor_pos);
             }
-            Action::EditRight => {
-                if state.edit_cursor_pos < state.edit_buffer.len() {
-                    state.edit_cursor_pos =
-                        next_char_boundary(&state.edit_buffer, state.edit_cursor_pos);
-                }
+            Action::EditRight if state.edit_cursor_pos < state.edit_buffer.len() => {
+                state.edit_cursor_pos =
+                    next_char_boundary(&state.edit_buffer, state.edit_cursor_pos);
             }
             Action::EditWordLeft => {
                 state.edit_cursor_pos =
@@ -795,7 +972,7 @@ fn handle_edit_mode(key: KeyEvent, state: &mut AppState) -> Result<()> {
 
 fn enter_edit_mode(state: &mut AppState) {
     if let Some(item) = state.selected_item() {
-        state.edit_buffer = item.content.clone();
+        state.edit_buffer = item.editable_content();
         state.edit_cursor_pos = state.edit_buffer.len();
         state.mode = Mode::Edit;
         state.is_creating_new_item = false;
@@ -846,7 +1023,7 @@ fn delete_current_item(state: &mut AppState) -> Result<()> {
         .map(|item| item.id)
         .collect();
 
-    soft_delete_todos(&ids, date)?;
+    state.store.soft_delete_todos(&ids, date)?;
     state.todo_list.remove_item_range(start, end)?;
     state.clamp_cursor();
     Ok(())
@@ -869,6 +1046,7 @@ fn save_edit_buffer(state: &mut AppState) -> Result<()> {
                 .todo_list
                 .add_item_with_indent(state.edit_buffer.clone(), state.pending_indent_level);
             state.cursor_position = 0;
+            state.todo_list.items[0].set_content(&state.edit_buffer);
         } else {
             let insert_position = if state.insert_above {
                 state.cursor_position
@@ -887,11 +1065,15 @@ fn save_edit_buffer(state: &mut AppState) -> Result<()> {
                     state.cursor_position + 1
                 }
             };
+            // New items join the section of the item they were added next to
+            let section = state.todo_list.items[state.cursor_position].section.clone();
             state.todo_list.insert_item(
                 insert_position,
                 state.edit_buffer.clone(),
                 state.pending_indent_level,
             )?;
+            state.todo_list.items[insert_position].set_content(&state.edit_buffer);
+            state.todo_list.items[insert_position].section = section;
             if state.insert_above {
                 state.cursor_position += 1;
             } else {
@@ -901,13 +1083,23 @@ fn save_edit_buffer(state: &mut AppState) -> Result<()> {
         state.is_creating_new_item = false;
         state.insert_above = false;
     } else if state.cursor_position < state.todo_list.items.len() {
-        state.todo_list.items[state.cursor_position].content = state.edit_buffer.clone();
+        state.todo_list.items[state.cursor_position].set_content(&state.edit_buffer);
     } else {
         state
             .todo_list
             .add_item_with_indent(state.edit_buffer.clone(), 0);
         state.cursor_position = state.todo_list.items.len() - 1;
+        state.todo_list.items[state.cursor_position].set_content(&state.edit_buffer);
+    }
+
+    // Items given a future @start(...) leave today's list until that day
+    let scheduled = state.store.schedule_future_items(&mut state.todo_list)?;
+    if let Some(start) = scheduled.first().and_then(|item| item.start_date) {
+        state.clamp_cursor();
+        state.set_status_message(format!("Scheduled for {}", start.format("%a %b %d")));
     }
+    // An @after(...) may name blockers on other days
+    state.refresh_blockers();
 
     state.edit_buffer.clear();
     state.edit_cursor_pos = 0;
@@ -1092,7 +1284,10 @@ fn handle_plugin_preview(
                 state.todo_list.items.push(item);
             }
             state.unsaved_changes = true;
-            save_todo_list(&state.todo_list)?;
+            set_event_source(EventSource::Plugin);
+            let saved = state.store.save_todo_list(&mut state.todo_list);
+            set_event_source(EventSource::Tui);
+            saved?;
             state.unsaved_changes = false;
             state.last_save_time = Some(std::time::Instant::now());
             state.set_status_message(format!("Added {count} item(s) from plugin"));
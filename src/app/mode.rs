@@ -9,6 +9,11 @@ pub enum Mode {
     ConfirmDelete,
     Plugin,
     Rollover,
+    Scheduled,
+    Detail,
+    ListPicker,
+    Section,
+    Trash,
 }
 
 impl fmt::Display for Mode {
@@ -20,6 +25,11 @@ impl fmt::Display for Mode {
             Mode::ConfirmDelete => write!(f, "CONFIRM"),
             Mode::Plugin => write!(f, "PLUGIN"),
             Mode::Rollover => write!(f, "ROLLOVER"),
+            Mode::Scheduled => write!(f, "SCHEDULED"),
+            Mode::Detail => write!(f, "DETAIL"),
+            Mode::ListPicker => write!(f, "LISTS"),
+            Mode::Section => write!(f, "SECTION"),
+            Mode::Trash => write!(f, "TRASH"),
         }
     }
 }
@@ -1,15 +1,20 @@
 use super::mode::Mode;
 use crate::keybindings::{KeyBinding, KeybindingCache};
 use crate::plugin::{GeneratorInfo, PluginRegistry};
-use crate::storage::file::load_todo_list;
-use crate::storage::load_todos_for_viewing;
-use crate::storage::UiCache;
-use crate::todo::{TodoItem, TodoList};
+use crate::storage::comments::Comment;
+use crate::storage::database::SaveConflict;
+use crate::storage::events::{Event, EventFilter};
+use crate::storage::sync::{SyncOutcome, conflict_message};
+use crate::storage::time_tracking::TrackedTime;
+use crate::storage::trash::{TrashedTodo, roots, subtree};
+use crate::storage::{TodoStore, UiCache};
+use crate::todo::{TodoItem, TodoList, TodoState};
 use crate::ui::theme::Theme;
 use anyhow::Result;
 use chrono::{Duration, Local, NaiveDate};
 use ratatui::widgets::ListState;
-use std::sync::mpsc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, mpsc};
 use std::time::Instant;
 use uuid::Uuid;
 
@@ -44,7 +49,51 @@ pub struct PendingRollover {
     pub items: Vec<TodoItem>,
 }
 
+/// The list switcher, also used to pick the target when moving an item to another list.
+/// Entry 0 is today's list, followed by the project lists.
+#[derive(Debug, Clone)]
+pub struct ListPicker {
+    pub names: Vec<String>,
+    pub selected: usize,
+    /// Name of a new list being typed, if any
+    pub input: Option<String>,
+    /// Move the selected item to the chosen list instead of switching to it
+    pub moving: bool,
+}
+
+impl ListPicker {
+    /// The chosen list: `None` for today's list, otherwise a project list name.
+    pub fn selected_name(&self) -> Option<&str> {
+        self.selected
+            .checked_sub(1)
+            .and_then(|i| self.names.get(i))
+            .map(String::as_str)
+    }
+}
+
+/// The trash overlay. Each row is a todo that was deleted, together with the subtasks deleted
+/// along with it.
+#[derive(Debug, Clone)]
+pub struct TrashView {
+    /// Every deleted todo, most recently deleted first
+    pub entries: Vec<TrashedTodo>,
+    pub selected: usize,
+}
+
+impl TrashView {
+    /// The rows shown, each with the number of subtasks deleted along with it.
+    pub fn rows(&self) -> Vec<(&TrashedTodo, usize)> {
+        roots(&self.entries)
+    }
+
+    fn selected_id(&self) -> Option<Uuid> {
+        self.rows().get(self.selected).map(|(entry, _)| entry.item.id)
+    }
+}
+
 pub struct AppState {
+    /// Where lists are loaded from and saved to
+    pub store: Arc<dyn TodoStore>,
     pub todo_list: TodoList,
     pub cursor_position: usize,
     pub mode: Mode,
@@ -80,10 +129,27 @@ pub struct AppState {
     pub terminal_height: u16,
     /// Help overlay scroll offset
     pub help_scroll: u16,
+    /// When set, only items carrying this tag (plus their ancestors and descendants) are shown
+    pub tag_filter: Option<String>,
+    /// Blockers from other days that are still unfinished; same-day blockers are checked live
+    pub open_external_blockers: HashSet<Uuid>,
+    /// Items waiting for their start date, shown in the scheduled view
+    pub scheduled_items: Vec<TodoItem>,
+    /// Time logged per lineage id for items on the current list
+    pub tracked_time: HashMap<Uuid, TrackedTime>,
+    /// Comment thread of the item shown in the detail view
+    pub detail_comments: Vec<Comment>,
+    /// Comment being typed in the detail view, if any
+    pub comment_input: Option<String>,
+    /// Change history of the item in the detail view, when shown instead of the comments
+    pub detail_history: Option<Vec<Event>>,
+    pub list_picker: Option<ListPicker>,
+    pub trash_view: Option<TrashView>,
 }
 
 impl AppState {
     pub fn new(
+        store: Arc<dyn TodoStore>,
         todo_list: TodoList,
         theme: Theme,
         keybindings: KeybindingCache,
@@ -102,6 +168,7 @@ impl AppState {
             .unwrap_or(0);
 
         let mut state = Self {
+            store,
             todo_list,
             cursor_position,
             mode: Mode::Navigate,
@@ -134,7 +201,18 @@ impl AppState {
             terminal_width: 80,  // Default, updated on first render
             terminal_height: 24, // Default, updated on first render
             help_scroll: 0,
+            tag_filter: None,
+            open_external_blockers: HashSet::new(),
+            scheduled_items: Vec::new(),
+            tracked_time: HashMap::new(),
+            detail_comments: Vec::new(),
+            comment_input: None,
+            detail_history: None,
+            list_picker: None,
+            trash_view: None,
         };
+        state.refresh_blockers();
+        state.refresh_tracked_time();
         // Sync list state with cursor position
         state.sync_list_state();
         state
@@ -153,18 +231,30 @@ impl AppState {
         self.viewing_date != self.today
     }
 
+    /// Indices hidden by collapsed parents or by the active tag filter.
+    pub fn hidden_indices(&self) -> HashSet<usize> {
+        let mut hidden = self.todo_list.build_hidden_indices();
+        if let Some(ref tag) = self.tag_filter {
+            hidden.extend(self.todo_list.build_tag_hidden_indices(tag));
+        }
+        hidden
+    }
+
     /// Returns the count of list items rendered (excluding hidden collapsed children,
     /// but including expanded description boxes which are separate ListItems).
     /// Used for scroll position indicator and scrollbar.
     pub fn visible_item_count(&self) -> usize {
-        let hidden = self.todo_list.build_hidden_indices();
+        let hidden = self.hidden_indices();
         let mut count = 0;
         for (i, item) in self.todo_list.items.iter().enumerate() {
             if hidden.contains(&i) {
                 continue;
             }
             count += 1;
-            // Expanded descriptions render as a separate ListItem
+            // Section headings and expanded descriptions render as separate ListItems
+            if self.section_header(i, &hidden).is_some() {
+                count += 1;
+            }
             if !item.collapsed && item.description.is_some() {
                 count += 1;
             }
@@ -174,15 +264,22 @@ impl AppState {
 
     /// Sync list_state selection with cursor_position among visible items only.
     /// This calculates the visible index (excluding hidden collapsed children,
-    /// but accounting for expanded description boxes which are separate ListItems).
+    /// but accounting for section headings and expanded description boxes which are
+    /// separate ListItems).
     /// Also adjusts scroll offset to keep selected item visible.
     pub fn sync_list_state(&mut self) {
-        let hidden_indices = self.todo_list.build_hidden_indices();
+        let hidden_indices = self.hidden_indices();
         let mut visible_index = 0;
-        for i in 0..self.cursor_position {
+        for i in 0..=self.cursor_position {
             if hidden_indices.contains(&i) {
                 continue;
             }
+            if self.section_header(i, &hidden_indices).is_some() {
+                visible_index += 1;
+            }
+            if i == self.cursor_position {
+                break;
+            }
             visible_index += 1;
             // Expanded descriptions render as a separate ListItem
             if !self.todo_list.items[i].collapsed && self.todo_list.items[i].description.is_some() {
@@ -213,8 +310,17 @@ impl AppState {
         if date > self.today {
             return Ok(());
         }
-        self.todo_list = load_todos_for_viewing(date)?;
-        self.viewing_date = date;
+        let list = self.store.load_todos_for_viewing(date)?;
+        self.show_list(list, date);
+        Ok(())
+    }
+
+    /// Replace the displayed list and reset per-list UI state.
+    fn show_list(&mut self, list: TodoList, viewing_date: NaiveDate) {
+        self.todo_list = list;
+        self.refresh_blockers();
+        self.refresh_tracked_time();
+        self.viewing_date = viewing_date;
         self.cursor_position = 0;
         self.undo_stack.clear();
         self.unsaved_changes = false;
@@ -224,6 +330,141 @@ impl AppState {
         self.is_creating_new_item = false;
         self.insert_above = false;
         self.sync_list_state();
+    }
+
+    /// Show today's list (`None`) or the project list `name`.
+    pub fn switch_to_list(&mut self, name: Option<&str>) -> Result<()> {
+        match name {
+            Some(name) => {
+                self.today = Local::now().date_naive();
+                let list = self.store.load_named_list(name)?;
+                self.show_list(list, self.today);
+                Ok(())
+            }
+            None => self.navigate_to_today(),
+        }
+    }
+
+    /// Heading row shown above the item at `idx` while the items in `hidden` are not shown:
+    /// the heading being typed for the selected item, or the item's section heading when it
+    /// starts a section.
+    pub fn section_header(&self, idx: usize, hidden: &HashSet<usize>) -> Option<String> {
+        if self.mode == Mode::Section && idx == self.cursor_position {
+            return Some(self.edit_buffer.clone());
+        }
+        self.todo_list.section_heading(idx, hidden).map(str::to_string)
+    }
+
+    /// Start typing a heading above the selected item, prefilled with the one it has.
+    pub fn start_section_edit(&mut self) {
+        let hidden = self.hidden_indices();
+        self.edit_buffer = self
+            .todo_list
+            .section_heading(self.cursor_position, &hidden)
+            .unwrap_or_default()
+            .to_string();
+        self.edit_cursor_pos = self.edit_buffer.len();
+        self.mode = Mode::Section;
+        self.sync_list_state();
+    }
+
+    /// File the selected item and the rest of its section under the typed heading. An
+    /// empty heading removes it.
+    pub fn confirm_section_edit(&mut self) {
+        let heading = self.edit_buffer.trim();
+        let heading = (!heading.is_empty()).then(|| heading.to_string());
+        let hidden = self.hidden_indices();
+        let current = self.todo_list.section_heading(self.cursor_position, &hidden);
+        if heading.as_deref() != current {
+            self.save_undo();
+            self.todo_list.set_section(self.cursor_position, heading);
+            self.unsaved_changes = true;
+        }
+        self.close_section_edit();
+    }
+
+    pub fn close_section_edit(&mut self) {
+        self.edit_buffer.clear();
+        self.edit_cursor_pos = 0;
+        self.mode = Mode::Navigate;
+        self.sync_list_state();
+    }
+
+    pub fn open_list_picker(&mut self, moving: bool) -> Result<()> {
+        let names = self.store.list_names()?;
+        let selected = self
+            .todo_list
+            .name
+            .as_ref()
+            .and_then(|current| names.iter().position(|n| n == current))
+            .map_or(0, |i| i + 1);
+        self.list_picker = Some(ListPicker {
+            names,
+            selected,
+            input: None,
+            moving,
+        });
+        self.mode = Mode::ListPicker;
+        Ok(())
+    }
+
+    pub fn close_list_picker(&mut self) {
+        self.list_picker = None;
+        self.mode = Mode::Navigate;
+    }
+
+    /// Act on the picker's selection, or on the typed name of a new list.
+    pub fn confirm_list_picker(&mut self) -> Result<()> {
+        let Some(picker) = self.list_picker.take() else {
+            return Ok(());
+        };
+        self.mode = Mode::Navigate;
+
+        let target = match picker.input {
+            Some(ref input) => {
+                if let Err(e) = self.store.create_named_list(input) {
+                    self.set_status_message(e.to_string());
+                    return Ok(());
+                }
+                Some(input.trim().to_string())
+            }
+            None => picker.selected_name().map(str::to_string),
+        };
+
+        if picker.moving {
+            self.move_current_item_to(target.as_deref())
+        } else {
+            self.switch_to_list(target.as_deref())
+        }
+    }
+
+    /// Move the selected item and its subtasks to the end of another list.
+    pub fn move_current_item_to(&mut self, name: Option<&str>) -> Result<()> {
+        if self.selected_item().is_none() {
+            return Ok(());
+        }
+        let is_current = match name {
+            Some(name) => self.todo_list.name.as_deref() == Some(name),
+            None => self.todo_list.name.is_none() && self.todo_list.date == self.today,
+        };
+        if is_current {
+            self.set_status_message("Already on that list".to_string());
+            return Ok(());
+        }
+
+        let mut target = self.store.load_list(name)?;
+        let moved = self
+            .store
+            .move_to_list(&mut self.todo_list, self.cursor_position, &mut target)?;
+
+        // Undo would restore the items here while they also live on the target list
+        self.undo_stack.clear();
+        self.unsaved_changes = false;
+        self.last_save_time = Some(Instant::now());
+        self.clamp_cursor();
+        self.refresh_blockers();
+        self.sync_list_state();
+        self.set_status_message(format!("Moved {moved} item(s) to {}", target.title()));
         Ok(())
     }
 
@@ -251,7 +492,10 @@ impl AppState {
     }
 
     pub fn undo(&mut self) -> bool {
-        if let Some((list, cursor)) = self.undo_stack.pop() {
+        if let Some((mut list, cursor)) = self.undo_stack.pop() {
+            // Undo brings back items, not the stored revision they were last saved against
+            list.revision = self.todo_list.revision;
+            list.base_items = std::mem::take(&mut self.todo_list.base_items);
             self.todo_list = list;
             self.cursor_position = cursor;
             self.unsaved_changes = true;
@@ -294,6 +538,10 @@ impl AppState {
         if index >= self.todo_list.items.len() {
             return false;
         }
+        if let Some(ref tag) = self.tag_filter
+            && !self.todo_list.matches_tag_filter(index, tag) {
+                return true;
+            }
         let mut current_indent = self.todo_list.items[index].indent_level;
         if current_indent == 0 {
             return false;
@@ -313,6 +561,27 @@ impl AppState {
         false
     }
 
+    /// Cycle the tag filter through the tags used in the current list, then back to none.
+    pub fn cycle_tag_filter(&mut self) {
+        let tags = self.todo_list.all_tags();
+        let next_index = match self.tag_filter {
+            Some(ref current) => tags
+                .iter()
+                .position(|t| t.eq_ignore_ascii_case(current))
+                .map(|i| i + 1),
+            None => Some(0),
+        };
+        self.tag_filter = next_index.and_then(|i| tags.get(i).cloned());
+
+        if self.is_item_hidden(self.cursor_position)
+            && let Some(first_visible) =
+                (0..self.todo_list.items.len()).find(|&i| !self.is_item_hidden(i))
+            {
+                self.cursor_position = first_visible;
+            }
+        self.sync_list_state();
+    }
+
     pub fn selected_item(&self) -> Option<&TodoItem> {
         self.todo_list.items.get(self.cursor_position)
     }
@@ -378,14 +647,77 @@ impl AppState {
     /// Reload the todo list from the database.
     /// Used when external changes are detected (e.g., from API server).
     pub fn reload_from_database(&mut self) -> Result<()> {
-        let date = self.todo_list.date;
-        let new_list = load_todo_list(date)?;
+        let new_list = match self.todo_list.name {
+            Some(ref name) => self.store.load_named_list(name)?,
+            None => self.store.load_todo_list(self.todo_list.date)?,
+        };
         self.todo_list = new_list;
+        self.refresh_blockers();
+        self.refresh_tracked_time();
         self.clamp_cursor();
         self.unsaved_changes = false;
         Ok(())
     }
 
+    /// Merge edits made to the markdown file of `date` in an editor. Returns whether the
+    /// stored list changed; a conflict or a file that cannot be read is shown in the status bar.
+    pub fn sync_daily_file(&mut self, date: NaiveDate) -> bool {
+        match self.store.sync_daily_file(date) {
+            Ok(SyncOutcome::Merged) => true,
+            Ok(SyncOutcome::Unchanged) => false,
+            Ok(SyncOutcome::Conflict) => {
+                self.set_status_message(conflict_message(date));
+                false
+            }
+            Err(e) => {
+                let file = format!("dailies/{}.md", date.format("%Y-%m-%d"));
+                self.set_status_message(format!("Could not merge {file}: {e}"));
+                false
+            }
+        }
+    }
+
+    /// Reload the list when a save lost to an overlapping edit made elsewhere (API, MCP or
+    /// another window). Any other error is passed through.
+    pub fn recover_from_save_conflict(&mut self, result: Result<()>) -> Result<()> {
+        match result {
+            Err(e) if e.is::<SaveConflict>() => {
+                self.reload_from_database()?;
+                self.set_status_message(
+                    "Changed elsewhere at the same time; your last edit was not saved".to_string(),
+                );
+                Ok(())
+            }
+            other => other,
+        }
+    }
+
+    /// Re-check which blockers on other days are still open.
+    pub fn refresh_blockers(&mut self) {
+        self.open_external_blockers =
+            self.store.load_open_blockers(&self.todo_list.external_blocker_ids()).unwrap_or_default();
+    }
+
+    /// Reload logged time for the items on the current list.
+    pub fn refresh_tracked_time(&mut self) {
+        let origins: Vec<Uuid> = self.todo_list.items.iter().map(|item| item.origin()).collect();
+        self.tracked_time = self.store.load_tracked_time(&origins).unwrap_or_default();
+    }
+
+    /// Time logged so far on an in-progress item, including the running interval.
+    pub fn elapsed(&self, item: &TodoItem) -> Option<chrono::Duration> {
+        if item.state != TodoState::InProgress {
+            return None;
+        }
+        self.tracked_time
+            .get(&item.origin())
+            .map(|tracked| tracked.total(chrono::Utc::now()))
+    }
+
+    pub fn is_blocked(&self, index: usize) -> bool {
+        self.todo_list.is_blocked(index, &self.open_external_blockers)
+    }
+
     pub fn open_plugin_menu(&mut self) {
         let plugins = self.plugin_registry.list();
         self.plugin_state = Some(PluginSubState::Selecting {
@@ -455,6 +787,7 @@ impl AppState {
             self.save_undo();
             if let Some(item) = self.selected_item_mut() {
                 item.toggle_state();
+                self.todo_list.sync_parent_states(self.cursor_position);
                 self.unsaved_changes = true;
                 return true;
             }
@@ -469,6 +802,7 @@ impl AppState {
             self.save_undo();
             if let Some(item) = self.selected_item_mut() {
                 item.cycle_state();
+                self.todo_list.sync_parent_states(self.cursor_position);
                 self.unsaved_changes = true;
                 return true;
             }
@@ -562,6 +896,147 @@ impl AppState {
         // Note: we keep pending_rollover so user can re-trigger with R key
     }
 
+    /// Open the scheduled view, listing items that are waiting for their start date.
+    /// Returns false if nothing is scheduled.
+    pub fn open_scheduled_view(&mut self) -> Result<bool> {
+        self.scheduled_items = self.store.load_scheduled_todos(None, None)?;
+        if self.scheduled_items.is_empty() {
+            return Ok(false);
+        }
+        self.mode = Mode::Scheduled;
+        Ok(true)
+    }
+
+    pub fn close_scheduled_view(&mut self) {
+        self.scheduled_items.clear();
+        self.mode = Mode::Navigate;
+    }
+
+    /// Open the trash overlay. Returns false if the trash is empty.
+    pub fn open_trash_view(&mut self) -> Result<bool> {
+        let entries = self.store.load_trash()?;
+        if entries.is_empty() {
+            return Ok(false);
+        }
+        self.trash_view = Some(TrashView { entries, selected: 0 });
+        self.mode = Mode::Trash;
+        Ok(true)
+    }
+
+    pub fn close_trash_view(&mut self) {
+        self.trash_view = None;
+        self.mode = Mode::Navigate;
+    }
+
+    /// Reload the trash after a change, closing the overlay once it is empty.
+    fn refresh_trash_view(&mut self) -> Result<()> {
+        let entries = self.store.load_trash()?;
+        match self.trash_view.as_mut() {
+            Some(view) if !entries.is_empty() => {
+                view.entries = entries;
+                view.selected = view.selected.min(view.rows().len().saturating_sub(1));
+            }
+            _ => self.close_trash_view(),
+        }
+        Ok(())
+    }
+
+    /// Put the selected todo back where it was deleted from. When that is the list on
+    /// screen, it is reloaded with the cursor on the restored item.
+    pub fn restore_selected_trash(&mut self) -> Result<()> {
+        let Some(id) = self.trash_view.as_ref().and_then(TrashView::selected_id) else {
+            return Ok(());
+        };
+        let Some((list, restored)) = self.store.restore_from_trash(id)? else {
+            return self.refresh_trash_view();
+        };
+
+        let on_screen = !self.is_readonly()
+            && self.todo_list.name == list.name
+            && (list.name.is_some() || self.todo_list.date == list.date);
+        self.set_status_message(format!("Restored {} item(s) to {}", restored.len(), list.title()));
+        if on_screen {
+            self.todo_list = list;
+            self.cursor_position = restored.start;
+            // Undo would go back to a version saved before the restore
+            self.undo_stack.clear();
+            self.refresh_blockers();
+            self.sync_list_state();
+        }
+        self.refresh_trash_view()
+    }
+
+    /// Remove the selected todo and the subtasks deleted with it for good.
+    pub fn purge_selected_trash(&mut self) -> Result<()> {
+        let Some(view) = self.trash_view.as_ref() else {
+            return Ok(());
+        };
+        let Some(id) = view.selected_id() else {
+            return Ok(());
+        };
+        let ids: Vec<Uuid> = subtree(&view.entries, id).iter().map(|entry| entry.item.id).collect();
+        let purged = self.store.purge_trash(&ids)?;
+        self.set_status_message(format!("Purged {purged} item(s)"));
+        self.refresh_trash_view()
+    }
+
+    /// Open the detail view for the selected item, loading its comment thread.
+    /// Returns false if no item is selected.
+    pub fn open_detail_view(&mut self) -> Result<bool> {
+        let Some(id) = self.selected_item().map(|item| item.id) else {
+            return Ok(false);
+        };
+        self.detail_comments = self.store.load_comments(id)?.unwrap_or_default();
+        self.comment_input = None;
+        self.detail_history = None;
+        self.mode = Mode::Detail;
+        Ok(true)
+    }
+
+    pub fn close_detail_view(&mut self) {
+        self.detail_comments.clear();
+        self.comment_input = None;
+        self.detail_history = None;
+        self.mode = Mode::Navigate;
+    }
+
+    /// Switch the detail view between the comment thread and the item's change history.
+    pub fn toggle_detail_history(&mut self) -> Result<()> {
+        if self.detail_history.take().is_some() {
+            return Ok(());
+        }
+        let Some(id) = self.selected_item().map(|item| item.id) else {
+            return Ok(());
+        };
+        let filter = EventFilter {
+            todo_id: Some(id),
+            ..Default::default()
+        };
+        self.detail_history = Some(self.store.load_events(&filter)?);
+        Ok(())
+    }
+
+    /// Append the typed comment to the selected item's thread.
+    pub fn submit_comment(&mut self) -> Result<()> {
+        let Some(body) = self.comment_input.take() else {
+            return Ok(());
+        };
+        let body = body.trim();
+        let Some(id) = self.selected_item().map(|item| item.id) else {
+            return Ok(());
+        };
+        if body.is_empty() {
+            return Ok(());
+        }
+
+        let author = std::env::var("USER").unwrap_or_else(|_| "me".to_string());
+        match self.store.add_comment(id, &author, body)? {
+            Some(comment) => self.detail_comments.push(comment),
+            None => self.set_status_message("Save the todo before commenting".to_string()),
+        }
+        Ok(())
+    }
+
     /// Check if there's pending rollover data available
     pub fn has_pending_rollover(&self) -> bool {
         self.pending_rollover.is_some()
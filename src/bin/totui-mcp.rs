@@ -1,20 +1,34 @@
 use anyhow::Result;
+use clap::Parser;
 use rmcp::{ServiceExt, transport::stdio};
-use std::env;
-use to_tui::mcp::TodoMcpServer;
-use tracing::info;
+use std::path::PathBuf;
+use std::sync::Arc;
+use to_tui::mcp::{TodoMcpServer, install_config};
+use to_tui::storage::SqliteStore;
+use to_tui::storage::events::{EventSource, set_event_source};
+use to_tui::utils::paths::set_data_location;
+use tracing::{info, warn};
 use tracing_subscriber::{EnvFilter, fmt};
 
-const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// MCP server for to-tui todos, speaking over stdio
+#[derive(Parser, Debug)]
+#[command(name = "totui-mcp")]
+#[command(version)]
+struct Args {
+    /// Keep todos and config here instead of $TOTUI_HOME or the default
+    #[arg(long, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Use a separate set of todos and config inside the data directory
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Check for version flag before anything else
-    let args: Vec<String> = env::args().collect();
-    if args.iter().any(|a| a == "--version" || a == "-V") {
-        println!("totui-mcp {}", VERSION);
-        return Ok(());
-    }
+    let args = Args::parse();
+    set_data_location(args.data_dir, args.profile.as_deref())?;
+
     fmt()
         .with_env_filter(
             EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
@@ -24,8 +38,13 @@ async fn main() -> Result<()> {
         .init();
 
     info!("Starting todo-mcp server");
+    set_event_source(EventSource::Mcp);
+
+    if let Err(e) = install_config() {
+        warn!(error = %e, "Failed to load config, using built-in states and clean markdown");
+    }
 
-    let server = TodoMcpServer::new();
+    let server = TodoMcpServer::new(Arc::new(SqliteStore));
     let service = server.serve(stdio()).await?;
 
     info!("Server ready, waiting for requests...");
@@ -1,8 +1,24 @@
+use crate::storage;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 /// Default port for the API server
 pub const DEFAULT_API_PORT: u16 = 48372;
 
+/// Port of the API server for `profile`. Each profile gets its own port after
+/// `DEFAULT_API_PORT`, so the servers of different profiles can run side by side.
+pub fn default_api_port(profile: Option<&str>) -> u16 {
+    let Some(profile) = profile else {
+        return DEFAULT_API_PORT;
+    };
+    // FNV-1a, so a profile keeps its port across builds
+    let hash = profile
+        .trim()
+        .bytes()
+        .fold(0x811c_9dc5_u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193));
+    DEFAULT_API_PORT + 1 + (hash % 1000) as u16
+}
+
 #[derive(Parser, Debug)]
 #[command(name = "totui")]
 #[command(version)]
@@ -10,27 +26,139 @@ pub const DEFAULT_API_PORT: u16 = 48372;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
+
+    /// Keep todos, config and server files here instead of $TOTUI_HOME or the default
+    #[arg(long, global = true, value_name = "DIR")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Use a separate set of todos, config and server files inside the data directory
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     Add {
         task: String,
+
+        /// Add to this project list instead of today's list
+        #[arg(short, long)]
+        list: Option<String>,
     },
     Show {
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "list")]
         date: Option<String>,
+
+        /// Show this project list instead of a daily list
+        #[arg(short, long)]
+        list: Option<String>,
+
+        /// Only show todos carrying this tag (e.g. 'review' or '#review')
+        #[arg(short, long)]
+        tag: Option<String>,
+    },
+    /// Show the names of all project lists
+    Lists,
+    /// Search todos on every day, in the archive and in project lists
+    Search {
+        /// Words to look for in the content or description
+        #[arg(required = true)]
+        query: Vec<String>,
+
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value_t = storage::search::DEFAULT_SEARCH_LIMIT)]
+        limit: usize,
+    },
+    /// Show the history of changes, newest last
+    Log {
+        /// Maximum number of changes to show
+        #[arg(short = 'n', long, default_value_t = 50)]
+        limit: usize,
+
+        /// Only changes made from tui, cli, api, mcp, plugin or file
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Only changes on or after this day (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only changes to this todo, across the days it was carried over
+        #[arg(long)]
+        todo: Option<String>,
     },
     /// Import old markdown files into the archive
-    ImportArchive,
+    ImportArchive {
+        /// Only report what would be imported, skipped or conflicted
+        #[arg(long)]
+        dry_run: bool,
+
+        /// First day to import (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Last day to import (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// What to do with days that are already archived: skip them, or merge the file
+        /// into them by todo id
+        #[arg(long, default_value = "skip")]
+        policy: String,
+    },
+    /// Report lines of markdown files that were read loosely or not understood
+    Check {
+        /// Files to check. Defaults to every daily and project list file
+        files: Vec<PathBuf>,
+    },
+    /// Total the time spent in progress per task, tag and day
+    Report {
+        /// First day to include (YYYY-MM-DD). Defaults to --to
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Last day to include (YYYY-MM-DD). Defaults to today
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Browse, restore and purge deleted todos
+    Trash {
+        #[command(subcommand)]
+        command: Option<TrashCommand>,
+    },
+    /// Snapshot the database, daily files and project list files
+    Backup {
+        /// Show the snapshots instead of taking one
+        #[arg(short, long)]
+        list: bool,
+    },
+    /// Replace the database and files with a snapshot taken by 'backup'
+    Restore {
+        /// Name of the snapshot, or the start of it as shown by 'backup --list'
+        snapshot: String,
+
+        /// Restore without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Restore even while the TUI is open on this data directory. Its next save then
+        /// goes into the restored database
+        #[arg(long)]
+        force: bool,
+    },
+    /// Inspect and maintain the database
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
     /// Manage the API server
     Serve {
         #[command(subcommand)]
         command: Option<ServeCommand>,
 
-        /// Port to run the server on
-        #[arg(short, long, global = true, default_value_t = DEFAULT_API_PORT)]
-        port: u16,
+        /// Port to run the server on [default: 48372, or a port derived from --profile]
+        #[arg(short, long, global = true)]
+        port: Option<u16>,
     },
     /// Generate todos from external sources using plugins
     Generate {
@@ -50,6 +178,36 @@ pub enum Commands {
     },
 }
 
+#[derive(Subcommand, Debug, Clone)]
+pub enum DbCommand {
+    /// Apply pending schema migrations
+    Migrate {
+        /// Only show the schema version and pending migrations
+        #[arg(long)]
+        status: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TrashCommand {
+    /// Show deleted todos, most recently deleted first (default if no subcommand given)
+    List,
+    /// Put a deleted todo and the subtasks deleted with it back where they were
+    Restore {
+        /// Id of the todo, or the start of it as shown by 'trash list'
+        id: String,
+    },
+    /// Remove deleted todos for good. Without ids, only those past the retention window
+    Purge {
+        /// Ids of todos to purge, with the subtasks deleted along with them
+        ids: Vec<String>,
+
+        /// Empty the whole trash
+        #[arg(long, conflicts_with = "ids")]
+        all: bool,
+    },
+}
+
 #[derive(Subcommand, Debug, Clone)]
 pub enum ServeCommand {
     /// Start the API server (default if no subcommand given)
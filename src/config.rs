@@ -3,6 +3,10 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 
 use crate::keybindings::KeybindingsConfig;
+use crate::storage::backup::BackupConfig;
+use crate::storage::markdown::MarkdownConfig;
+use crate::storage::trash::TrashConfig;
+use crate::todo::StatesConfig;
 use crate::utils::paths::get_config_path;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +19,18 @@ pub struct Config {
 
     #[serde(default)]
     pub keybindings: KeybindingsConfig,
+
+    #[serde(default)]
+    pub states: StatesConfig,
+
+    #[serde(default)]
+    pub markdown: MarkdownConfig,
+
+    #[serde(default)]
+    pub trash: TrashConfig,
+
+    #[serde(default)]
+    pub backup: BackupConfig,
 }
 
 fn default_theme() -> String {
@@ -31,6 +47,10 @@ impl Default for Config {
             theme: default_theme(),
             timeoutlen: default_timeoutlen(),
             keybindings: KeybindingsConfig::default(),
+            states: StatesConfig::default(),
+            markdown: MarkdownConfig::default(),
+            trash: TrashConfig::default(),
+            backup: BackupConfig::default(),
         }
     }
 }
@@ -55,6 +75,7 @@ impl Config {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::todo::StateRegistry;
 
     #[test]
     fn test_default_config() {
@@ -78,4 +99,25 @@ mod tests {
         let config: Config = toml::from_str(toml_str).unwrap();
         assert_eq!(config.theme, "dark");
     }
+
+    #[test]
+    fn test_states_deserialization() {
+        let toml_str = r#"
+        [states]
+        cycle = [" ", "x", "~"]
+        auto_complete_parents = true
+
+        [[states.custom]]
+        marker = "~"
+        label = "waiting"
+        color = "magenta"
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.states.custom.len(), 1);
+        assert_eq!(config.states.custom[0].label, "waiting");
+        assert_eq!(config.states.cycle.as_ref().unwrap().len(), 3);
+        assert!(config.states.auto_complete_parents);
+        assert!(StateRegistry::from_config(&config.states).unwrap().auto_complete_parents());
+    }
 }
@@ -63,9 +63,28 @@ pub enum Action {
     // Rollover
     OpenRolloverModal,
 
+    // Scheduled view
+    OpenScheduledView,
+
+    // Trash
+    OpenTrash,
+
+    // Detail view
+    OpenDetailView,
+
+    // Sections
+    EditSection,
+
+    // Project lists
+    OpenListSwitcher,
+    MoveToList,
+
     // Clipboard
     Yank,
 
+    // Filtering
+    CycleTagFilter,
+
     // Edit mode specific
     EditCancel,
     EditConfirm,
@@ -112,7 +131,14 @@ impl fmt::Display for Action {
             Action::GoToToday => "go_to_today",
             Action::OpenPluginMenu => "open_plugin_menu",
             Action::OpenRolloverModal => "open_rollover_modal",
+            Action::OpenScheduledView => "open_scheduled_view",
+            Action::OpenTrash => "open_trash",
+            Action::OpenDetailView => "open_detail_view",
+            Action::EditSection => "edit_section",
+            Action::OpenListSwitcher => "open_list_switcher",
+            Action::MoveToList => "move_to_list",
             Action::Yank => "yank",
+            Action::CycleTagFilter => "cycle_tag_filter",
             Action::EditCancel => "edit_cancel",
             Action::EditConfirm => "edit_confirm",
             Action::EditBackspace => "edit_backspace",
@@ -163,7 +189,14 @@ impl FromStr for Action {
             "go_to_today" => Ok(Action::GoToToday),
             "open_plugin_menu" => Ok(Action::OpenPluginMenu),
             "open_rollover_modal" => Ok(Action::OpenRolloverModal),
+            "open_scheduled_view" => Ok(Action::OpenScheduledView),
+            "open_trash" => Ok(Action::OpenTrash),
+            "open_detail_view" => Ok(Action::OpenDetailView),
+            "edit_section" => Ok(Action::EditSection),
+            "open_list_switcher" => Ok(Action::OpenListSwitcher),
+            "move_to_list" => Ok(Action::MoveToList),
             "yank" => Ok(Action::Yank),
+            "cycle_tag_filter" => Ok(Action::CycleTagFilter),
             "edit_cancel" => Ok(Action::EditCancel),
             "edit_confirm" => Ok(Action::EditConfirm),
             "edit_backspace" => Ok(Action::EditBackspace),
@@ -599,7 +632,14 @@ fn default_navigate_bindings() -> HashMap<String, String> {
     m.insert("T".to_string(), "go_to_today".to_string());
     m.insert("p".to_string(), "open_plugin_menu".to_string());
     m.insert("R".to_string(), "open_rollover_modal".to_string());
+    m.insert("S".to_string(), "open_scheduled_view".to_string());
+    m.insert("X".to_string(), "open_trash".to_string());
+    m.insert("K".to_string(), "open_detail_view".to_string());
+    m.insert("H".to_string(), "edit_section".to_string());
+    m.insert("L".to_string(), "open_list_switcher".to_string());
+    m.insert("m".to_string(), "move_to_list".to_string());
     m.insert("y".to_string(), "yank".to_string());
+    m.insert("#".to_string(), "cycle_tag_filter".to_string());
 
     m
 }
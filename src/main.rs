@@ -10,50 +10,98 @@ mod todo;
 mod ui;
 mod utils;
 
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
 use chrono::Local;
 use clap::Parser;
-use cli::{Cli, Commands, DEFAULT_API_PORT, ServeCommand};
+use cli::{Cli, Commands, DbCommand, ServeCommand, TrashCommand, default_api_port};
 use config::Config;
 use keybindings::KeybindingCache;
 use std::env;
 use std::fs;
 use std::io::{Read, Write};
 use std::net::TcpStream;
+use std::path::Path;
 use std::process::{Command, Stdio};
+use std::sync::Arc;
 use std::time::Duration;
-use storage::{UiCache, find_rollover_candidates, save_todo_list};
-use storage::file::{file_exists, load_todo_list};
+use storage::events::{EventFilter, EventSource, set_event_source};
+use storage::{SqliteStore, TodoStore, UiCache, find_rollover_candidates, save_todo_list};
+use storage::file::{
+    list_names, load_list, load_named_list, load_todo_list, schedule_future_items,
+};
 use ui::theme::Theme;
-use utils::paths::get_pid_file_path;
+use utils::paths::{get_pid_file_path, get_tui_lock_path};
 
 /// Load today's todo list without prompting for rollover.
-/// Creates an empty list if no existing todos are found.
+/// Creates an empty list if no existing todos are found; scheduled items starting
+/// today are included either way. Warns when the markdown file conflicts with the database.
 fn load_today_list() -> Result<todo::TodoList> {
-    let today = Local::now().date_naive();
-    if file_exists(today)? {
-        load_todo_list(today)
-    } else {
-        Ok(todo::TodoList::new(today, utils::paths::get_daily_file_path(today)?))
+    let list = load_todo_list(Local::now().date_naive())?;
+    if list.file_conflict {
+        eprintln!("Warning: {}", storage::sync::conflict_message(list.date));
     }
+    Ok(list)
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    utils::paths::set_data_location(cli.data_dir.clone(), cli.profile.as_deref())?;
     let config = Config::load()?;
+    todo::StateRegistry::from_config(&config.states)?.install();
+    storage::markdown::install_markdown_config(&config.markdown);
+    storage::trash::install_trash_config(&config.trash);
+    storage::backup::install_backup_config(&config.backup);
+    let api_port = default_api_port(cli.profile.as_deref());
 
     match cli.command {
-        Some(Commands::Add { task }) => {
-            handle_add(task)?;
+        Some(Commands::Add { task, list }) => {
+            handle_add(task, list)?;
+        }
+        Some(Commands::Show { date, list, tag }) => {
+            handle_show(date, list, tag)?;
+        }
+        Some(Commands::Lists) => {
+            handle_lists()?;
+        }
+        Some(Commands::Search { query, limit }) => {
+            handle_search(&query.join(" "), limit)?;
+        }
+        Some(Commands::Log {
+            limit,
+            source,
+            since,
+            todo,
+        }) => {
+            handle_log(limit, source, since, todo)?;
         }
-        Some(Commands::Show { date }) => {
-            handle_show(date)?;
+        Some(Commands::ImportArchive {
+            dry_run,
+            since,
+            until,
+            policy,
+        }) => {
+            handle_import_archive(dry_run, since, until, &policy)?;
+        }
+        Some(Commands::Check { files }) => {
+            handle_check(files)?;
+        }
+        Some(Commands::Report { from, to }) => {
+            handle_report(from, to)?;
+        }
+        Some(Commands::Trash { command }) => {
+            handle_trash_command(command.unwrap_or(TrashCommand::List))?;
         }
-        Some(Commands::ImportArchive) => {
-            handle_import_archive()?;
+        Some(Commands::Backup { list }) => {
+            handle_backup(list)?;
+        }
+        Some(Commands::Restore { snapshot, yes, force }) => {
+            handle_restore(&snapshot, yes, force, api_port)?;
+        }
+        Some(Commands::Db { command }) => {
+            handle_db_command(command)?;
         }
         Some(Commands::Serve { command, port }) => {
-            handle_serve_command(command, port)?;
+            handle_serve_command(command, port.unwrap_or(api_port))?;
         }
         Some(Commands::Generate {
             generator,
@@ -64,9 +112,13 @@ fn main() -> Result<()> {
             handle_generate(generator, input, list, yes)?;
         }
         None => {
-            ensure_server_running(DEFAULT_API_PORT)?;
+            let _tui_lock = lock_tui()?;
+            ensure_server_running(api_port)?;
+            set_event_source(EventSource::Tui);
 
-            let list = load_today_list()?;
+            let store: Arc<dyn TodoStore> = Arc::new(SqliteStore);
+            store.purge_expired_trash()?;
+            let list = store.load_todo_list(Local::now().date_naive())?;
 
             // Load UI cache for restoring cursor position
             let ui_cache = UiCache::load().ok();
@@ -75,6 +127,7 @@ fn main() -> Result<()> {
             let keybindings = KeybindingCache::from_config(&config.keybindings);
             let plugin_registry = plugin::PluginRegistry::new();
             let mut state = app::AppState::new(
+                store.clone(),
                 list,
                 theme,
                 keybindings,
@@ -84,7 +137,7 @@ fn main() -> Result<()> {
             );
 
             // Check for rollover candidates and show modal on startup if found
-            if let Ok(Some((source_date, items))) = find_rollover_candidates() {
+            if let Ok(Some((source_date, items))) = find_rollover_candidates(store.as_ref()) {
                 state.open_rollover_modal(source_date, items);
             }
 
@@ -95,6 +148,179 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+fn handle_db_command(command: DbCommand) -> Result<()> {
+    use storage::database::get_connection;
+    use storage::database::migrate_with_snapshot;
+    use storage::migrations::{latest_version, pending_migrations, schema_version};
+
+    let mut conn = get_connection()?;
+    match command {
+        DbCommand::Migrate { status: true } => {
+            let current = schema_version(&conn)?;
+            let latest = latest_version();
+            println!("Schema version: {current} (this build: {latest})");
+            if current > latest {
+                println!("Database was written by a newer totui; upgrade before using it");
+                return Ok(());
+            }
+            let pending = pending_migrations(&conn)?;
+            if pending.is_empty() {
+                println!("Up to date");
+            } else {
+                println!("Pending migrations:");
+                for migration in pending {
+                    println!("  {:>3}  {}", migration.version, migration.description);
+                }
+            }
+        }
+        DbCommand::Migrate { status: false } => {
+            let applied = migrate_with_snapshot(&mut conn)?;
+            if applied.is_empty() {
+                println!("Up to date (schema version {})", latest_version());
+            }
+            for migration in applied {
+                println!("✓ Applied {}: {}", migration.version, migration.description);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_backup(list: bool) -> Result<()> {
+    use storage::backup::{SnapshotReason, create_snapshot, get_backups_dir, list_snapshots};
+
+    if !list {
+        let snapshot = create_snapshot(SnapshotReason::Manual)?;
+        println!("✓ Saved snapshot {} to {}", snapshot.name, snapshot.path.display());
+        return Ok(());
+    }
+
+    let snapshots = list_snapshots()?;
+    if snapshots.is_empty() {
+        println!("No snapshots in {}", get_backups_dir()?.display());
+        return Ok(());
+    }
+    println!("\n💾 {} snapshot(s) in {}\n", snapshots.len(), get_backups_dir()?.display());
+    for snapshot in snapshots {
+        println!(
+            "{:<30} {:>8.1} MB  {}",
+            snapshot.name,
+            snapshot.size() as f64 / 1_000_000.0,
+            snapshot.reason.map(SnapshotReason::description).unwrap_or_default(),
+        );
+    }
+    println!();
+    Ok(())
+}
+
+fn handle_restore(name: &str, yes: bool, force: bool, api_port: u16) -> Result<()> {
+    use dialoguer::Confirm;
+    use storage::backup::{find_snapshot, restore_snapshot};
+
+    if is_server_running(api_port) {
+        return Err(anyhow!(
+            "The API server is running. Close the TUI and run 'totui serve stop' before restoring"
+        ));
+    }
+    // Held until the restore is done, so a TUI started meanwhile waits for it
+    let _tui_lock = match lock_out_tui()? {
+        Some(lock) => Some(lock),
+        None if force => None,
+        None => {
+            return Err(anyhow!(
+                "The TUI is open on this data directory. Close it before restoring, or pass --force"
+            ));
+        }
+    };
+
+    let snapshot = find_snapshot(name)?;
+    let prompt = format!(
+        "Replace all todos with snapshot {} from {}?",
+        snapshot.name,
+        snapshot.taken_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    if !yes && !Confirm::new().with_prompt(prompt).default(false).interact()? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let previous = restore_snapshot(&snapshot)?;
+    println!("✓ Restored snapshot {}", snapshot.name);
+    println!("  The replaced data was saved as {}", previous.name);
+    Ok(())
+}
+
+fn handle_trash_command(command: TrashCommand) -> Result<()> {
+    use storage::trash::{roots, subtree};
+
+    let store = SqliteStore;
+    let trash = store.load_trash()?;
+
+    // Ids are shown shortened, so accept any prefix that names exactly one deleted todo
+    let find = |prefix: &str| {
+        let prefix = prefix.trim().to_lowercase();
+        let mut matches = trash
+            .iter()
+            .filter(|entry| !prefix.is_empty() && entry.item.id.to_string().starts_with(&prefix));
+        match (matches.next(), matches.next()) {
+            (Some(entry), None) => Ok(entry.item.id),
+            (Some(_), Some(_)) => Err(anyhow!("'{prefix}' matches more than one deleted todo")),
+            (None, _) => Err(anyhow!("No deleted todo with id '{prefix}'")),
+        }
+    };
+
+    match command {
+        TrashCommand::List => {
+            let roots = roots(&trash);
+            if roots.is_empty() {
+                println!("The trash is empty");
+                return Ok(());
+            }
+            println!("\n🗑  {} deleted todo(s)\n", trash.len());
+            for (entry, subtasks) in roots {
+                let extra = match subtasks {
+                    0 => String::new(),
+                    n => format!(" (+{n} subtask{})", if n == 1 { "" } else { "s" }),
+                };
+                println!(
+                    "{}  {}  {:<12} {} {}{extra}",
+                    &entry.item.id.to_string()[..8],
+                    entry.deleted_at().with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                    entry.origin_label(),
+                    entry.item.state,
+                    entry.item.content_with_tags(),
+                );
+            }
+            println!();
+        }
+        TrashCommand::Restore { id } => {
+            let id = find(&id)?;
+            let Some((list, restored)) = store.restore_from_trash(id)? else {
+                return Err(anyhow!("No deleted todo with id '{id}'"));
+            };
+            println!("✓ Restored {} item(s) to {}", restored.len(), list.title());
+        }
+        TrashCommand::Purge { all: true, .. } => {
+            let purged = store.purge_trash_before(chrono::Utc::now())?;
+            println!("✓ Purged {purged} item(s)");
+        }
+        TrashCommand::Purge { ids, .. } if ids.is_empty() => {
+            let purged = store.purge_expired_trash()?;
+            println!("✓ Purged {purged} item(s) past the retention window");
+        }
+        TrashCommand::Purge { ids, .. } => {
+            let mut purge = Vec::new();
+            for id in ids {
+                let id = find(&id)?;
+                purge.extend(subtree(&trash, id).iter().map(|entry| entry.item.id));
+            }
+            let purged = store.purge_trash(&purge)?;
+            println!("✓ Purged {purged} item(s)");
+        }
+    }
+    Ok(())
+}
+
 fn handle_serve_command(command: Option<ServeCommand>, port: u16) -> Result<()> {
     match command.unwrap_or(ServeCommand::Start { daemon: false }) {
         ServeCommand::Start { daemon } => {
@@ -111,7 +337,8 @@ fn handle_serve_command(command: Option<ServeCommand>, port: u16) -> Result<()>
 }
 
 fn handle_serve_start(port: u16) -> Result<()> {
-    if is_server_running(port) {
+    if let Some(health) = server_health(port) {
+        check_serves_this_profile(port, &health)?;
         println!("Server is already running on port {port}");
         return Ok(());
     }
@@ -165,29 +392,57 @@ fn handle_serve_status(port: u16) -> Result<()> {
 }
 
 fn is_server_running(port: u16) -> bool {
+    server_health(port).is_some()
+}
+
+/// Body of the `/api/health` answer of the server on `port`, if one is running there.
+fn server_health(port: u16) -> Option<String> {
     let addr = format!("127.0.0.1:{port}");
-    match TcpStream::connect_timeout(&addr.parse().unwrap(), Duration::from_millis(500)) {
-        Ok(mut stream) => {
-            let request = format!(
-                "GET /api/health HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\nConnection: close\r\n\r\n"
-            );
-            if stream.write_all(request.as_bytes()).is_ok() {
-                let mut response = String::new();
-                let _ = stream.read_to_string(&mut response);
-                response.contains("200") || response.contains("ok")
-            } else {
-                false
-            }
-        }
-        Err(_) => false,
+    let mut stream = TcpStream::connect_timeout(&addr.parse().unwrap(), Duration::from_millis(500)).ok()?;
+    let request = format!(
+        "GET /api/health HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\nConnection: close\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes()).ok()?;
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response);
+    if !(response.contains("200") || response.contains("ok")) {
+        return None;
     }
+    let body = response.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+    Some(body.to_string())
+}
+
+/// Fail if the server that answered `health` on `port` serves another data directory,
+/// such as another profile's. Servers that do not say which directory they serve pass.
+fn check_serves_this_profile(port: u16, health: &str) -> Result<()> {
+    let Some(served) = serde_json::from_str::<serde_json::Value>(health)
+        .ok()
+        .and_then(|health| health["data_dir"].as_str().map(str::to_string))
+    else {
+        return Ok(());
+    };
+    let ours = utils::paths::get_to_tui_dir()?;
+    let same = |a: &Path, b: &Path| match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    };
+    if !same(Path::new(&served), &ours) {
+        return Err(anyhow!(
+            "The API server on port {port} serves {served}, not {}. Pick another port with --port",
+            ours.display()
+        ));
+    }
+    Ok(())
 }
 
 fn start_server_background(port: u16) -> Result<()> {
     let current_exe = env::current_exe()?;
 
+    // Hand the resolved directory down so the server uses the same profile
     let child = Command::new(&current_exe)
         .args(["serve", "start", "--port", &port.to_string(), "--daemon"])
+        .arg("--data-dir")
+        .arg(utils::paths::get_to_tui_dir()?)
         .stdin(Stdio::null())
         .stdout(Stdio::null())
         .stderr(Stdio::null())
@@ -207,13 +462,53 @@ fn start_server_background(port: u16) -> Result<()> {
 }
 
 fn ensure_server_running(port: u16) -> Result<()> {
-    if !is_server_running(port) {
-        println!("Starting API server on port {port}...");
-        start_server_background(port)?;
+    match server_health(port) {
+        Some(health) => {
+            // The TUI works without the server, so a port taken by another profile is no
+            // reason not to start
+            if let Err(e) = check_serves_this_profile(port, &health) {
+                eprintln!("Not using the API server: {e}");
+            }
+        }
+        None => {
+            println!("Starting API server on port {port}...");
+            start_server_background(port)?;
+        }
     }
     Ok(())
 }
 
+fn open_tui_lock() -> Result<fs::File> {
+    let lock_path = get_tui_lock_path()?;
+    if let Some(parent) = lock_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open {}", lock_path.display()))
+}
+
+/// Mark this process as a running TUI until the returned file is dropped. Any number of
+/// TUIs can hold the lock at once.
+fn lock_tui() -> Result<fs::File> {
+    let file = open_tui_lock()?;
+    file.lock_shared()?;
+    Ok(file)
+}
+
+/// Take the TUI lock for ourselves, or `None` while a TUI holds it.
+fn lock_out_tui() -> Result<Option<fs::File>> {
+    let file = open_tui_lock()?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(fs::TryLockError::WouldBlock) => Ok(None),
+        Err(fs::TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
 fn read_pid_file() -> Result<Option<u32>> {
     let pid_path = get_pid_file_path()?;
 
@@ -273,7 +568,8 @@ async fn run_server_foreground(port: u16) -> Result<()> {
         )
         .init();
 
-    let app = api::create_router();
+    set_event_source(EventSource::Api);
+    let app = api::create_router(Arc::new(SqliteStore));
     let addr = format!("0.0.0.0:{port}");
 
     tracing::info!("Starting server on {}", addr);
@@ -284,18 +580,127 @@ async fn run_server_foreground(port: u16) -> Result<()> {
     Ok(())
 }
 
-fn handle_add(task: String) -> Result<()> {
-    let mut list = load_today_list()?;
+fn handle_add(task: String, list_name: Option<String>) -> Result<()> {
+    let mut list = load_list(list_name.as_deref())?;
 
     list.add_item(task);
-    save_todo_list(&list)?;
+    let scheduled = schedule_future_items(&mut list)?;
+    save_todo_list(&mut list)?;
+
+    match (scheduled.first().and_then(|item| item.start_date), list_name) {
+        (Some(start), _) => println!("✓ Todo scheduled for {}", start.format("%Y-%m-%d")),
+        (None, Some(name)) => println!("✓ Todo added to {name}"),
+        (None, None) => println!("✓ Todo added successfully!"),
+    }
+
+    Ok(())
+}
+
+fn handle_lists() -> Result<()> {
+    let names = list_names()?;
+    if names.is_empty() {
+        println!("No project lists yet. Create one with: totui add --list <name> \"<task>\"");
+        return Ok(());
+    }
+    for name in names {
+        println!("{name}");
+    }
+    Ok(())
+}
 
-    println!("✓ Todo added successfully!");
+fn handle_search(query: &str, limit: usize) -> Result<()> {
+    let hits = storage::search::search_todos(query, limit)?;
+    if hits.is_empty() {
+        println!("No todos match \"{query}\"");
+        return Ok(());
+    }
 
+    println!("\n🔍 {} result(s) for \"{query}\"\n", hits.len());
+    for hit in hits {
+        let place = match (&hit.list, hit.date) {
+            (Some(list), _) => list.clone(),
+            (None, Some(date)) if hit.archived => format!("{date} (archived)"),
+            (None, Some(date)) => date.to_string(),
+            (None, None) => String::new(),
+        };
+        println!("{place:<22} [{}] {}", hit.state.to_char(), hit.content);
+        if hit.snippet.replace("**", "") != hit.content {
+            println!("{:<22} {}", "", hit.snippet);
+        }
+    }
+    println!();
     Ok(())
 }
 
-fn handle_show(date: Option<String>) -> Result<()> {
+fn handle_log(
+    limit: usize,
+    source: Option<String>,
+    since: Option<String>,
+    todo_id: Option<String>,
+) -> Result<()> {
+    let source = source
+        .map(|s| {
+            EventSource::parse(&s)
+                .ok_or_else(|| anyhow!("Unknown source '{s}'. Use tui, cli, api, mcp, plugin or file"))
+        })
+        .transpose()?;
+    let since = since
+        .map(|s| {
+            chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+                .map_err(|_| anyhow!("Invalid date format. Use YYYY-MM-DD"))
+        })
+        .transpose()?
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map(|dt| dt.with_timezone(&chrono::Utc));
+
+    let todo_id = todo_id
+        .map(|id| uuid::Uuid::parse_str(&id).map_err(|_| anyhow!("Invalid todo id '{id}'")))
+        .transpose()?;
+
+    let events = storage::events::load_events(&EventFilter {
+        source,
+        todo_id,
+        since,
+        limit: Some(limit),
+    })?;
+
+    if events.is_empty() {
+        println!("No changes recorded");
+        return Ok(());
+    }
+
+    for event in events {
+        println!(
+            "{}  {:<6} {:<8} {}  {}",
+            event.occurred_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            event.source,
+            event.kind.as_str(),
+            event.content,
+            event.describe(),
+        );
+    }
+    Ok(())
+}
+
+fn handle_show(date: Option<String>, list_name: Option<String>, tag: Option<String>) -> Result<()> {
+    if let Some(name) = list_name {
+        let list = load_named_list(&name)?;
+        let items: Vec<&todo::TodoItem> = list
+            .items
+            .iter()
+            .filter(|item| tag.as_ref().is_none_or(|tag| item.has_tag(tag)))
+            .collect();
+        if items.is_empty() {
+            println!("No todos in {name}!");
+            return Ok(());
+        }
+        println!("\n📁 {name}\n");
+        print_items(items.into_iter());
+        println!();
+        return Ok(());
+    }
+
     let (items, display_date, is_archived) = if let Some(date_str) = date {
         let parsed_date = chrono::NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
             .map_err(|_| anyhow!("Invalid date format. Use YYYY-MM-DD"))?;
@@ -314,6 +719,11 @@ fn handle_show(date: Option<String>) -> Result<()> {
         (list.items, date, false)
     };
 
+    let items: Vec<todo::TodoItem> = match tag {
+        Some(ref tag) => items.into_iter().filter(|item| item.has_tag(tag)).collect(),
+        None => items,
+    };
+
     if items.is_empty() {
         if is_archived {
             println!(
@@ -333,12 +743,111 @@ fn handle_show(date: Option<String>) -> Result<()> {
     };
     println!("\n{} - {}\n", label, display_date.format("%B %d, %Y"));
 
-    for (idx, item) in items.iter().enumerate() {
+    print_items(items.iter());
+
+    println!();
+
+    Ok(())
+}
+
+fn print_items<'a>(items: impl Iterator<Item = &'a todo::TodoItem>) {
+    let mut section = None;
+    for (idx, item) in items.enumerate() {
+        if item.section.is_some() && item.section != section {
+            section = item.section.clone();
+            println!("\n## {}", item.section.as_deref().unwrap_or_default());
+        }
         let indent = "  ".repeat(item.indent_level);
-        println!("{}{}. {} {}", indent, idx + 1, item.state, item.content);
+        println!(
+            "{}{}. {} {}",
+            indent,
+            idx + 1,
+            item.state,
+            item.editable_content()
+        );
     }
+}
 
-    println!();
+fn handle_report(from: Option<String>, to: Option<String>) -> Result<()> {
+    use std::collections::{BTreeMap, HashMap};
+    use storage::time_tracking::{format_duration, load_time_entries};
+
+    let parse_date = |date_str: &str| {
+        chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+            .map_err(|_| anyhow!("Invalid date format. Use YYYY-MM-DD"))
+    };
+    let to = match to {
+        Some(ref s) => parse_date(s)?,
+        None => Local::now().date_naive(),
+    };
+    let from = match from {
+        Some(ref s) => parse_date(s)?,
+        None => to,
+    };
+    if from > to {
+        return Err(anyhow!("--from must not be after --to"));
+    }
+
+    storage::database::init_database()?;
+
+    let now = chrono::Utc::now();
+    let mut by_task: HashMap<uuid::Uuid, chrono::Duration> = HashMap::new();
+    let mut by_day: BTreeMap<chrono::NaiveDate, chrono::Duration> = BTreeMap::new();
+    for entry in load_time_entries(from, to)? {
+        for (day, spent) in entry.split_by_day(from, to, now) {
+            *by_task.entry(entry.origin_id).or_default() += spent;
+            *by_day.entry(day).or_default() += spent;
+        }
+    }
+
+    let range = if from == to {
+        from.format("%B %d, %Y").to_string()
+    } else {
+        format!("{} - {}", from.format("%B %d, %Y"), to.format("%B %d, %Y"))
+    };
+
+    if by_task.is_empty() {
+        println!("No time tracked for {range}");
+        return Ok(());
+    }
+
+    // Describe each task by its most recent occurrence
+    let mut tasks = Vec::new();
+    let mut by_tag: BTreeMap<String, chrono::Duration> = BTreeMap::new();
+    for (origin, spent) in by_task {
+        let latest = storage::database::load_todo_history(origin)?.pop();
+        let (title, tags) = match latest {
+            Some((_, item)) => (item.content_with_tags(), item.tags),
+            None => ("(deleted todo)".to_string(), Vec::new()),
+        };
+        for tag in tags {
+            *by_tag.entry(tag.to_lowercase()).or_default() += spent;
+        }
+        tasks.push((spent, title));
+    }
+    tasks.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    println!("\n⏱  Time Report - {range}\n");
+
+    println!("By task:");
+    for (spent, title) in &tasks {
+        println!("  {:>8}  {}", format_duration(*spent), title);
+    }
+
+    if !by_tag.is_empty() {
+        println!("\nBy tag:");
+        for (tag, spent) in &by_tag {
+            println!("  {:>8}  #{}", format_duration(*spent), tag);
+        }
+    }
+
+    println!("\nBy day:");
+    for (day, spent) in &by_day {
+        println!("  {:>8}  {}", format_duration(*spent), day.format("%a %b %d"));
+    }
+
+    let total: chrono::Duration = by_day.values().copied().sum();
+    println!("\nTotal: {}\n", format_duration(total));
 
     Ok(())
 }
@@ -454,13 +963,14 @@ fn handle_generate(
 }
 
 fn add_items_to_today(items: Vec<todo::TodoItem>) -> Result<()> {
+    set_event_source(EventSource::Plugin);
     let mut list = load_today_list()?;
 
     for item in items {
         list.items.push(item);
     }
 
-    save_todo_list(&list)?;
+    save_todo_list(&mut list)?;
     Ok(())
 }
 
@@ -486,11 +996,87 @@ fn select_items_interactive(items: &[todo::TodoItem]) -> Result<Vec<todo::TodoIt
         .collect())
 }
 
-fn handle_import_archive() -> Result<()> {
-    use storage::database::{archive_todos_for_date, init_database};
-    use storage::markdown::parse_todo_list;
+fn handle_check(files: Vec<std::path::PathBuf>) -> Result<()> {
+    use storage::markdown::parse_todo_list_with_warnings;
+    use utils::paths::{get_dailies_dir, get_lists_dir};
+
+    let files = if files.is_empty() {
+        let mut found = Vec::new();
+        for dir in [get_dailies_dir()?, get_lists_dir()?] {
+            if !dir.exists() {
+                continue;
+            }
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|e| e == "md") {
+                    found.push(path);
+                }
+            }
+        }
+        found.sort();
+        found
+    } else {
+        files
+    };
+
+    let today = Local::now().date_naive();
+    let mut problems = 0;
+    let mut files_with_problems = 0;
+    for path in &files {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {e}", path.display()))?;
+        // The date only names the list; it does not change how the file is read
+        let date = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+            .unwrap_or(today);
+        let (_, warnings) = parse_todo_list_with_warnings(&content, date, path.clone());
+        if !warnings.is_empty() {
+            files_with_problems += 1;
+        }
+        for warning in &warnings {
+            println!("{}:{}: {}", path.display(), warning.line, warning.message);
+        }
+        problems += warnings.len();
+    }
+
+    if problems == 0 {
+        println!("No problems found in {} files", files.len());
+    } else {
+        println!(
+            "\n{problems} problem{} in {files_with_problems} of {} files",
+            if problems == 1 { "" } else { "s" },
+            files.len()
+        );
+    }
+    Ok(())
+}
+
+fn handle_import_archive(
+    dry_run: bool,
+    since: Option<String>,
+    until: Option<String>,
+    policy: &str,
+) -> Result<()> {
+    use std::collections::HashMap;
+    use storage::database::{get_connection, init_database};
+    use storage::import::{ImportOutcome, ImportPolicy, apply_import, plan_import};
+    use storage::markdown::parse_todo_list_with_warnings;
     use utils::paths::get_dailies_dir;
 
+    let policy = ImportPolicy::parse(policy)
+        .ok_or_else(|| anyhow!("Unknown policy '{policy}'. Use skip or merge"))?;
+    let parse_day = |day: Option<String>| {
+        day.map(|s| {
+            chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+                .map_err(|_| anyhow!("Invalid date format. Use YYYY-MM-DD"))
+        })
+        .transpose()
+    };
+    let since = parse_day(since)?;
+    let until = parse_day(until)?;
+
     init_database()?;
 
     let dailies_dir = get_dailies_dir()?;
@@ -499,38 +1085,131 @@ fn handle_import_archive() -> Result<()> {
         return Ok(());
     }
 
-    let today = Local::now().date_naive();
-    let mut imported = 0;
+    let mut files: Vec<(chrono::NaiveDate, std::path::PathBuf)> = fs::read_dir(&dailies_dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "md"))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?;
+            let date = chrono::NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok()?;
+            Some((date, path))
+        })
+        .filter(|(date, _)| since.is_none_or(|since| *date >= since) && until.is_none_or(|until| *date <= until))
+        .collect();
+    files.sort();
 
-    for entry in std::fs::read_dir(&dailies_dir)? {
-        let entry = entry?;
-        let path = entry.path();
+    // Work out every file before writing anything, so a dry run reports exactly what a
+    // real run does and conflicts between files are caught up front
+    let today = Local::now().date_naive();
+    let mut plans = Vec::new();
+    let mut planned_todos = 0;
+    let (mut skipped, mut conflicted, mut failed) = (0, 0, 0);
+    let conn = get_connection()?;
+    let mut claimed = HashMap::new();
+    for (date, path) in files {
+        let filename = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if date >= today {
+            println!("Skipping {filename} (today or future)");
+            skipped += 1;
+            continue;
+        }
 
-        if path.extension().map(|e| e == "md").unwrap_or(false) {
-            let filename = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                println!("✗ {filename}: {err}");
+                failed += 1;
+                continue;
+            }
+        };
+        let (list, warnings) = parse_todo_list_with_warnings(&content, date, path.clone());
+        let plan = match plan_import(&conn, list, policy, &mut claimed) {
+            Ok(plan) => plan,
+            Err(err) => {
+                println!("✗ {filename}: {err:#}");
+                failed += 1;
+                continue;
+            }
+        };
 
-            if let Ok(date) = chrono::NaiveDate::parse_from_str(filename, "%Y-%m-%d") {
-                if date >= today {
-                    println!("Skipping {filename} (today or future)");
-                    continue;
+        match &plan.outcome {
+            ImportOutcome::Import { todos } => {
+                println!("{filename}: {todos} todo(s) to archive");
+                planned_todos += todos;
+            }
+            ImportOutcome::Merge {
+                added: 0,
+                updated: 0,
+                ..
+            } => {
+                println!("Skipping {filename} (already archived, nothing changed)");
+                skipped += 1;
+            }
+            ImportOutcome::Merge {
+                added,
+                updated,
+                unchanged,
+            } => {
+                println!("{filename}: {added} new, {updated} changed and {unchanged} unchanged todo(s) to merge");
+                planned_todos += added + updated;
+            }
+            ImportOutcome::Skip(reason) => {
+                println!("Skipping {filename} ({reason})");
+                skipped += 1;
+            }
+            ImportOutcome::Conflict(conflicts) => {
+                println!("✗ {filename}: not imported, these todos are stored elsewhere:");
+                for conflict in conflicts {
+                    println!("    {conflict}");
                 }
+                conflicted += 1;
+            }
+        }
+        let writes = match plan.outcome {
+            ImportOutcome::Import { .. } => true,
+            ImportOutcome::Merge { added, updated, .. } => added + updated > 0,
+            _ => false,
+        };
+        // Files that are left alone anyway are not worth a closer look
+        if writes || matches!(plan.outcome, ImportOutcome::Conflict(_)) {
+            for warning in warnings {
+                println!("    {warning}");
+            }
+        }
+        if writes {
+            plans.push((filename, plan));
+        }
+    }
+    drop(conn);
 
-                let content = std::fs::read_to_string(&path)?;
-                let list = parse_todo_list(&content, date, path.clone())?;
-
-                if list.items.is_empty() {
-                    println!("Skipping {filename} (empty)");
-                    continue;
-                }
+    if dry_run {
+        println!(
+            "\nDry run, nothing was written: {} file(s) ({planned_todos} todos) to import, {skipped} skipped, {conflicted} conflicted, {failed} failed",
+            plans.len()
+        );
+        return Ok(());
+    }
 
-                storage::database::save_todo_list(&list)?;
-                let count = archive_todos_for_date(date)?;
-                println!("Imported {count} items from {filename}");
-                imported += count;
+    let (mut imported, mut imported_todos) = (0, 0);
+    if !plans.is_empty() {
+        let snapshot = storage::backup::create_snapshot(storage::backup::SnapshotReason::Import)?;
+        println!("\nSaved snapshot {} before importing", snapshot.name);
+    }
+    for (filename, plan) in plans {
+        match apply_import(plan) {
+            Ok(count) => {
+                imported += 1;
+                imported_todos += count;
+            }
+            Err(err) => {
+                println!("✗ {filename}: {err:#}");
+                failed += 1;
             }
         }
     }
 
-    println!("\nTotal: {imported} items imported to archive");
+    println!(
+        "\nImported {imported} file(s) ({imported_todos} todos) to the archive, {skipped} skipped, {conflicted} conflicted, {failed} failed"
+    );
     Ok(())
 }
@@ -0,0 +1,43 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+
+use crate::storage::backup::{BackupConfig, install_backup_config};
+use crate::storage::markdown::{MarkdownConfig, install_markdown_config};
+use crate::storage::trash::{TrashConfig, install_trash_config};
+use crate::todo::{StateRegistry, StatesConfig};
+use crate::utils::paths::get_config_path;
+
+/// The parts of `config.toml` the MCP server cares about. The full `Config` lives in the
+/// `totui` binary, so only the sections needed here are read.
+#[derive(Debug, Default, Deserialize)]
+struct McpConfig {
+    #[serde(default)]
+    states: StatesConfig,
+    #[serde(default)]
+    markdown: MarkdownConfig,
+    #[serde(default)]
+    trash: TrashConfig,
+    #[serde(default)]
+    backup: BackupConfig,
+}
+
+/// Load `[states]`, `[markdown]`, `[trash]` and `[backup]` from the shared config file and install them.
+pub fn install_config() -> Result<()> {
+    let config_path = get_config_path()?;
+
+    let config = if config_path.exists() {
+        let content = fs::read_to_string(&config_path)
+            .with_context(|| format!("Failed to read config: {}", config_path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config: {}", config_path.display()))?
+    } else {
+        McpConfig::default()
+    };
+
+    install_markdown_config(&config.markdown);
+    install_trash_config(&config.trash);
+    install_backup_config(&config.backup);
+    StateRegistry::from_config(&config.states)?.install();
+    Ok(())
+}
@@ -1,6 +1,9 @@
 use schemars::JsonSchema;
 use serde::Serialize;
 
+use crate::storage::database::SaveConflict;
+use crate::todo::StateRegistry;
+
 #[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct McpErrorDetail {
     pub code: String,
@@ -34,10 +37,10 @@ impl McpErrorDetail {
             code: "INVALID_STATE".to_string(),
             message: message.into(),
             retryable: true,
-            suggestion: Some(
-                "Valid states: ' ' (empty), 'x' (done), '?' (question), '!' (important)"
-                    .to_string(),
-            ),
+            suggestion: Some(format!(
+                "Valid states: {}",
+                StateRegistry::global().describe()
+            )),
         }
     }
 
@@ -50,6 +53,18 @@ impl McpErrorDetail {
         }
     }
 
+    /// Another client saved the same list in between and the edits could not be combined.
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self {
+            code: "CONFLICT".to_string(),
+            message: message.into(),
+            retryable: true,
+            suggestion: Some(
+                "Another client changed the same todos at the same time. Retry the call; it will see their changes".to_string(),
+            ),
+        }
+    }
+
     pub fn storage_error(message: impl Into<String>) -> Self {
         Self {
             code: "STORAGE_ERROR".to_string(),
@@ -65,8 +80,14 @@ pub trait IntoMcpError<T> {
     fn into_mcp_storage_error(self) -> Result<T, McpErrorDetail>;
 }
 
-impl<T, E: std::fmt::Display> IntoMcpError<T> for Result<T, E> {
+impl<T> IntoMcpError<T> for anyhow::Result<T> {
     fn into_mcp_storage_error(self) -> Result<T, McpErrorDetail> {
-        self.map_err(|e| McpErrorDetail::storage_error(e.to_string()))
+        self.map_err(|e| {
+            if e.is::<SaveConflict>() {
+                McpErrorDetail::conflict(e.to_string())
+            } else {
+                McpErrorDetail::storage_error(e.to_string())
+            }
+        })
     }
 }
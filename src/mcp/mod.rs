@@ -1,5 +1,7 @@
+pub mod config;
 pub mod errors;
 pub mod schemas;
 pub mod server;
 
+pub use config::install_config;
 pub use server::TodoMcpServer;
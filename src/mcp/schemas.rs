@@ -3,20 +3,35 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::storage::comments::Comment;
+use crate::storage::search::SearchHit;
+use crate::storage::trash::TrashedTodo;
 use crate::todo::{TodoItem, TodoState};
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct ListTodosRequest {
     #[schemars(description = "Date in YYYY-MM-DD format. Defaults to today if not provided.")]
     pub date: Option<String>,
+    #[schemars(
+        description = "Name of a project list (e.g. 'backlog') to use instead of a daily list. Use list_lists to see existing lists."
+    )]
+    pub list: Option<String>,
+    #[schemars(description = "Only return todos carrying this tag (e.g. 'review' or '#review').")]
+    pub tag: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct CreateTodoRequest {
-    #[schemars(description = "The todo content text. Cannot be empty.")]
+    #[schemars(
+        description = "The todo content text. Cannot be empty. May include #tags and a recurrence rule like @every(weekday), @every(mon,thu) or @every(1st)."
+    )]
     pub content: String,
     #[schemars(description = "Date in YYYY-MM-DD format. Defaults to today if not provided.")]
     pub date: Option<String>,
+    #[schemars(
+        description = "Name of a project list (e.g. 'backlog') to use instead of a daily list. Use list_lists to see existing lists."
+    )]
+    pub list: Option<String>,
     #[schemars(
         description = "UUID of parent todo to nest under. Use list_todos to get valid IDs."
     )]
@@ -25,6 +40,18 @@ pub struct CreateTodoRequest {
     pub due_date: Option<String>,
     #[schemars(description = "Additional notes or description for the todo.")]
     pub description: Option<String>,
+    #[schemars(
+        description = "Tags for the todo, without the leading '#'. Inline #tags in content are also picked up."
+    )]
+    pub tags: Option<Vec<String>>,
+    #[schemars(
+        description = "UUIDs of todos (on any date) that must be finished before this one can start."
+    )]
+    pub blocked_by: Option<Vec<String>>,
+    #[schemars(
+        description = "Start date in YYYY-MM-DD format. A todo starting after the list's date is kept out of lists until that day."
+    )]
+    pub start_date: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -33,24 +60,102 @@ pub struct UpdateTodoRequest {
     pub id: String,
     #[schemars(description = "Date in YYYY-MM-DD format. Defaults to today if not provided.")]
     pub date: Option<String>,
+    #[schemars(
+        description = "Name of a project list (e.g. 'backlog') to use instead of a daily list. Use list_lists to see existing lists."
+    )]
+    pub list: Option<String>,
     #[schemars(description = "New content text for the todo.")]
     pub content: Option<String>,
     #[schemars(
-        description = "New state: ' ' (empty/pending), 'x' (done), '?' (question), '!' (important)"
+        description = "New state: ' ' (empty/pending), 'x' (done), '?' (question), '!' (important), or a custom state marker/label configured by the user"
     )]
     pub state: Option<String>,
     #[schemars(description = "New due date in YYYY-MM-DD format.")]
     pub due_date: Option<String>,
     #[schemars(description = "New description. Empty string clears the description.")]
     pub description: Option<String>,
+    #[schemars(description = "Replacement tags for the todo. Empty list clears all tags.")]
+    pub tags: Option<Vec<String>>,
+    #[schemars(
+        description = "Replacement list of blocking todo UUIDs (on any date). Empty list removes all blockers."
+    )]
+    pub blocked_by: Option<Vec<String>>,
+    #[schemars(
+        description = "New start date in YYYY-MM-DD format. A date after the list's date moves the todo out of the list until that day."
+    )]
+    pub start_date: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AddCommentRequest {
+    #[schemars(
+        description = "UUID of any daily occurrence of the todo. Use list_todos to get valid IDs."
+    )]
+    pub id: String,
+    #[schemars(description = "The comment text. Cannot be empty.")]
+    pub body: String,
+    #[schemars(description = "Who is writing the comment. Defaults to 'agent'.")]
+    pub author: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListCommentsRequest {
+    #[schemars(
+        description = "UUID of any daily occurrence of the todo. Use list_todos to get valid IDs."
+    )]
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SearchTodosRequest {
+    #[schemars(
+        description = "Words to look for in todo content and descriptions. Every word must match, as a prefix (e.g. 'budget q3')."
+    )]
+    pub query: String,
+    #[schemars(description = "Maximum number of results, best match first. Defaults to 20.")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TodoHistoryRequest {
+    #[schemars(
+        description = "UUID of any daily occurrence of the todo. Use list_todos to get valid IDs."
+    )]
+    pub id: String,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct DeleteTodoRequest {
-    #[schemars(description = "UUID of the todo to delete. This also deletes all child todos.")]
+    #[schemars(
+        description = "UUID of the todo to delete. This also deletes all child todos. Deleted todos go to the trash and can be restored with restore_todo."
+    )]
     pub id: String,
     #[schemars(description = "Date in YYYY-MM-DD format. Defaults to today if not provided.")]
     pub date: Option<String>,
+    #[schemars(
+        description = "Name of a project list (e.g. 'backlog') to use instead of a daily list. Use list_lists to see existing lists."
+    )]
+    pub list: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RestoreTodoRequest {
+    #[schemars(
+        description = "UUID of the deleted todo to restore. Child todos deleted with it come back too. Use list_trash to get valid IDs."
+    )]
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PurgeTrashRequest {
+    #[schemars(
+        description = "UUID of one deleted todo to purge, with the child todos deleted along with it. Omit to purge by age instead."
+    )]
+    pub id: Option<String>,
+    #[schemars(
+        description = "Without an id: true empties the whole trash, false (default) only purges todos past the configured retention window."
+    )]
+    pub all: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -61,6 +166,10 @@ pub struct MarkCompleteRequest {
     pub id: String,
     #[schemars(description = "Date in YYYY-MM-DD format. Defaults to today if not provided.")]
     pub date: Option<String>,
+    #[schemars(
+        description = "Name of a project list (e.g. 'backlog') to use instead of a daily list. Use list_lists to see existing lists."
+    )]
+    pub list: Option<String>,
 }
 
 #[derive(Debug, Serialize, JsonSchema)]
@@ -76,6 +185,32 @@ pub struct TodoItemResponse {
     pub due_date: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series_id: Option<String>,
+    #[schemars(description = "Id shared by every daily occurrence of this task.")]
+    pub origin_id: String,
+    #[schemars(description = "Number of days this task has been carried forward unfinished.")]
+    #[serde(skip_serializing_if = "is_zero")]
+    pub carry_count: u32,
+    #[schemars(description = "Lineage ids of todos that must be finished before this one.")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub blocked_by: Vec<String>,
+    #[schemars(
+        description = "True while any blocker is unfinished. Do not start blocked todos."
+    )]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub blocked: bool,
+    #[schemars(description = "Day the todo becomes actionable, if scheduled.")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
 }
 
 impl From<&TodoItem> for TodoItemResponse {
@@ -84,18 +219,19 @@ impl From<&TodoItem> for TodoItemResponse {
             id: item.id.to_string(),
             content: item.content.clone(),
             state: item.state.to_char().to_string(),
-            state_description: match item.state {
-                TodoState::Empty => "pending",
-                TodoState::Checked => "done",
-                TodoState::Question => "question",
-                TodoState::Exclamation => "important",
-                TodoState::InProgress => "in_progress",
-            }
-            .to_string(),
+            state_description: item.state.label(),
             indent_level: item.indent_level,
             parent_id: item.parent_id.map(|id| id.to_string()),
             due_date: item.due_date.map(|d| d.format("%Y-%m-%d").to_string()),
             description: item.description.clone(),
+            tags: item.tags.clone(),
+            recurrence: item.recurrence.as_ref().map(|r| r.to_string()),
+            series_id: item.series_id.map(|id| id.to_string()),
+            origin_id: item.origin().to_string(),
+            carry_count: item.carry_count,
+            blocked_by: item.blocked_by.iter().map(|id| id.to_string()).collect(),
+            blocked: false,
+            start_date: item.start_date.map(|d| d.format("%Y-%m-%d").to_string()),
         }
     }
 }
@@ -103,6 +239,9 @@ impl From<&TodoItem> for TodoItemResponse {
 #[derive(Debug, Serialize, JsonSchema)]
 pub struct TodoListResponse {
     pub date: String,
+    #[schemars(description = "Name of the project list, if this is not a daily list.")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
     pub item_count: usize,
     #[schemars(
         description = "Pre-formatted todo list for display. Show this directly to the user."
@@ -112,23 +251,39 @@ pub struct TodoListResponse {
         description = "Raw item data for programmatic access. Use 'formatted' for display."
     )]
     pub items: Vec<TodoItemResponse>,
+    #[schemars(
+        description = "Set when the list's markdown file conflicts with the stored list, which is returned. Tell the user."
+    )]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<String>,
 }
 
 impl TodoListResponse {
-    pub fn new(date: String, items: Vec<TodoItemResponse>) -> Self {
-        let formatted = Self::format_list(&date, &items);
+    pub fn new(date: String, list: Option<String>, items: Vec<TodoItemResponse>) -> Self {
+        let formatted = Self::format_list(list.as_deref().unwrap_or(&date), &items);
         let item_count = items.len();
         Self {
             date,
+            list,
             item_count,
             formatted,
             items,
+            conflict: None,
         }
     }
 
-    fn format_list(date: &str, items: &[TodoItemResponse]) -> String {
+    /// Report a conflict with the markdown file, in `formatted` too.
+    pub fn with_conflict(mut self, conflict: Option<String>) -> Self {
+        if let Some(ref message) = conflict {
+            self.formatted = format!("⚠️ {message}\n\n{}", self.formatted);
+        }
+        self.conflict = conflict;
+        self
+    }
+
+    fn format_list(title: &str, items: &[TodoItemResponse]) -> String {
         if items.is_empty() {
-            return format!("No todos for {date}");
+            return format!("No todos for {title}");
         }
 
         let mut lines = Vec::new();
@@ -136,7 +291,7 @@ impl TodoListResponse {
             (done + if item.state == "x" { 1 } else { 0 }, total + 1)
         });
 
-        lines.push(format!("## Todos for {date} ({done}/{total})"));
+        lines.push(format!("## Todos for {title} ({done}/{total})"));
         lines.push(String::new());
 
         for (i, item) in items.iter().enumerate() {
@@ -159,7 +314,22 @@ impl TodoListResponse {
                 .as_ref()
                 .map(|d| format!(" (due: {d})"))
                 .unwrap_or_default();
-            lines.push(format!("{}{} {}{}", indent, checkbox, item.content, due));
+            let tags: String = item.tags.iter().map(|t| format!(" #{t}")).collect();
+            let every = item
+                .recurrence
+                .as_ref()
+                .map(|r| format!(" (every {r})"))
+                .unwrap_or_default();
+            let carried = match item.carry_count {
+                0 => String::new(),
+                1 => " (carried 1 day)".to_string(),
+                n => format!(" (carried {n} days)"),
+            };
+            let blocked = if item.blocked { " ⛔ blocked" } else { "" };
+            lines.push(format!(
+                "{}{} {}{}{}{}{}{}",
+                indent, checkbox, item.content, tags, every, due, carried, blocked
+            ));
         }
 
         lines.join("\n")
@@ -179,6 +349,141 @@ impl TodoListResponse {
     }
 }
 
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TodoOccurrenceResponse {
+    #[schemars(description = "Date of the daily list this occurrence belongs to (YYYY-MM-DD).")]
+    pub date: String,
+    #[serde(flatten)]
+    pub item: TodoItemResponse,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TodoHistoryResponse {
+    pub origin_id: String,
+    pub occurrence_count: usize,
+    #[schemars(description = "Every daily occurrence of the task, oldest first.")]
+    pub occurrences: Vec<TodoOccurrenceResponse>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SearchResultResponse {
+    pub id: String,
+    pub content: String,
+    pub state: String,
+    #[schemars(description = "Day the todo is or was on (YYYY-MM-DD); absent for project list items.")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[schemars(description = "Project list holding the todo.")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
+    #[schemars(description = "True if the todo is on a past, archived day.")]
+    pub archived: bool,
+    #[schemars(description = "Matching fragment of the content or description, matches in **bold**.")]
+    pub snippet: String,
+}
+
+impl From<&SearchHit> for SearchResultResponse {
+    fn from(hit: &SearchHit) -> Self {
+        Self {
+            id: hit.id.to_string(),
+            content: hit.content.clone(),
+            state: hit.state.to_char().to_string(),
+            date: hit.date.map(|d| d.format("%Y-%m-%d").to_string()),
+            list: hit.list.clone(),
+            archived: hit.archived,
+            snippet: hit.snippet.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SearchTodosResponse {
+    pub result_count: usize,
+    #[schemars(description = "Matching todos, best match first.")]
+    pub results: Vec<SearchResultResponse>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CommentResponse {
+    pub id: i64,
+    pub author: String,
+    pub body: String,
+    #[schemars(description = "When the comment was written (RFC 3339).")]
+    pub created_at: String,
+}
+
+impl From<&Comment> for CommentResponse {
+    fn from(comment: &Comment) -> Self {
+        Self {
+            id: comment.id,
+            author: comment.author.clone(),
+            body: comment.body.clone(),
+            created_at: comment.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CommentListResponse {
+    pub comment_count: usize,
+    #[schemars(description = "The todo's comment thread, oldest first.")]
+    pub comments: Vec<CommentResponse>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ListNamesResponse {
+    #[schemars(description = "Names of all project lists, alphabetically.")]
+    pub lists: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TrashedTodoResponse {
+    #[serde(flatten)]
+    pub item: TodoItemResponse,
+    #[schemars(description = "Day the todo was deleted from (YYYY-MM-DD); absent for project list items.")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[schemars(description = "Project list the todo was deleted from.")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
+    #[schemars(description = "When the todo was deleted (RFC 3339).")]
+    pub deleted_at: String,
+}
+
+impl From<&TrashedTodo> for TrashedTodoResponse {
+    fn from(entry: &TrashedTodo) -> Self {
+        Self {
+            item: TodoItemResponse::from(&entry.item),
+            date: entry.date.map(|d| d.format("%Y-%m-%d").to_string()),
+            list: entry.list.clone(),
+            deleted_at: entry.deleted_at().to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TrashResponse {
+    pub item_count: usize,
+    #[schemars(
+        description = "Deleted todos, most recently deleted first. Child todos deleted with a parent keep its id in parent_id."
+    )]
+    pub items: Vec<TrashedTodoResponse>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RestoreTodoResponse {
+    pub restored_count: usize,
+    #[schemars(description = "The restored todos, as they are now.")]
+    pub items: Vec<TodoItemResponse>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PurgeTrashResponse {
+    pub purged_count: usize,
+    pub message: String,
+}
+
 #[derive(Debug, Serialize, JsonSchema)]
 pub struct DeleteTodoResponse {
     pub deleted_count: usize,
@@ -5,60 +5,98 @@ use rmcp::{
     model::{ServerCapabilities, ServerInfo},
     tool, tool_handler, tool_router,
 };
+use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
-use crate::storage::database::soft_delete_todos;
-use crate::storage::file::{file_exists, load_todo_list, save_todo_list};
 use crate::storage::rollover::create_rolled_over_list;
-use crate::todo::{TodoItem, TodoList};
+use crate::storage::search::DEFAULT_SEARCH_LIMIT;
+use crate::storage::sync::conflict_message;
+use crate::storage::trash;
+use crate::storage::{SqliteStore, TodoStore};
+use crate::todo::{TodoItem, TodoList, normalize_tags};
+use crate::utils::paths::validate_list_name;
 
 use super::errors::{IntoMcpError, McpErrorDetail};
 use super::schemas::{
-    CreateTodoRequest, DeleteTodoRequest, DeleteTodoResponse, ListTodosRequest,
-    MarkCompleteRequest, TodoItemResponse, TodoListResponse, UpdateTodoRequest, parse_date,
-    parse_state, parse_uuid,
+    AddCommentRequest, CommentListResponse, CommentResponse, CreateTodoRequest, DeleteTodoRequest, DeleteTodoResponse, ListTodosRequest,
+    ListCommentsRequest, ListNamesResponse, MarkCompleteRequest, PurgeTrashRequest, PurgeTrashResponse,
+    RestoreTodoRequest, RestoreTodoResponse, SearchResultResponse, SearchTodosRequest, SearchTodosResponse,
+    TodoHistoryRequest, TodoHistoryResponse, TodoItemResponse, TodoListResponse, TodoOccurrenceResponse,
+    TrashResponse, TrashedTodoResponse, UpdateTodoRequest, parse_date, parse_state,
+    parse_uuid,
 };
 
 #[derive(Clone)]
 pub struct TodoMcpServer {
+    store: Arc<dyn TodoStore>,
     tool_router: ToolRouter<Self>,
 }
 
 impl TodoMcpServer {
-    pub fn new() -> Self {
+    pub fn new(store: Arc<dyn TodoStore>) -> Self {
         Self {
+            store,
             tool_router: Self::tool_router(),
         }
     }
+
+    /// Load the project list `name` if given, otherwise the daily list for `date`.
+    fn load_requested_list(
+        &self,
+        date: chrono::NaiveDate,
+        name: Option<&str>,
+    ) -> Result<TodoList, String> {
+        let store = self.store.as_ref();
+        match name {
+            Some(name) => {
+                validate_list_name(name).map_err(|e| {
+                    format_error(McpErrorDetail::invalid_input(
+                        e.to_string(),
+                        "Use list_lists to see existing lists, or pick a new name made of letters, digits, '-' and '_'",
+                    ))
+                })?;
+                store
+                    .load_named_list(name)
+                    .into_mcp_storage_error()
+                    .map_err(format_error)
+            }
+            None => load_list_with_rollover(store, date).map_err(format_error),
+        }
+    }
 }
 
 impl Default for TodoMcpServer {
     fn default() -> Self {
-        Self::new()
+        Self::new(Arc::new(SqliteStore))
     }
 }
 
-fn load_list_with_rollover(date: chrono::NaiveDate) -> Result<TodoList, McpErrorDetail> {
+fn load_list_with_rollover(
+    store: &dyn TodoStore,
+    date: chrono::NaiveDate,
+) -> Result<TodoList, McpErrorDetail> {
     let today = Local::now().date_naive();
 
-    if date == today && !file_exists(date).into_mcp_storage_error()? {
+    if date == today && !store.has_todos_for_date(date).into_mcp_storage_error()? {
         debug!(date = %date, "No todos for today, checking for rollover candidates");
         for days_back in 1..=30 {
             if let Some(check_date) = today.checked_sub_days(chrono::Days::new(days_back))
-                && file_exists(check_date).into_mcp_storage_error()? {
-                    let list = load_todo_list(check_date).into_mcp_storage_error()?;
-                    let incomplete = list.get_incomplete_items();
+                && store.has_todos_for_date(check_date).into_mcp_storage_error()? {
+                    let list = store.load_todo_list(check_date).into_mcp_storage_error()?;
+                    let items = list.get_rollover_items(today);
 
-                    if !incomplete.is_empty() {
+                    if !items.is_empty() {
                         info!(
                             from_date = %check_date,
                             to_date = %today,
-                            count = incomplete.len(),
+                            count = items.len(),
                             "Rolling over incomplete todos"
                         );
-                        let rolled_list =
-                            create_rolled_over_list(today, incomplete).into_mcp_storage_error()?;
-                        save_todo_list(&rolled_list).into_mcp_storage_error()?;
+                        let mut rolled_list =
+                            create_rolled_over_list(today, items).into_mcp_storage_error()?;
+                        store.schedule_future_items(&mut rolled_list).into_mcp_storage_error()?;
+                        store.merge_due_scheduled(&mut rolled_list).into_mcp_storage_error()?;
+                        store.save_todo_list(&mut rolled_list).into_mcp_storage_error()?;
                         return Ok(rolled_list);
                     }
                     break;
@@ -66,7 +104,7 @@ fn load_list_with_rollover(date: chrono::NaiveDate) -> Result<TodoList, McpError
         }
     }
 
-    load_todo_list(date).into_mcp_storage_error()
+    store.load_todo_list(date).into_mcp_storage_error()
 }
 
 fn format_error(detail: McpErrorDetail) -> String {
@@ -90,32 +128,250 @@ fn parse_uuid_or_err(id_str: &str) -> Result<uuid::Uuid, String> {
     })
 }
 
+fn resolve_blockers_or_err(
+    store: &dyn TodoStore,
+    list: &TodoList,
+    todo_id: uuid::Uuid,
+    blocker_ids: &[String],
+) -> Result<Vec<uuid::Uuid>, String> {
+    let ids = blocker_ids
+        .iter()
+        .map(|s| parse_uuid_or_err(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    store.resolve_blocker_ids(list, todo_id, &ids).map_err(|e| {
+        format_error(McpErrorDetail::validation_error(
+            e.to_string(),
+            "Use list_todos or get_todo_history to find valid blocker IDs",
+        ))
+    })
+}
+
+/// Response for the item at `index`, including whether it is currently blocked.
+fn item_response(
+    store: &dyn TodoStore,
+    list: &TodoList,
+    index: usize,
+) -> Result<TodoItemResponse, String> {
+    let blocked = store
+        .find_blocked_indices(list)
+        .into_mcp_storage_error()
+        .map_err(format_error)?;
+    Ok(TodoItemResponse {
+        blocked: blocked.contains(&index),
+        ..TodoItemResponse::from(&list.items[index])
+    })
+}
+
 #[tool_router]
 impl TodoMcpServer {
     #[tool(
         name = "list_todos",
-        description = "List all todos for a specific date. Defaults to today. Automatically rolls over incomplete todos from previous days if today's list is empty. Response includes a 'formatted' field - display it directly as markdown to the user."
+        description = "List all todos for a specific date, optionally filtered by tag. Defaults to today. Automatically rolls over incomplete todos from previous days if today's list is empty. Response includes a 'formatted' field - display it directly as markdown to the user."
     )]
     async fn list_todos(
         &self,
         params: Parameters<ListTodosRequest>,
     ) -> Result<Json<TodoListResponse>, String> {
-        info!(date = ?params.0.date, "list_todos called");
+        info!(date = ?params.0.date, tag = ?params.0.tag, "list_todos called");
 
         let date = parse_date_or_err(params.0.date.as_deref())?;
 
-        let list = load_list_with_rollover(date).map_err(format_error)?;
+        let list = self.load_requested_list(date, params.0.list.as_deref())?;
 
-        let items: Vec<TodoItemResponse> = list.items.iter().map(TodoItemResponse::from).collect();
-        let response = TodoListResponse::new(list.date.format("%Y-%m-%d").to_string(), items);
+        let blocked = self
+            .store
+            .find_blocked_indices(&list)
+            .into_mcp_storage_error()
+            .map_err(format_error)?;
+
+        let items: Vec<TodoItemResponse> = list
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| params.0.tag.as_ref().is_none_or(|tag| item.has_tag(tag)))
+            .map(|(idx, item)| TodoItemResponse {
+                blocked: blocked.contains(&idx),
+                ..TodoItemResponse::from(item)
+            })
+            .collect();
+        let response = TodoListResponse::new(
+            list.date.format("%Y-%m-%d").to_string(),
+            list.name.clone(),
+            items,
+        )
+        .with_conflict(list.file_conflict.then(|| conflict_message(list.date)));
 
         info!(date = %date, count = response.item_count, "list_todos returning items");
         Ok(Json(response))
     }
 
+    #[tool(
+        name = "list_lists",
+        description = "List the names of all project lists (e.g. a backlog or per-project lists). Pass a name as 'list' to the other tools to work on that list instead of a daily list."
+    )]
+    async fn list_lists(&self) -> Result<Json<ListNamesResponse>, String> {
+        info!("list_lists called");
+
+        let lists = self
+            .store
+            .list_names()
+            .into_mcp_storage_error()
+            .map_err(format_error)?;
+
+        info!(count = lists.len(), "list_lists completed");
+        Ok(Json(ListNamesResponse { lists }))
+    }
+
+    #[tool(
+        name = "search_todos",
+        description = "Full-text search over todo content and descriptions on every day, in the archive and in project lists. Use it to find something from earlier days instead of listing day by day."
+    )]
+    async fn search_todos(
+        &self,
+        params: Parameters<SearchTodosRequest>,
+    ) -> Result<Json<SearchTodosResponse>, String> {
+        let req = params.0;
+        info!(query = %req.query, "search_todos called");
+
+        if req.query.trim().is_empty() {
+            return Err(format_error(McpErrorDetail::validation_error(
+                "Search query cannot be empty",
+                "Provide one or more words to look for",
+            )));
+        }
+
+        let hits = self
+            .store
+            .search_todos(&req.query, req.limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+            .into_mcp_storage_error()
+            .map_err(format_error)?;
+
+        info!(count = hits.len(), "search_todos completed");
+        Ok(Json(SearchTodosResponse {
+            result_count: hits.len(),
+            results: hits.iter().map(SearchResultResponse::from).collect(),
+        }))
+    }
+
+    #[tool(
+        name = "get_todo_history",
+        description = "Get every occurrence of a task, following it across rollovers and into scheduled or project-list copies. Useful to see how long a task has been carried forward."
+    )]
+    async fn get_todo_history(
+        &self,
+        params: Parameters<TodoHistoryRequest>,
+    ) -> Result<Json<TodoHistoryResponse>, String> {
+        info!(id = %params.0.id, "get_todo_history called");
+
+        let id = parse_uuid_or_err(&params.0.id)?;
+
+        let history = self
+            .store
+            .load_todo_history(id)
+            .into_mcp_storage_error()
+            .map_err(format_error)?;
+
+        let Some((_, first)) = history.first() else {
+            return Err(format_error(McpErrorDetail::not_found(
+                format!("Todo with id '{}' not found", params.0.id),
+                "Use list_todos to get valid IDs",
+            )));
+        };
+
+        let response = TodoHistoryResponse {
+            origin_id: first.origin().to_string(),
+            occurrence_count: history.len(),
+            occurrences: history
+                .iter()
+                .map(|(date, item)| TodoOccurrenceResponse {
+                    date: date.format("%Y-%m-%d").to_string(),
+                    item: TodoItemResponse::from(item),
+                })
+                .collect(),
+        };
+
+        info!(count = response.occurrence_count, "get_todo_history completed");
+        Ok(Json(response))
+    }
+
+    #[tool(
+        name = "add_comment",
+        description = "Append a timestamped comment to a todo's thread. Use this to leave progress notes instead of overwriting the description."
+    )]
+    async fn add_comment(
+        &self,
+        params: Parameters<AddCommentRequest>,
+    ) -> Result<Json<CommentResponse>, String> {
+        let req = params.0;
+        info!(id = %req.id, "add_comment called");
+
+        let id = parse_uuid_or_err(&req.id)?;
+        let body = req.body.trim();
+        if body.is_empty() {
+            return Err(format_error(McpErrorDetail::validation_error(
+                "Comment body cannot be empty",
+                "Provide the text of the comment",
+            )));
+        }
+        let author = req
+            .author
+            .as_deref()
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .unwrap_or("agent");
+
+        let comment = self
+            .store
+            .add_comment(id, author, body)
+            .into_mcp_storage_error()
+            .map_err(format_error)?
+            .ok_or_else(|| {
+                format_error(McpErrorDetail::not_found(
+                    format!("Todo with id '{}' not found", req.id),
+                    "Use list_todos to get valid IDs",
+                ))
+            })?;
+
+        info!(comment_id = comment.id, "add_comment completed");
+        Ok(Json(CommentResponse::from(&comment)))
+    }
+
+    #[tool(
+        name = "list_comments",
+        description = "List the comment thread of a todo, oldest first. The thread follows the task across rollovers."
+    )]
+    async fn list_comments(
+        &self,
+        params: Parameters<ListCommentsRequest>,
+    ) -> Result<Json<CommentListResponse>, String> {
+        info!(id = %params.0.id, "list_comments called");
+
+        let id = parse_uuid_or_err(&params.0.id)?;
+
+        let comments = self
+            .store
+            .load_comments(id)
+            .into_mcp_storage_error()
+            .map_err(format_error)?
+            .ok_or_else(|| {
+                format_error(McpErrorDetail::not_found(
+                    format!("Todo with id '{}' not found", params.0.id),
+                    "Use list_todos to get valid IDs",
+                ))
+            })?;
+
+        let response = CommentListResponse {
+            comment_count: comments.len(),
+            comments: comments.iter().map(CommentResponse::from).collect(),
+        };
+
+        info!(count = response.comment_count, "list_comments completed");
+        Ok(Json(response))
+    }
+
     #[tool(
         name = "create_todo",
-        description = "Create a new todo item. Optionally nest under a parent todo by providing parent_id."
+        description = "Create a new todo item. Optionally nest under a parent todo by providing parent_id, or set start_date to keep it hidden until a later day."
     )]
     async fn create_todo(
         &self,
@@ -138,13 +394,18 @@ impl TodoMcpServer {
 
         let date = parse_date_or_err(req.date.as_deref())?;
 
-        let mut list = load_list_with_rollover(date).map_err(format_error)?;
+        let mut list = self.load_requested_list(date, req.list.as_deref())?;
 
         let due_date = req
             .due_date
             .as_deref()
             .map(|s| parse_date_or_err(Some(s)))
             .transpose()?;
+        let start_date = req
+            .start_date
+            .as_deref()
+            .map(|s| parse_date_or_err(Some(s)))
+            .transpose()?;
 
         let (indent_level, insert_index) = if let Some(ref parent_id_str) = req.parent_id {
             let parent_id = parse_uuid_or_err(parent_id_str)?;
@@ -162,15 +423,30 @@ impl TodoMcpServer {
             (0, list.items.len())
         };
 
-        let mut item = TodoItem::new(req.content, indent_level);
+        let mut item = TodoItem::new(String::new(), indent_level);
+        item.set_content(&req.content);
+        if let Some(tags) = req.tags {
+            item.tags = normalize_tags([std::mem::take(&mut item.tags), tags].concat());
+        }
         item.parent_id = req.parent_id.as_deref().and_then(|s| parse_uuid(s).ok());
         item.due_date = due_date;
         item.description = req.description;
+        if start_date.is_some() {
+            item.start_date = start_date;
+        }
+
+        if let Some(ref blocked_by) = req.blocked_by {
+            item.blocked_by = resolve_blockers_or_err(self.store.as_ref(), &list, item.id, blocked_by)?;
+        }
 
-        let response = TodoItemResponse::from(&item);
         list.items.insert(insert_index, item);
 
-        save_todo_list(&list)
+        // Respond before a future start date moves the item off this day's list
+        let response = item_response(self.store.as_ref(), &list, insert_index)?;
+
+        self.store
+            .schedule_future_items(&mut list)
+            .and_then(|_| self.store.save_todo_list(&mut list))
             .into_mcp_storage_error().map_err(format_error)?;
 
         info!(id = %response.id, content = %response.content, "create_todo completed");
@@ -179,7 +455,7 @@ impl TodoMcpServer {
 
     #[tool(
         name = "update_todo",
-        description = "Update an existing todo's content, state, due date, or description. State values: ' ' (empty/pending), '*' (in progress), 'x' (done), '?' (question), '!' (important)"
+        description = "Update an existing todo's content, state, due date, description, tags, or blockers. State values: ' ' (empty/pending), '*' (in progress), 'x' (done), '?' (question), '!' (important), or any custom state marker/label from the user's config"
     )]
     async fn update_todo(
         &self,
@@ -197,19 +473,31 @@ impl TodoMcpServer {
         let id = parse_uuid_or_err(&req.id)?;
         let date = parse_date_or_err(req.date.as_deref())?;
 
-        let mut list = load_list_with_rollover(date).map_err(format_error)?;
+        let mut list = self.load_requested_list(date, req.list.as_deref())?;
 
-        let item = list
+        let idx = list
             .items
-            .iter_mut()
-            .find(|item| item.id == id)
+            .iter()
+            .position(|item| item.id == id)
             .ok_or_else(|| {
                 format_error(McpErrorDetail::not_found(
-                    format!("Todo with id '{}' not found on {}", req.id, date),
+                    format!("Todo with id '{}' not found in {}", req.id, list.title()),
                     "Use list_todos to verify the todo exists on this date",
                 ))
             })?;
 
+        let blocked_by = req
+            .blocked_by
+            .as_deref()
+            .map(|ids| resolve_blockers_or_err(self.store.as_ref(), &list, id, ids))
+            .transpose()?;
+
+        let item = &mut list.items[idx];
+
+        if let Some(blocked_by) = blocked_by {
+            item.blocked_by = blocked_by;
+        }
+
         if let Some(ref content) = req.content {
             if content.trim().is_empty() {
                 return Err(format_error(McpErrorDetail::validation_error(
@@ -217,7 +505,11 @@ impl TodoMcpServer {
                     "Provide a non-empty string or omit the content field",
                 )));
             }
-            item.content = content.clone();
+            item.merge_content(content);
+        }
+
+        if let Some(ref tags) = req.tags {
+            item.tags = normalize_tags(tags.clone());
         }
 
         if let Some(ref state_str) = req.state {
@@ -226,7 +518,7 @@ impl TodoMcpServer {
                     "Invalid state '{state_str}'. "
                 )))
             })?;
-            item.state = state;
+            item.set_state(state);
         }
 
         if let Some(ref due_date_str) = req.due_date {
@@ -234,6 +526,11 @@ impl TodoMcpServer {
             item.due_date = Some(due_date);
         }
 
+        if let Some(ref start_date_str) = req.start_date {
+            let start_date = parse_date_or_err(Some(start_date_str))?;
+            item.start_date = Some(start_date);
+        }
+
         if let Some(ref description) = req.description {
             item.description = if description.is_empty() {
                 None
@@ -242,9 +539,15 @@ impl TodoMcpServer {
             };
         }
 
-        let response = TodoItemResponse::from(&*item);
+        if req.state.is_some() {
+            list.sync_parent_states(idx);
+        }
+
+        let response = item_response(self.store.as_ref(), &list, idx)?;
 
-        save_todo_list(&list)
+        self.store
+            .schedule_future_items(&mut list)
+            .and_then(|_| self.store.save_todo_list(&mut list))
             .into_mcp_storage_error().map_err(format_error)?;
 
         info!(id = %response.id, state = %response.state, "update_todo completed");
@@ -253,7 +556,7 @@ impl TodoMcpServer {
 
     #[tool(
         name = "delete_todo",
-        description = "Delete a todo and all its children. This action is irreversible."
+        description = "Delete a todo and all its children. They go to the trash, from where restore_todo brings them back until they are purged."
     )]
     async fn delete_todo(
         &self,
@@ -265,7 +568,7 @@ impl TodoMcpServer {
         let id = parse_uuid_or_err(&req.id)?;
         let date = parse_date_or_err(req.date.as_deref())?;
 
-        let mut list = load_list_with_rollover(date).map_err(format_error)?;
+        let mut list = self.load_requested_list(date, req.list.as_deref())?;
 
         let idx = list
             .items
@@ -273,7 +576,7 @@ impl TodoMcpServer {
             .position(|item| item.id == id)
             .ok_or_else(|| {
                 format_error(McpErrorDetail::not_found(
-                    format!("Todo with id '{}' not found on {}", req.id, date),
+                    format!("Todo with id '{}' not found in {}", req.id, list.title()),
                     "Use list_todos to verify the todo exists on this date",
                 ))
             })?;
@@ -285,13 +588,15 @@ impl TodoMcpServer {
         let deleted_count = end - start;
 
         let ids: Vec<_> = list.items[start..end].iter().map(|item| item.id).collect();
-        soft_delete_todos(&ids, date)
+        self.store
+            .soft_delete_todos(&ids, date)
             .into_mcp_storage_error().map_err(format_error)?;
 
         list.items.drain(start..end);
         list.recalculate_parent_ids();
 
-        save_todo_list(&list)
+        self.store
+            .save_todo_list(&mut list)
             .into_mcp_storage_error().map_err(format_error)?;
 
         info!(deleted_count = deleted_count, "delete_todo completed");
@@ -301,6 +606,100 @@ impl TodoMcpServer {
         }))
     }
 
+    #[tool(
+        name = "list_trash",
+        description = "List deleted todos that have not been purged yet, most recently deleted first, with the day or project list each was deleted from."
+    )]
+    async fn list_trash(&self) -> Result<Json<TrashResponse>, String> {
+        info!("list_trash called");
+
+        let trash = self
+            .store
+            .load_trash()
+            .into_mcp_storage_error()
+            .map_err(format_error)?;
+
+        info!(count = trash.len(), "list_trash completed");
+        Ok(Json(TrashResponse {
+            item_count: trash.len(),
+            items: trash.iter().map(TrashedTodoResponse::from).collect(),
+        }))
+    }
+
+    #[tool(
+        name = "restore_todo",
+        description = "Restore a deleted todo, with the children deleted along with it, to its old position and parent. Todos deleted from a past day go to today's list."
+    )]
+    async fn restore_todo(
+        &self,
+        params: Parameters<RestoreTodoRequest>,
+    ) -> Result<Json<RestoreTodoResponse>, String> {
+        info!(id = %params.0.id, "restore_todo called");
+
+        let id = parse_uuid_or_err(&params.0.id)?;
+
+        let Some((list, restored)) = self
+            .store
+            .restore_from_trash(id)
+            .into_mcp_storage_error()
+            .map_err(format_error)?
+        else {
+            return Err(format_error(McpErrorDetail::not_found(
+                format!("Todo with id '{}' is not in the trash", params.0.id),
+                "Use list_trash to get valid IDs",
+            )));
+        };
+
+        let items: Vec<TodoItemResponse> = list.items[restored].iter().map(TodoItemResponse::from).collect();
+        info!(restored_count = items.len(), "restore_todo completed");
+        Ok(Json(RestoreTodoResponse {
+            restored_count: items.len(),
+            message: format!("Restored {} item(s) to {}", items.len(), list.title()),
+            items,
+        }))
+    }
+
+    #[tool(
+        name = "purge_trash",
+        description = "Permanently remove deleted todos: one todo and the children deleted with it, everything past the retention window, or the whole trash. This cannot be undone."
+    )]
+    async fn purge_trash(
+        &self,
+        params: Parameters<PurgeTrashRequest>,
+    ) -> Result<Json<PurgeTrashResponse>, String> {
+        let req = params.0;
+        info!(id = ?req.id, all = ?req.all, "purge_trash called");
+
+        let purged = match req.id {
+            Some(ref id_str) => {
+                let id = parse_uuid_or_err(id_str)?;
+                let trash = self
+                    .store
+                    .load_trash()
+                    .into_mcp_storage_error()
+                    .map_err(format_error)?;
+                let ids: Vec<uuid::Uuid> = trash::subtree(&trash, id).iter().map(|entry| entry.item.id).collect();
+                if ids.is_empty() {
+                    return Err(format_error(McpErrorDetail::not_found(
+                        format!("Todo with id '{id_str}' is not in the trash"),
+                        "Use list_trash to get valid IDs",
+                    )));
+                }
+                self.store.purge_trash(&ids)
+            }
+            None if req.all.unwrap_or(false) => self.store.purge_trash_before(chrono::Utc::now()),
+            None => self.store.purge_expired_trash(),
+        }
+        .into_mcp_storage_error()
+        .map_err(format_error)?;
+
+        info!(purged_count = purged, "purge_trash completed");
+        Ok(Json(PurgeTrashResponse {
+            purged_count: purged,
+            message: format!("Purged {purged} item(s)"),
+        }))
+    }
+
     #[tool(
         name = "mark_complete",
         description = "Toggle completion status: marks a todo as done [x] if pending, or pending [ ] if already done."
@@ -315,23 +714,25 @@ impl TodoMcpServer {
         let id = parse_uuid_or_err(&req.id)?;
         let date = parse_date_or_err(req.date.as_deref())?;
 
-        let mut list = load_list_with_rollover(date).map_err(format_error)?;
+        let mut list = self.load_requested_list(date, req.list.as_deref())?;
 
-        let item = list
+        let idx = list
             .items
-            .iter_mut()
-            .find(|item| item.id == id)
+            .iter()
+            .position(|item| item.id == id)
             .ok_or_else(|| {
                 format_error(McpErrorDetail::not_found(
-                    format!("Todo with id '{}' not found on {}", req.id, date),
+                    format!("Todo with id '{}' not found in {}", req.id, list.title()),
                     "Use list_todos to verify the todo exists on this date",
                 ))
             })?;
 
-        item.toggle_state();
-        let response = TodoItemResponse::from(&*item);
+        list.items[idx].toggle_state();
+        list.sync_parent_states(idx);
+        let response = TodoItemResponse::from(&list.items[idx]);
 
-        save_todo_list(&list)
+        self.store
+            .save_todo_list(&mut list)
             .into_mcp_storage_error().map_err(format_error)?;
 
         info!(id = %response.id, new_state = %response.state, "mark_complete completed");
@@ -346,10 +747,15 @@ impl rmcp::ServerHandler for TodoMcpServer {
             instructions: Some(
                 "Todo list management server.\n\n\
                 TOOLS:\n\
-                - list_todos: List todos. Response has 'formatted' field - display it directly as markdown.\n\
-                - create_todo: Create new todo. Can nest under parent via parent_id.\n\
-                - update_todo: Update content/state/due_date. States: ' '=pending, 'x'=done, '?'=question, '!'=important\n\
-                - delete_todo: Delete todo and children.\n\
+                - list_todos: List todos, optionally filtered by tag. Response has 'formatted' field - display it directly as markdown. Items with blocked=true wait on another todo; do not start them.\n\
+                - create_todo: Create new todo. Can nest under parent via parent_id. Inline #tags in content become tags; @every(weekday|mon,thu|1st) makes it recurring.\n\
+                - update_todo: Update content/state/due_date/start_date/tags/blocked_by. States: ' '=pending, 'x'=done, '?'=question, '!'=important\n\
+                - list_lists: Names of project lists. Pass 'list' to list_todos/create_todo/update_todo/delete_todo/mark_complete to work on one instead of a daily list.\n\
+                - search_todos: Full-text search over all days, the archive and project lists. Use it to find older todos.\n\
+                - get_todo_history: Every occurrence of a task across rollovers, including scheduled and project-list copies.\n\
+                - add_comment / list_comments: Append timestamped notes to a todo's thread, or read it. Prefer comments over rewriting the description.\n\
+                - delete_todo: Delete todo and children. They go to the trash.\n\
+                - list_trash / restore_todo / purge_trash: Browse deleted todos, put one back where it was, or remove them for good.\n\
                 - mark_complete: Toggle done/pending.\n\n\
                 DISPLAY GUIDELINES:\n\
                 - For list_todos: Display the 'formatted' field directly as markdown. Do NOT create tables.\n\
@@ -363,3 +769,67 @@ impl rmcp::ServerHandler for TodoMcpServer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::InMemoryStore;
+    use serde_json::json;
+
+    fn params<T: serde::de::DeserializeOwned>(value: serde_json::Value) -> Parameters<T> {
+        Parameters(serde_json::from_value(value).unwrap())
+    }
+
+    // The todos only exist in memory, so finding them again shows every tool went through
+    // the store rather than todos.db
+    #[tokio::test]
+    async fn test_tools_work_over_an_in_memory_store() {
+        let server = TodoMcpServer::new(Arc::new(InMemoryStore::new()));
+
+        let Json(draft) = server
+            .create_todo(params(json!({"content": "Write the draft", "list": "work"})))
+            .await
+            .unwrap();
+        let Json(send) = server
+            .create_todo(params(json!({
+                "content": "Send the draft",
+                "list": "work",
+                "blocked_by": [draft.id],
+            })))
+            .await
+            .unwrap();
+        assert!(send.blocked);
+
+        let Json(list) = server
+            .list_todos(params(json!({"list": "work"})))
+            .await
+            .unwrap();
+        assert_eq!(list.item_count, 2);
+        assert!(list.items[1].blocked);
+
+        server
+            .add_comment(params(json!({"id": draft.id, "body": "Outline done"})))
+            .await
+            .unwrap();
+        let Json(comments) = server
+            .list_comments(params(json!({"id": draft.id})))
+            .await
+            .unwrap();
+        assert_eq!(comments.comments[0].body, "Outline done");
+
+        let Json(found) = server
+            .search_todos(params(json!({"query": "draft"})))
+            .await
+            .unwrap();
+        assert_eq!(found.result_count, 2);
+
+        let Json(history) = server
+            .get_todo_history(params(json!({"id": draft.id})))
+            .await
+            .unwrap();
+        assert_eq!(history.occurrence_count, 1);
+
+        let unknown = uuid::Uuid::new_v4().to_string();
+        assert!(server.get_todo_history(params(json!({"id": unknown}))).await.is_err());
+    }
+}
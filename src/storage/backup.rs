@@ -0,0 +1,449 @@
+use super::database::get_connection;
+use super::migrations::{latest_version, migrate, schema_version};
+use crate::utils::paths::get_to_tui_dir;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{Local, NaiveDateTime};
+use rusqlite::backup::Progress;
+use rusqlite::{Connection, MAIN_DB, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+const DEFAULT_KEEP: usize = 10;
+
+const DATABASE_FILE: &str = "todos.db";
+
+/// Directories of the data directory that are copied into a snapshot next to the database.
+const DATA_DIRS: [&str; 2] = ["dailies", "lists"];
+
+/// Snapshot directories are named after the moment they were taken, then the reason.
+const NAME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// `[backup]` section of the config file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// Snapshots to keep. The oldest are deleted whenever a new one is taken; 0 keeps them
+    /// all.
+    #[serde(default = "default_keep")]
+    pub keep: usize,
+
+    /// Where snapshots are written, absolute or relative to the data directory. Defaults
+    /// to `backups` in the data directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+}
+
+fn default_keep() -> usize {
+    DEFAULT_KEEP
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            keep: DEFAULT_KEEP,
+            dir: None,
+        }
+    }
+}
+
+static CONFIG: RwLock<BackupConfig> = RwLock::new(BackupConfig {
+    keep: DEFAULT_KEEP,
+    dir: None,
+});
+
+/// Choose where snapshots go and how many are kept from now on.
+pub fn install_backup_config(config: &BackupConfig) {
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = config.clone();
+}
+
+fn installed_config() -> BackupConfig {
+    CONFIG.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Directory holding the snapshots of the current profile.
+pub fn get_backups_dir() -> Result<PathBuf> {
+    let data_dir = get_to_tui_dir()?;
+    Ok(match installed_config().dir {
+        Some(dir) => data_dir.join(dir),
+        None => data_dir.join("backups"),
+    })
+}
+
+/// Why a snapshot was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotReason {
+    Manual,
+    Migration,
+    Import,
+    Restore,
+}
+
+impl SnapshotReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SnapshotReason::Manual => "manual",
+            SnapshotReason::Migration => "pre-migrate",
+            SnapshotReason::Import => "pre-import",
+            SnapshotReason::Restore => "pre-restore",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "manual" => Some(SnapshotReason::Manual),
+            "pre-migrate" => Some(SnapshotReason::Migration),
+            "pre-import" => Some(SnapshotReason::Import),
+            "pre-restore" => Some(SnapshotReason::Restore),
+            _ => None,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            SnapshotReason::Manual => "taken with totui backup",
+            SnapshotReason::Migration => "before a schema migration",
+            SnapshotReason::Import => "before import-archive",
+            SnapshotReason::Restore => "before a restore",
+        }
+    }
+}
+
+/// A snapshot directory: a copy of `todos.db` taken through SQLite's online backup, plus
+/// the daily and project list files.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub name: String,
+    pub path: PathBuf,
+    pub taken_at: NaiveDateTime,
+    pub reason: Option<SnapshotReason>,
+}
+
+impl Snapshot {
+    /// The snapshot at `path`, or `None` if the directory is not named like one.
+    fn from_dir(path: &Path) -> Option<Snapshot> {
+        let name = path.file_name()?.to_str()?;
+        let stamp = name.get(..15)?;
+        let taken_at = NaiveDateTime::parse_from_str(stamp, NAME_FORMAT).ok()?;
+        let rest = name[15..].strip_prefix('-').unwrap_or_default();
+        // A second snapshot taken in the same second carries a counter after the reason
+        let reason = SnapshotReason::parse(rest).or_else(|| {
+            rest.rsplit_once('-')
+                .and_then(|(reason, _)| SnapshotReason::parse(reason))
+        });
+        Some(Snapshot {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            taken_at,
+            reason,
+        })
+    }
+
+    /// Bytes the snapshot takes on disk.
+    pub fn size(&self) -> u64 {
+        dir_size(&self.path)
+    }
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Snapshots in the backups directory, newest first.
+pub fn list_snapshots() -> Result<Vec<Snapshot>> {
+    read_snapshots(&get_backups_dir()?)
+}
+
+fn read_snapshots(dir: &Path) -> Result<Vec<Snapshot>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut snapshots: Vec<(Snapshot, Option<SystemTime>)> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read backups directory {dir:?}"))?
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let modified = entry.metadata().and_then(|meta| meta.modified()).ok();
+            Snapshot::from_dir(&entry.path()).map(|snapshot| (snapshot, modified))
+        })
+        .collect();
+    // Names only resolve to the second; snapshots taken within one are told apart by when
+    // they were written
+    snapshots.sort_by(|(a, a_modified), (b, b_modified)| {
+        (b.taken_at, b_modified, &b.name).cmp(&(a.taken_at, a_modified, &a.name))
+    });
+    Ok(snapshots.into_iter().map(|(snapshot, _)| snapshot).collect())
+}
+
+/// The snapshot called `name`, or the only one whose name starts with it. A path to a
+/// snapshot directory outside the backups directory works too.
+pub fn find_snapshot(name: &str) -> Result<Snapshot> {
+    let path = Path::new(name);
+    if path.components().count() > 1 && path.is_dir() {
+        return Snapshot::from_dir(path)
+            .ok_or_else(|| anyhow!("{path:?} is not a totui snapshot"));
+    }
+
+    let snapshots = list_snapshots()?;
+    if let Some(snapshot) = snapshots.iter().find(|snapshot| snapshot.name == name) {
+        return Ok(snapshot.clone());
+    }
+    let mut matches = snapshots
+        .into_iter()
+        .filter(|snapshot| snapshot.name.starts_with(name));
+    match (matches.next(), matches.next()) {
+        (Some(snapshot), None) => Ok(snapshot),
+        (Some(_), Some(_)) => Err(anyhow!("'{name}' matches more than one snapshot")),
+        (None, _) => Err(anyhow!("No snapshot named '{name}'")),
+    }
+}
+
+/// Snapshot the database, daily files and project list files, then delete the oldest
+/// snapshots beyond the configured number.
+pub fn create_snapshot(reason: SnapshotReason) -> Result<Snapshot> {
+    let conn = get_connection()?;
+    snapshot_connection(&conn, reason)
+}
+
+/// `create_snapshot` through a connection the caller already holds, such as one that is
+/// about to be migrated.
+pub(crate) fn snapshot_connection(conn: &Connection, reason: SnapshotReason) -> Result<Snapshot> {
+    let backups_dir = get_backups_dir()?;
+    let snapshot = write_snapshot(conn, &get_to_tui_dir()?, &backups_dir, reason, Local::now().naive_local())?;
+    prune_snapshots(&backups_dir, installed_config().keep, None)?;
+    Ok(snapshot)
+}
+
+fn write_snapshot(
+    conn: &Connection,
+    data_dir: &Path,
+    backups_dir: &Path,
+    reason: SnapshotReason,
+    taken_at: NaiveDateTime,
+) -> Result<Snapshot> {
+    fs::create_dir_all(backups_dir)
+        .with_context(|| format!("Failed to create backups directory {backups_dir:?}"))?;
+
+    let base = format!("{}-{}", taken_at.format(NAME_FORMAT), reason.as_str());
+    let mut name = base.clone();
+    let mut counter = 1;
+    while backups_dir.join(&name).exists() {
+        counter += 1;
+        name = format!("{base}-{counter}");
+    }
+
+    // Written under a hidden name and renamed when complete, so a snapshot that failed
+    // halfway is never listed or restored
+    let partial = backups_dir.join(format!(".{name}.partial"));
+    if partial.exists() {
+        fs::remove_dir_all(&partial)?;
+    }
+    fs::create_dir(&partial)?;
+    conn.backup(MAIN_DB, partial.join(DATABASE_FILE), None)
+        .context("Failed to back up the database")?;
+    for dir in DATA_DIRS {
+        let from = data_dir.join(dir);
+        if from.is_dir() {
+            copy_dir(&from, &partial.join(dir))?;
+        }
+    }
+
+    let path = backups_dir.join(&name);
+    fs::rename(&partial, &path)?;
+    Snapshot::from_dir(&path).ok_or_else(|| anyhow!("Snapshot {name} was written under a bad name"))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)
+                .with_context(|| format!("Failed to copy {:?}", entry.path()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Delete the oldest snapshots in `dir` so that at most `keep` remain, never `protected`.
+/// Returns the deleted snapshots.
+fn prune_snapshots(dir: &Path, keep: usize, protected: Option<&Path>) -> Result<Vec<Snapshot>> {
+    if keep == 0 {
+        return Ok(Vec::new());
+    }
+    let expired: Vec<Snapshot> = read_snapshots(dir)?
+        .into_iter()
+        .skip(keep)
+        .filter(|snapshot| Some(snapshot.path.as_path()) != protected)
+        .collect();
+    for snapshot in &expired {
+        fs::remove_dir_all(&snapshot.path)
+            .with_context(|| format!("Failed to delete old snapshot {}", snapshot.name))?;
+    }
+    Ok(expired)
+}
+
+/// Replace the database, daily files and project list files with those of `snapshot`,
+/// after taking a snapshot of the current state. The restored database is brought up to
+/// this build's schema. Returns the snapshot of the state that was replaced.
+pub fn restore_snapshot(snapshot: &Snapshot) -> Result<Snapshot> {
+    check_restorable(&snapshot.path)?;
+
+    let mut conn = get_connection()?;
+    let backups_dir = get_backups_dir()?;
+    let previous = write_snapshot(
+        &conn,
+        &get_to_tui_dir()?,
+        &backups_dir,
+        SnapshotReason::Restore,
+        Local::now().naive_local(),
+    )?;
+    restore_into(&mut conn, &get_to_tui_dir()?, &snapshot.path)?;
+    prune_snapshots(&backups_dir, installed_config().keep, Some(&snapshot.path))?;
+    Ok(previous)
+}
+
+/// Fail unless `snapshot_dir` holds a database this build can open.
+fn check_restorable(snapshot_dir: &Path) -> Result<()> {
+    let db_path = snapshot_dir.join(DATABASE_FILE);
+    if !db_path.is_file() {
+        bail!("{snapshot_dir:?} has no {DATABASE_FILE}");
+    }
+    let conn = Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open {db_path:?}"))?;
+    let version = schema_version(&conn)?;
+    let latest = latest_version();
+    if version > latest {
+        bail!(
+            "The snapshot has schema version {version}, newer than this build supports \
+             ({latest}). Upgrade totui before restoring it."
+        );
+    }
+    Ok(())
+}
+
+fn restore_into(conn: &mut Connection, data_dir: &Path, snapshot_dir: &Path) -> Result<()> {
+    check_restorable(snapshot_dir)?;
+    conn.restore(
+        MAIN_DB,
+        snapshot_dir.join(DATABASE_FILE),
+        None::<fn(Progress)>,
+    )
+    .context("Failed to restore the database")?;
+    migrate(conn)?;
+
+    for dir in DATA_DIRS {
+        let target = data_dir.join(dir);
+        if target.exists() {
+            fs::remove_dir_all(&target)
+                .with_context(|| format!("Failed to clear {target:?}"))?;
+        }
+        let from = snapshot_dir.join(dir);
+        if from.is_dir() {
+            copy_dir(&from, &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate};
+    use tempfile::TempDir;
+
+    fn at(seconds: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, 1)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            + Duration::seconds(seconds)
+    }
+
+    fn list_count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM lists", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_restore_brings_back_database_and_files() {
+        let data_dir = TempDir::new().unwrap();
+        let backups_dir = data_dir.path().join("backups");
+        let dailies = data_dir.path().join("dailies");
+        fs::create_dir_all(&dailies).unwrap();
+        fs::write(dailies.join("2025-03-01.md"), "- [ ] Plan\n").unwrap();
+
+        let mut conn = Connection::open(data_dir.path().join(DATABASE_FILE)).unwrap();
+        migrate(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO lists (name, created_at) VALUES ('backlog', '2025-03-01T09:00:00Z')",
+            [],
+        )
+        .unwrap();
+
+        let snapshot = write_snapshot(&conn, data_dir.path(), &backups_dir, SnapshotReason::Manual, at(0)).unwrap();
+        assert_eq!(snapshot.name, "20250301-090000-manual");
+        assert_eq!(snapshot.reason, Some(SnapshotReason::Manual));
+        assert!(snapshot.path.join("dailies/2025-03-01.md").is_file());
+
+        conn.execute("DELETE FROM lists", []).unwrap();
+        fs::write(dailies.join("2025-03-01.md"), "- [x] Plan\n").unwrap();
+        fs::write(dailies.join("2025-03-02.md"), "- [ ] Ship\n").unwrap();
+
+        restore_into(&mut conn, data_dir.path(), &snapshot.path).unwrap();
+        assert_eq!(list_count(&conn), 1);
+        assert_eq!(fs::read_to_string(dailies.join("2025-03-01.md")).unwrap(), "- [ ] Plan\n");
+        assert!(!dailies.join("2025-03-02.md").exists());
+
+        conn.pragma_update(None, "user_version", latest_version() + 1).unwrap();
+        let newer = write_snapshot(&conn, data_dir.path(), &backups_dir, SnapshotReason::Manual, at(1)).unwrap();
+        assert!(restore_into(&mut conn, data_dir.path(), &newer.path).is_err());
+    }
+
+    #[test]
+    fn test_prune_keeps_the_newest_snapshots() {
+        let data_dir = TempDir::new().unwrap();
+        let backups_dir = data_dir.path().join("backups");
+        let conn = Connection::open_in_memory().unwrap();
+
+        let oldest = write_snapshot(&conn, data_dir.path(), &backups_dir, SnapshotReason::Migration, at(0)).unwrap();
+        write_snapshot(&conn, data_dir.path(), &backups_dir, SnapshotReason::Import, at(60)).unwrap();
+        write_snapshot(&conn, data_dir.path(), &backups_dir, SnapshotReason::Manual, at(120)).unwrap();
+        let same_second = write_snapshot(&conn, data_dir.path(), &backups_dir, SnapshotReason::Manual, at(120)).unwrap();
+        assert_eq!(same_second.name, "20250301-090200-manual-2");
+        assert_eq!(same_second.reason, Some(SnapshotReason::Manual));
+
+        let removed = prune_snapshots(&backups_dir, 2, Some(&oldest.path)).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].reason, Some(SnapshotReason::Import));
+
+        let names: Vec<String> = read_snapshots(&backups_dir)
+            .unwrap()
+            .into_iter()
+            .map(|snapshot| snapshot.name)
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "20250301-090200-manual-2",
+                "20250301-090200-manual",
+                "20250301-090000-pre-migrate",
+            ]
+        );
+        assert!(prune_snapshots(&backups_dir, 0, None).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+use super::database::{get_connection, resolve_origin_id};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, params};
+use uuid::Uuid;
+
+/// A note appended to a todo's thread. Comments are never edited or removed.
+#[derive(Debug, Clone)]
+pub struct Comment {
+    pub id: i64,
+    /// Lineage id of the todo, so the thread follows the task across rollover.
+    pub todo_id: Uuid,
+    pub author: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+pub(crate) fn insert_comment(
+    conn: &Connection,
+    origin_id: Uuid,
+    author: &str,
+    body: &str,
+) -> Result<Comment> {
+    let created_at = Utc::now();
+    conn.execute(
+        "INSERT INTO comments (todo_id, author, body, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![origin_id.to_string(), author, body, created_at.to_rfc3339()],
+    )?;
+
+    Ok(Comment {
+        id: conn.last_insert_rowid(),
+        todo_id: origin_id,
+        author: author.to_string(),
+        body: body.to_string(),
+        created_at,
+    })
+}
+
+pub(crate) fn query_comments(conn: &Connection, origin_id: Uuid) -> Result<Vec<Comment>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, author, body, created_at FROM comments WHERE todo_id = ?1 ORDER BY created_at, id",
+    )?;
+
+    let rows = stmt.query_map([origin_id.to_string()], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+        ))
+    })?;
+
+    let mut comments = Vec::new();
+    for row in rows {
+        let (id, author, body, created_at) = row?;
+        let Ok(created_at) = DateTime::parse_from_rfc3339(&created_at) else {
+            continue;
+        };
+        comments.push(Comment {
+            id,
+            todo_id: origin_id,
+            author,
+            body,
+            created_at: created_at.with_timezone(&Utc),
+        });
+    }
+
+    Ok(comments)
+}
+
+/// Append a comment to the thread of any occurrence of a todo.
+/// Returns `None` if no todo with this id is stored.
+pub fn add_comment(todo_id: Uuid, author: &str, body: &str) -> Result<Option<Comment>> {
+    let conn = get_connection()?;
+    let Some(origin) = resolve_origin_id(&conn, todo_id)? else {
+        return Ok(None);
+    };
+    insert_comment(&conn, origin, author, body).map(Some)
+}
+
+/// The comment thread of a todo, oldest first.
+/// Returns `None` if no todo with this id is stored.
+pub fn load_comments(todo_id: Uuid) -> Result<Option<Vec<Comment>>> {
+    let conn = get_connection()?;
+    let Some(origin) = resolve_origin_id(&conn, todo_id)? else {
+        return Ok(None);
+    };
+    query_comments(&conn, origin).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comments_are_appended_in_order() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE comments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                todo_id TEXT NOT NULL,
+                author TEXT NOT NULL,
+                body TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+        let origin = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        insert_comment(&conn, origin, "alice", "Asked finance for the numbers").unwrap();
+        insert_comment(&conn, other, "bob", "Unrelated").unwrap();
+        let second = insert_comment(&conn, origin, "agent", "Numbers received").unwrap();
+
+        let thread = query_comments(&conn, origin).unwrap();
+        assert_eq!(thread.len(), 2);
+        assert_eq!(thread[0].author, "alice");
+        assert_eq!(thread[1].body, "Numbers received");
+        assert_eq!(thread[1].id, second.id);
+        assert!(query_comments(&conn, Uuid::new_v4()).unwrap().is_empty());
+    }
+}
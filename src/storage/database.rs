@@ -1,9 +1,18 @@
-use crate::todo::{TodoItem, TodoList, TodoState};
-use crate::utils::paths::get_to_tui_dir;
-use anyhow::{Context, Result};
+use super::backup::{SnapshotReason, snapshot_connection};
+use super::events::{Event, EventKind, Whereabouts, diff_events, insert_events};
+use super::migrations::{Migration, migrate, pending_migrations};
+use super::time_tracking::sync_time_entries;
+use super::trash::TrashedTodo;
+use crate::todo::{Recurrence, TodoItem, TodoList, TodoState};
+use crate::utils::paths::get_database_path;
+use anyhow::{Context, Result, anyhow};
 use chrono::{DateTime, NaiveDate, Utc};
-use rusqlite::{params, Connection};
-use std::path::PathBuf;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use std::collections::{HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
 use uuid::Uuid;
 
 /// Parse an RFC3339 timestamp string into a DateTime<Utc>
@@ -13,11 +22,6 @@ fn parse_rfc3339(s: &str) -> Option<DateTime<Utc>> {
         .map(|dt| dt.with_timezone(&Utc))
 }
 
-fn get_db_path() -> Result<PathBuf> {
-    let dir = get_to_tui_dir()?;
-    Ok(dir.join("todos.db"))
-}
-
 /// Raw data extracted from a database row before conversion to TodoItem
 struct TodoRowData {
     id_str: String,
@@ -32,6 +36,14 @@ struct TodoRowData {
     updated_at_str: Option<String>,
     completed_at_str: Option<String>,
     deleted_at_str: Option<String>,
+    tags_str: Option<String>,
+    recurrence_str: Option<String>,
+    series_id_str: Option<String>,
+    origin_id_str: Option<String>,
+    carry_count: i64,
+    blocked_by_str: Option<String>,
+    start_date_str: Option<String>,
+    section: Option<String>,
 }
 
 impl TodoRowData {
@@ -50,13 +62,22 @@ impl TodoRowData {
             updated_at_str: row.get(9).ok(),
             completed_at_str: row.get(10).ok().flatten(),
             deleted_at_str: row.get(11).ok().flatten(),
+            tags_str: row.get(12).ok().flatten(),
+            recurrence_str: row.get(13).ok().flatten(),
+            series_id_str: row.get(14).ok().flatten(),
+            origin_id_str: row.get(15).ok().flatten(),
+            carry_count: row.get(16).unwrap_or(0),
+            blocked_by_str: row.get(17).ok().flatten(),
+            start_date_str: row.get(18).ok().flatten(),
+            section: row.get(19).ok().flatten(),
         })
     }
 
     fn into_todo_item(self) -> TodoItem {
         let id = Uuid::parse_str(&self.id_str).unwrap_or_else(|_| Uuid::new_v4());
-        let state = TodoState::from_char(self.state_str.chars().next().unwrap_or(' '))
-            .unwrap_or(TodoState::Empty);
+        // Keep markers of states that are no longer configured rather than losing them
+        let state_char = self.state_str.chars().next().unwrap_or(' ');
+        let state = TodoState::from_char(state_char).unwrap_or(TodoState::Custom(state_char));
         let parent_id = self.parent_id_str.and_then(|s| Uuid::parse_str(&s).ok());
         let due_date = self
             .due_date_str
@@ -69,6 +90,22 @@ impl TodoRowData {
         todo.due_date = due_date;
         todo.description = self.description;
         todo.collapsed = self.collapsed != 0;
+        todo.tags = self
+            .tags_str
+            .map(|s| s.split(' ').filter(|t| !t.is_empty()).map(String::from).collect())
+            .unwrap_or_default();
+        todo.recurrence = self.recurrence_str.and_then(|s| Recurrence::parse(&s));
+        todo.series_id = self.series_id_str.and_then(|s| Uuid::parse_str(&s).ok());
+        todo.origin_id = self.origin_id_str.and_then(|s| Uuid::parse_str(&s).ok());
+        todo.carry_count = self.carry_count.max(0) as u32;
+        todo.blocked_by = self
+            .blocked_by_str
+            .map(|s| s.split(',').filter_map(|id| Uuid::parse_str(id).ok()).collect())
+            .unwrap_or_default();
+        todo.start_date = self
+            .start_date_str
+            .and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok());
+        todo.section = self.section;
 
         if let Some(s) = self.created_at_str
             && let Some(dt) = parse_rfc3339(&s) {
@@ -89,115 +126,157 @@ impl TodoRowData {
     }
 }
 
-pub fn get_connection() -> Result<Connection> {
-    let db_path = get_db_path()?;
-    let conn = Connection::open(&db_path)
-        .with_context(|| format!("Failed to open database at {db_path:?}"))?;
-    Ok(conn)
+/// How long a statement waits for another process's write lock before failing. The TUI,
+/// the API server and `totui-mcp` all write to the same file.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Idle connections kept open for reuse; overlapping calls open more as needed.
+const MAX_IDLE_CONNECTIONS: usize = 4;
+
+/// Open connections to the database at `path` that are not in use.
+struct Pool {
+    path: PathBuf,
+    idle: Vec<Connection>,
 }
 
-pub fn init_database() -> Result<()> {
-    let conn = get_connection()?;
+/// Connections to one database at a time, reused across calls. Switching to another
+/// database closes the idle connections to the old one.
+struct ConnectionPool {
+    pool: Mutex<Option<Pool>>,
+}
 
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS todos (
-            id TEXT PRIMARY KEY,
-            date TEXT NOT NULL,
-            content TEXT NOT NULL,
-            state TEXT NOT NULL,
-            indent_level INTEGER NOT NULL,
-            parent_id TEXT,
-            due_date TEXT,
-            description TEXT,
-            collapsed INTEGER NOT NULL DEFAULT 0,
-            position INTEGER NOT NULL,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL,
-            completed_at TEXT,
-            deleted_at TEXT
-        )",
-        [],
-    )?;
+impl ConnectionPool {
+    const fn new() -> Self {
+        Self {
+            pool: Mutex::new(None),
+        }
+    }
 
-    conn.execute(
-        "ALTER TABLE todos ADD COLUMN collapsed INTEGER NOT NULL DEFAULT 0",
-        [],
-    )
-    .ok();
+    /// Borrow an idle connection to the database at `path`, or open one.
+    fn checkout(&self, path: &Path) -> Result<PooledConnection<'_>> {
+        let idle = {
+            let mut pool = lock(&self.pool);
+            match pool.as_mut() {
+                Some(pool) if pool.path == path => pool.idle.pop(),
+                // The data directory changed; connections to the old one are closed
+                _ => {
+                    *pool = Some(Pool {
+                        path: path.to_path_buf(),
+                        idle: Vec::new(),
+                    });
+                    None
+                }
+            }
+        };
+        let conn = match idle {
+            Some(conn) => conn,
+            None => open_connection(path)?,
+        };
+        Ok(PooledConnection {
+            conn: Some(conn),
+            path: path.to_path_buf(),
+            owner: self,
+        })
+    }
 
-    conn.execute("ALTER TABLE todos ADD COLUMN completed_at TEXT", [])
-        .ok();
+    fn give_back(&self, path: &Path, conn: Connection) {
+        let mut pool = lock(&self.pool);
+        if let Some(pool) = pool.as_mut()
+            && pool.path == path
+            && pool.idle.len() < MAX_IDLE_CONNECTIONS
+        {
+            pool.idle.push(conn);
+        }
+    }
+}
 
-    conn.execute("ALTER TABLE todos ADD COLUMN deleted_at TEXT", [])
-        .ok();
+static POOL: ConnectionPool = ConnectionPool::new();
 
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_todos_date ON todos(date)",
-        [],
-    )?;
+/// Database whose schema this process already brought up to date.
+static MIGRATED: Mutex<Option<PathBuf>> = Mutex::new(None);
 
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_todos_parent_id ON todos(parent_id)",
-        [],
-    )?;
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // Nothing is left half-updated while these locks are held, so a panic elsewhere is harmless
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
 
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS archived_todos (
-            id TEXT PRIMARY KEY,
-            original_date TEXT NOT NULL,
-            archived_at TEXT NOT NULL,
-            content TEXT NOT NULL,
-            state TEXT NOT NULL,
-            indent_level INTEGER NOT NULL,
-            parent_id TEXT,
-            due_date TEXT,
-            description TEXT,
-            collapsed INTEGER NOT NULL DEFAULT 0,
-            position INTEGER NOT NULL,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL,
-            completed_at TEXT,
-            deleted_at TEXT
-        )",
-        [],
-    )?;
+/// A connection borrowed from a pool. It goes back to the pool when dropped.
+pub struct PooledConnection<'a> {
+    conn: Option<Connection>,
+    path: PathBuf,
+    owner: &'a ConnectionPool,
+}
 
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_archived_todos_original_date ON archived_todos(original_date)",
-        [],
-    )?;
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
 
-    conn.execute(
-        "ALTER TABLE archived_todos ADD COLUMN completed_at TEXT",
-        [],
-    )
-    .ok();
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection is only taken when dropped")
+    }
+}
 
-    conn.execute("ALTER TABLE archived_todos ADD COLUMN deleted_at TEXT", [])
-        .ok();
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection is only taken when dropped")
+    }
+}
 
-    Ok(())
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.owner.give_back(&self.path, conn);
+        }
+    }
 }
 
-pub fn load_todos_for_date(date: NaiveDate) -> Result<Vec<TodoItem>> {
-    let conn = get_connection()?;
-    let date_str = date.format("%Y-%m-%d").to_string();
+/// Open a connection in WAL mode, so readers do not block the writer, and make it wait for
+/// locks held by other processes instead of failing right away.
+fn open_connection(path: &Path) -> Result<Connection> {
+    let conn = Connection::open(path)
+        .with_context(|| format!("Failed to open database at {path:?}"))?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    let _mode: String = conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    conn.set_prepared_statement_cache_capacity(64);
+    Ok(conn)
+}
 
-    let mut stmt = conn.prepare(
-        "SELECT id, content, state, indent_level, parent_id, due_date, description, collapsed, created_at, updated_at, completed_at, deleted_at
-         FROM todos
-         WHERE date = ?1 AND deleted_at IS NULL
-         ORDER BY position ASC",
-    )?;
+/// A connection to the database of the current data directory, reused across calls.
+pub fn get_connection() -> Result<PooledConnection<'static>> {
+    POOL.checkout(&get_database_path()?)
+}
 
-    let items = stmt.query_map([&date_str], TodoRowData::from_row)?;
+/// Apply any pending schema migrations, once per process.
+pub fn init_database() -> Result<()> {
+    let db_path = get_database_path()?;
+    let mut migrated = lock(&MIGRATED);
+    if migrated.as_ref() == Some(&db_path) {
+        return Ok(());
+    }
+    let mut conn = POOL.checkout(&db_path)?;
+    migrate_with_snapshot(&mut conn)?;
+    *migrated = Some(db_path);
+    Ok(())
+}
 
-    let mut result = Vec::new();
-    for item in items {
-        result.push(item?.into_todo_item());
+/// Apply pending schema migrations, taking a snapshot first if the database already holds
+/// data. Returns the steps that were applied.
+pub fn migrate_with_snapshot(conn: &mut Connection) -> Result<Vec<&'static Migration>> {
+    let has_tables: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table')",
+        [],
+        |row| row.get(0),
+    )?;
+    if has_tables && !pending_migrations(conn)?.is_empty() {
+        snapshot_connection(conn, SnapshotReason::Migration)
+            .context("Failed to take a snapshot before migrating")?;
     }
+    migrate(conn)
+}
 
-    Ok(result)
+pub fn load_todos_for_date(date: NaiveDate) -> Result<Vec<TodoItem>> {
+    let conn = get_connection()?;
+    query_todo_rows(&conn, "todos", "date", &date.format("%Y-%m-%d").to_string())
 }
 
 pub fn soft_delete_todos(ids: &[Uuid], date: NaiveDate) -> Result<()> {
@@ -209,63 +288,645 @@ pub fn soft_delete_todos(ids: &[Uuid], date: NaiveDate) -> Result<()> {
     let date_str = date.format("%Y-%m-%d").to_string();
     let now = chrono::Utc::now().to_rfc3339();
 
+    let stored = query_todo_rows(&conn, "todos", "date", &date_str)?;
+    let mut events = Vec::new();
     for id in ids {
         let id_str = id.to_string();
         conn.execute(
             "UPDATE todos SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2 AND date = ?3",
             params![now, id_str, date_str],
         )?;
+        if let Some(item) = stored.iter().find(|item| item.id == *id) {
+            events.push(Event::new(EventKind::Delete, item, &date_str));
+        }
     }
+    insert_events(&conn, &events)?;
 
     Ok(())
 }
 
-pub fn save_todo_list(list: &TodoList) -> Result<()> {
+/// A save was refused because another writer saved the same list after it was loaded.
+#[derive(Debug)]
+pub struct SaveConflict {
+    pub list: String,
+    pub expected: u64,
+    pub found: u64,
+}
+
+impl std::fmt::Display for SaveConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} was changed by another writer (revision {}, expected {}); reload and try again",
+            self.list, self.found, self.expected
+        )
+    }
+}
+
+impl std::error::Error for SaveConflict {}
+
+fn revision_key(list: &TodoList) -> String {
+    match list.name {
+        Some(ref name) => format!("list:{name}"),
+        None => list.date.format("%Y-%m-%d").to_string(),
+    }
+}
+
+fn query_revision(conn: &Connection, key: &str) -> Result<u64> {
+    let revision: Option<i64> = conn
+        .query_row(
+            "SELECT revision FROM list_revisions WHERE list_key = ?1",
+            [key],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(revision.unwrap_or(0).max(0) as u64)
+}
+
+/// Current stored revision of the day or project list `list` refers to. Read it before
+/// loading the items, so a save in between shows up as a conflict rather than being lost.
+pub fn load_revision(list: &TodoList) -> Result<u64> {
     let conn = get_connection()?;
+    query_revision(&conn, &revision_key(list))
+}
+
+/// Check that the stored list is still at `list.revision` and bump it. Run inside the save
+/// transaction, which is opened immediately so the check and the write cannot interleave
+/// with another writer.
+fn bump_revision(conn: &Connection, list: &TodoList) -> Result<u64> {
+    let key = revision_key(list);
+    let found = query_revision(conn, &key)?;
+    if found != list.revision {
+        return Err(SaveConflict {
+            list: list.title(),
+            expected: list.revision,
+            found,
+        }
+        .into());
+    }
+
+    let revision = found + 1;
+    conn.execute(
+        "INSERT INTO list_revisions (list_key, revision) VALUES (?1, ?2)
+         ON CONFLICT(list_key) DO UPDATE SET revision = excluded.revision",
+        params![key, revision as i64],
+    )?;
+    Ok(revision)
+}
+
+/// Save a daily list and its notes. Fails with `SaveConflict` if the day was saved by
+/// someone else since `list` was loaded. Returns the new revision.
+pub fn save_todo_list(list: &TodoList) -> Result<u64> {
+    let mut conn = get_connection()?;
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let revision = bump_revision(&tx, list)?;
     let date_str = list.date.format("%Y-%m-%d").to_string();
 
+    let day_origins = sync_todo_rows(&tx, "todos", "date", &date_str, list)?;
+    save_notes(&tx, list)?;
+    trash_dropped_scheduled(&tx, &date_str, list)?;
+
+    // Saving a scheduled item into a day claims it from the scheduled area
+    {
+        let mut unschedule = tx.prepare_cached("DELETE FROM scheduled_todos WHERE id = ?1")?;
+        for item in &list.items {
+            unschedule.execute([item.id.to_string()])?;
+        }
+    }
+
+    sync_time_entries(&tx, &day_origins, list)?;
+    tx.commit()?;
+
+    Ok(revision)
+}
+
+/// Move scheduled items that were merged into `list` and deleted from it before it was saved
+/// out of the scheduled area and into the trash of the list's day.
+fn trash_dropped_scheduled(conn: &Connection, date_str: &str, list: &TodoList) -> Result<()> {
+    let kept: HashSet<Uuid> = list.items.iter().map(|item| item.id).collect();
+    let dropped: Vec<&TodoItem> = list
+        .merged_scheduled
+        .iter()
+        .filter(|item| !kept.contains(&item.id))
+        .collect();
+    if dropped.is_empty() {
+        return Ok(());
+    }
+
+    let now = Utc::now().to_rfc3339();
+    let mut trash_row = conn.prepare_cached(
+        "INSERT OR IGNORE INTO todos (id, date, content, state, indent_level, parent_id, due_date, description, collapsed, position, created_at, updated_at, deleted_at, recurrence, series_id, origin_id, carry_count, section)
+         SELECT id, ?2, content, state, indent_level, parent_id, due_date, description, collapsed, ?3, created_at, ?4, ?4, recurrence, series_id, origin_id, carry_count, section
+         FROM scheduled_todos WHERE id = ?1",
+    )?;
+    let mut unschedule = conn.prepare_cached("DELETE FROM scheduled_todos WHERE id = ?1")?;
+    let mut events = Vec::new();
+    for item in dropped {
+        let id_str = item.id.to_string();
+        if trash_row.execute(params![id_str, date_str, list.items.len() as i64, now])? > 0 {
+            unschedule.execute([&id_str])?;
+            events.push(Event::new(EventKind::Delete, item, date_str));
+        }
+    }
+    insert_events(conn, &events)
+}
+
+/// Free-form notes stored with `list`, if any: those above the todos and those below them.
+pub fn load_notes(list: &TodoList) -> Result<(Option<String>, Option<String>)> {
+    let conn = get_connection()?;
+    query_notes(&conn, list)
+}
+
+fn query_notes(conn: &Connection, list: &TodoList) -> Result<(Option<String>, Option<String>)> {
+    let notes: Option<(String, Option<String>)> = conn
+        .query_row(
+            "SELECT notes, trailing_notes FROM list_notes WHERE list_key = ?1",
+            [revision_key(list)],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    Ok(match notes {
+        Some((above, below)) => ((!above.is_empty()).then_some(above), below),
+        None => (None, None),
+    })
+}
+
+fn save_notes(conn: &Connection, list: &TodoList) -> Result<()> {
+    if list.notes.is_none() && list.trailing_notes.is_none() {
+        conn.execute("DELETE FROM list_notes WHERE list_key = ?1", [revision_key(list)])?;
+        return Ok(());
+    }
     conn.execute(
-        "DELETE FROM todos WHERE date = ?1 AND deleted_at IS NULL",
-        [&date_str],
+        "INSERT INTO list_notes (list_key, notes, trailing_notes) VALUES (?1, ?2, ?3)
+         ON CONFLICT(list_key) DO UPDATE SET notes = excluded.notes, trailing_notes = excluded.trailing_notes",
+        params![revision_key(list), list.notes.as_deref().unwrap_or_default(), list.trailing_notes],
     )?;
+    Ok(())
+}
+
+/// Markdown last written to a list's file, and the ids of its items in file order.
+#[derive(Debug, Clone)]
+pub struct MarkdownSnapshot {
+    pub content: String,
+    pub item_ids: Vec<Uuid>,
+}
 
-    let mut stmt = conn.prepare(
-        "INSERT INTO todos (id, date, content, state, indent_level, parent_id, due_date, description, collapsed, position, created_at, updated_at, completed_at, deleted_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)"
+pub fn load_markdown_snapshot(list: &TodoList) -> Result<Option<MarkdownSnapshot>> {
+    let conn = get_connection()?;
+    let row: Option<(String, String)> = conn
+        .query_row(
+            "SELECT content, item_ids FROM markdown_snapshots WHERE list_key = ?1",
+            [revision_key(list)],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    Ok(row.map(|(content, ids)| MarkdownSnapshot {
+        content,
+        item_ids: ids.split(',').filter_map(|id| Uuid::parse_str(id).ok()).collect(),
+    }))
+}
+
+/// Remember `content` as written to the file of `list`, whose items are in file order.
+pub fn save_markdown_snapshot(list: &TodoList, content: &str) -> Result<()> {
+    let conn = get_connection()?;
+    let ids: Vec<String> = list.items.iter().map(|item| item.id.to_string()).collect();
+    conn.execute(
+        "INSERT INTO markdown_snapshots (list_key, content, item_ids) VALUES (?1, ?2, ?3)
+         ON CONFLICT(list_key) DO UPDATE SET content = excluded.content, item_ids = excluded.item_ids",
+        params![revision_key(list), content, ids.join(",")],
     )?;
+    Ok(())
+}
+
+/// Columns written for every item besides `id`, the key column and `created_at`, which
+/// keeps the value from the first insert.
+const TODO_COLUMNS: [&str; 17] = [
+    "content",
+    "state",
+    "indent_level",
+    "parent_id",
+    "due_date",
+    "description",
+    "collapsed",
+    "position",
+    "updated_at",
+    "completed_at",
+    "deleted_at",
+    "recurrence",
+    "series_id",
+    "origin_id",
+    "carry_count",
+    "start_date",
+    "section",
+];
+
+/// The columns `TodoRowData::from_row` reads, selected from `table`. Tags keep their written
+/// order and blockers are sorted, so every load path yields the same `blocked_by`.
+fn todo_columns(table: &str) -> String {
+    // Scheduled items are never finished or deleted while they wait
+    let finished = if table == "scheduled_todos" { "NULL, NULL" } else { "completed_at, deleted_at" };
+    format!(
+        "id, content, state, indent_level, parent_id, due_date, description, collapsed, created_at, updated_at, {finished},
+         (SELECT group_concat(tag, ' ') FROM (SELECT tag FROM todo_tags WHERE todo_id = {table}.id ORDER BY position)),
+         recurrence, series_id, origin_id, carry_count,
+         (SELECT group_concat(blocker_id) FROM (SELECT blocker_id FROM todo_dependencies WHERE todo_id = {table}.id ORDER BY blocker_id)),
+         start_date, section"
+    )
+}
+
+/// Live items of `table` whose `key_column` equals `key`, in list order.
+pub(crate) fn query_todo_rows(conn: &Connection, table: &str, key_column: &str, key: &str) -> Result<Vec<TodoItem>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}
+         FROM {table}
+         WHERE {key_column} = ?1 AND deleted_at IS NULL
+         ORDER BY position ASC",
+        todo_columns(table),
+    ))?;
+
+    let items = stmt.query_map([key], TodoRowData::from_row)?;
+
+    let mut result = Vec::new();
+    for item in items {
+        result.push(item?.into_todo_item());
+    }
+
+    Ok(result)
+}
+
+/// Where the todo `id` is live other than under `key` in `table`: a day, `list:<name>` or
+/// `scheduled:<start date>`.
+pub(crate) fn live_elsewhere(conn: &Connection, id: Uuid, table: &str, key: &str) -> Result<Option<String>> {
+    let location = conn
+        .query_row(
+            "SELECT date FROM todos
+             WHERE id = ?1 AND deleted_at IS NULL AND NOT (?2 = 'todos' AND date = ?3)
+             UNION ALL
+             SELECT 'list:' || list_name FROM list_todos
+             WHERE id = ?1 AND deleted_at IS NULL AND NOT (?2 = 'list_todos' AND list_name = ?3)
+             UNION ALL
+             SELECT 'scheduled:' || start_date FROM scheduled_todos WHERE id = ?1
+             LIMIT 1",
+            params![id.to_string(), table, key],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(location)
+}
+
+/// Latest day another occurrence of the task `origin` was on, if `id` continues an earlier one.
+fn previous_occurrence(conn: &Connection, id: Uuid, origin: Uuid) -> Result<Option<String>> {
+    let day = conn.query_row(
+        "SELECT MAX(day) FROM (
+             SELECT date AS day FROM todos WHERE COALESCE(origin_id, id) = ?1 AND id != ?2
+             UNION ALL
+             SELECT original_date FROM archived_todos WHERE COALESCE(origin_id, id) = ?1 AND id != ?2
+         )",
+        params![origin.to_string(), id.to_string()],
+        |row| row.get(0),
+    )?;
+    Ok(day)
+}
+
+/// Bring the live rows of `table` whose `key_column` equals `key` (the day for `todos`, the
+/// list name for `list_todos`) in line with `list`. Items are upserted by id and a row is
+/// only rewritten when one of its values differs, so unchanged items and items that did not
+/// move are left alone. Tags and dependencies are rewritten only when they changed. Rows that
+/// are no longer in the list are marked deleted, which puts them in the trash, unless the
+/// item now lives in another list or the scheduled area; an item saved again comes back out
+/// of the trash. Every change is recorded in the event log.
+///
+/// Returns the lineage ids stored under the key before the save, except for items that moved
+/// elsewhere.
+fn sync_todo_rows(
+    conn: &Connection,
+    table: &str,
+    key_column: &str,
+    key: &str,
+    list: &TodoList,
+) -> Result<HashSet<Uuid>> {
+    let stored = query_todo_rows(conn, table, key_column, key)?;
+    let stored_by_id: HashMap<Uuid, &TodoItem> = stored.iter().map(|item| (item.id, item)).collect();
+    let list_key = match table {
+        "list_todos" => format!("list:{key}"),
+        _ => key.to_string(),
+    };
+
+    // Work out where arriving and departing items come from and go to before any row moves
+    let mut whereabouts = Whereabouts::default();
+    for item in list.items.iter().filter(|item| !stored_by_id.contains_key(&item.id)) {
+        if let Some(from) = live_elsewhere(conn, item.id, table, key)? {
+            whereabouts.arrived.insert(item.id, from);
+        } else if item.origin() != item.id
+            && let Some(day) = previous_occurrence(conn, item.id, item.origin())?
+        {
+            whereabouts.rolled_from.insert(item.id, day);
+        }
+    }
+    let mut in_trash = conn.prepare_cached(&format!(
+        "SELECT 1 FROM {table} WHERE id = ?1 AND deleted_at IS NOT NULL"
+    ))?;
+    for item in list.items.iter().filter(|item| !stored_by_id.contains_key(&item.id)) {
+        if !whereabouts.arrived.contains_key(&item.id) && in_trash.exists([item.id.to_string()])? {
+            whereabouts.restored.insert(item.id);
+        }
+    }
+    let kept: HashSet<Uuid> = list.items.iter().map(|item| item.id).collect();
+    for item in stored.iter().filter(|item| !kept.contains(&item.id)) {
+        if let Some(to) = live_elsewhere(conn, item.id, table, key)? {
+            // A move between lists is logged once, when the item arrives
+            let logged_here = to.starts_with("scheduled:").then_some(to);
+            whereabouts.departed.insert(item.id, logged_here);
+        }
+    }
+
+    let events = diff_events(&list_key, &stored, &list.items, &whereabouts);
+    insert_events(conn, &events)?;
+
+    let updates = TODO_COLUMNS
+        .iter()
+        .map(|c| format!("{c} = excluded.{c}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let differs = TODO_COLUMNS
+        .iter()
+        .map(|c| format!("{table}.{c} IS NOT excluded.{c}"))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    let mut upsert = conn.prepare_cached(&format!(
+        "INSERT INTO {table} (id, {key_column}, created_at, {columns})
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)
+         ON CONFLICT(id) DO UPDATE SET {key_column} = excluded.{key_column}, {updates}
+         WHERE {table}.{key_column} IS NOT excluded.{key_column} OR {differs}",
+        columns = TODO_COLUMNS.join(", "),
+    ))?;
 
     for (position, item) in list.items.iter().enumerate() {
         let id_str = item.id.to_string();
-        let state_str = item.state.to_char().to_string();
-        let parent_id_str = item.parent_id.map(|id| id.to_string());
-        let due_date_str = item.due_date.map(|d| d.format("%Y-%m-%d").to_string());
-        let collapsed_int: i32 = if item.collapsed { 1 } else { 0 };
-        let created_at_str = item.created_at.to_rfc3339();
-        let modified_at_str = item.modified_at.to_rfc3339();
-        let completed_at_str = item.completed_at.map(|dt| dt.to_rfc3339());
-        let deleted_at_str = item.deleted_at.map(|dt| dt.to_rfc3339());
 
-        stmt.execute(params![
+        upsert.execute(params![
             id_str,
-            date_str,
+            key,
+            item.created_at.to_rfc3339(),
             item.content,
-            state_str,
+            item.state.to_char().to_string(),
             item.indent_level as i64,
-            parent_id_str,
-            due_date_str,
+            item.parent_id.map(|id| id.to_string()),
+            item.due_date.map(|d| d.format("%Y-%m-%d").to_string()),
             item.description,
-            collapsed_int,
+            item.collapsed as i32,
             position as i64,
-            created_at_str,
-            modified_at_str,
-            completed_at_str,
-            deleted_at_str,
+            item.modified_at.to_rfc3339(),
+            item.completed_at.map(|dt| dt.to_rfc3339()),
+            item.deleted_at.map(|dt| dt.to_rfc3339()),
+            item.recurrence.as_ref().map(|r| r.to_string()),
+            item.series_id.map(|id| id.to_string()),
+            item.origin_id.map(|id| id.to_string()),
+            item.carry_count as i64,
+            item.start_date.map(|d| d.format("%Y-%m-%d").to_string()),
+            item.section,
         ])?;
+
+        let mut blocked_by = item.blocked_by.clone();
+        blocked_by.sort();
+        let previous = stored_by_id.get(&item.id);
+        if previous.is_none_or(|row| row.tags != item.tags) {
+            save_tags(conn, &id_str, &item.tags)?;
+        }
+        if previous.is_none_or(|row| row.blocked_by != blocked_by) {
+            save_dependencies(conn, &id_str, &item.blocked_by)?;
+        }
+    }
+
+    // An item that moved elsewhere takes its tags and dependencies along; the others go to
+    // the trash with theirs
+    let now = Utc::now().to_rfc3339();
+    let mut drop_row = conn.prepare_cached(&format!("DELETE FROM {table} WHERE id = ?1"))?;
+    let mut trash_row = conn.prepare_cached(&format!(
+        "UPDATE {table} SET deleted_at = ?2, updated_at = ?2 WHERE id = ?1"
+    ))?;
+    for item in stored.iter().filter(|item| !kept.contains(&item.id)) {
+        let id_str = item.id.to_string();
+        if whereabouts.departed.contains_key(&item.id) {
+            drop_row.execute([&id_str])?;
+        } else {
+            trash_row.execute(params![id_str, now])?;
+        }
     }
 
+    // Timers of items that moved to another list keep running there
+    Ok(stored
+        .iter()
+        .filter(|item| !matches!(whereabouts.departed.get(&item.id), Some(None)))
+        .map(|item| item.origin())
+        .collect())
+}
+
+/// Register a project list so it shows up even before it has any items.
+pub fn create_named_list(name: &str) -> Result<()> {
+    let conn = get_connection()?;
+    conn.execute(
+        "INSERT OR IGNORE INTO lists (name, created_at) VALUES (?1, ?2)",
+        params![name, Utc::now().to_rfc3339()],
+    )?;
     Ok(())
 }
 
+/// Names of all project lists, alphabetically.
+pub fn load_list_names() -> Result<Vec<String>> {
+    let conn = get_connection()?;
+    let mut stmt = conn.prepare_cached("SELECT name FROM lists ORDER BY name COLLATE NOCASE")?;
+    let names = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(names)
+}
+
+pub fn has_named_list(name: &str) -> Result<bool> {
+    let conn = get_connection()?;
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM lists WHERE name = ?1",
+        [name],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+pub fn load_named_list_items(name: &str) -> Result<Vec<TodoItem>> {
+    let conn = get_connection()?;
+    query_todo_rows(&conn, "list_todos", "list_name", name)
+}
+
+/// Save the items and notes of the project list `list.name`. Like `save_todo_list`, fails with
+/// `SaveConflict` on a concurrent save and returns the new revision.
+pub fn save_named_list(list: &TodoList) -> Result<u64> {
+    let name = list
+        .name
+        .as_deref()
+        .ok_or_else(|| anyhow!("save_named_list called with a daily list"))?;
+    let mut conn = get_connection()?;
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let revision = bump_revision(&tx, list)?;
+
+    tx.execute(
+        "INSERT OR IGNORE INTO lists (name, created_at) VALUES (?1, ?2)",
+        params![name, Utc::now().to_rfc3339()],
+    )?;
+
+    let list_origins = sync_todo_rows(&tx, "list_todos", "list_name", name, list)?;
+    save_notes(&tx, list)?;
+
+    sync_time_entries(&tx, &list_origins, list)?;
+    tx.commit()?;
+
+    Ok(revision)
+}
+
+/// Store subtrees whose start date has not arrived yet (see `TodoList::take_scheduled`),
+/// replacing earlier copies.
+pub fn save_scheduled_todos(items: &[TodoItem]) -> Result<()> {
+    let conn = get_connection()?;
+
+    let mut stmt = conn.prepare_cached(
+        "INSERT OR REPLACE INTO scheduled_todos (id, start_date, content, state, indent_level, parent_id, due_date, description, collapsed, position, created_at, updated_at, recurrence, series_id, origin_id, carry_count, section)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9,
+                 (SELECT COALESCE(MAX(position), -1) + 1 FROM scheduled_todos),
+                 ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+    )?;
+
+    // Items arrive as subtrees rooted at indent level 0; subtasks wait with their root
+    let mut root_start = None;
+    for item in items {
+        if item.indent_level == 0 {
+            root_start = item.start_date;
+        }
+        let Some(start) = root_start else {
+            continue;
+        };
+        let id_str = item.id.to_string();
+
+        stmt.execute(params![
+            id_str,
+            start.format("%Y-%m-%d").to_string(),
+            item.content,
+            item.state.to_char().to_string(),
+            item.indent_level as i64,
+            item.parent_id.map(|id| id.to_string()),
+            item.due_date.map(|d| d.format("%Y-%m-%d").to_string()),
+            item.description,
+            item.collapsed as i32,
+            item.created_at.to_rfc3339(),
+            item.modified_at.to_rfc3339(),
+            item.recurrence.as_ref().map(|r| r.to_string()),
+            item.series_id.map(|id| id.to_string()),
+            item.origin_id.map(|id| id.to_string()),
+            item.carry_count as i64,
+            item.section,
+        ])?;
+
+        save_tags(&conn, &id_str, &item.tags)?;
+        save_dependencies(&conn, &id_str, &item.blocked_by)?;
+    }
+
+    Ok(())
+}
+
+/// Scheduled items starting between `from` and `to` (inclusive, either bound optional),
+/// ordered by start date. Subtasks follow their parent.
+pub fn load_scheduled_todos(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<Vec<TodoItem>> {
+    let conn = get_connection()?;
+    let from_str = from.map(|d| d.format("%Y-%m-%d").to_string());
+    let to_str = to.map(|d| d.format("%Y-%m-%d").to_string());
+
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}
+         FROM scheduled_todos
+         WHERE (?1 IS NULL OR start_date >= ?1) AND (?2 IS NULL OR start_date <= ?2)
+         ORDER BY start_date ASC, position ASC",
+        todo_columns("scheduled_todos"),
+    ))?;
+
+    let items = stmt.query_map(params![from_str, to_str], TodoRowData::from_row)?;
+
+    let mut result = Vec::new();
+    for item in items {
+        result.push(item?.into_todo_item());
+    }
+
+    Ok(result)
+}
+
+pub(crate) fn save_tags(conn: &Connection, todo_id: &str, tags: &[String]) -> Result<()> {
+    conn.prepare_cached("DELETE FROM todo_tags WHERE todo_id = ?1")?
+        .execute([todo_id])?;
+    let mut stmt = conn.prepare_cached(
+        "INSERT OR IGNORE INTO todo_tags (todo_id, tag, position) VALUES (?1, ?2, ?3)",
+    )?;
+    for (position, tag) in tags.iter().enumerate() {
+        stmt.execute(params![todo_id, tag, position as i64])?;
+    }
+    Ok(())
+}
+
+pub(crate) fn save_dependencies(conn: &Connection, todo_id: &str, blocked_by: &[Uuid]) -> Result<()> {
+    conn.prepare_cached("DELETE FROM todo_dependencies WHERE todo_id = ?1")?
+        .execute([todo_id])?;
+    let mut stmt = conn.prepare_cached(
+        "INSERT OR IGNORE INTO todo_dependencies (todo_id, blocker_id) VALUES (?1, ?2)",
+    )?;
+    for blocker_id in blocked_by {
+        stmt.execute(params![todo_id, blocker_id.to_string()])?;
+    }
+    Ok(())
+}
+
+/// Of the given blocker lineage ids, return those whose latest occurrence (live or archived)
+/// is not finished. Unknown ids are treated as resolved so a deleted blocker never blocks.
+pub fn load_open_blockers(blocker_ids: &[Uuid]) -> Result<HashSet<Uuid>> {
+    if blocker_ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let conn = get_connection()?;
+    query_open_blockers(&conn, blocker_ids)
+}
+
+fn query_open_blockers(conn: &Connection, blocker_ids: &[Uuid]) -> Result<HashSet<Uuid>> {
+    let mut open = HashSet::new();
+    let mut stmt = conn.prepare_cached(
+        "SELECT state FROM (
+            SELECT state, date AS day FROM todos
+            WHERE (id = ?1 OR origin_id = ?1) AND deleted_at IS NULL
+            UNION ALL
+            SELECT state, original_date AS day FROM archived_todos
+            WHERE (id = ?1 OR origin_id = ?1) AND deleted_at IS NULL
+            UNION ALL
+            SELECT state, start_date AS day FROM scheduled_todos
+            WHERE id = ?1 OR origin_id = ?1
+            UNION ALL
+            SELECT state, date('now', 'localtime') AS day FROM list_todos
+            WHERE (id = ?1 OR origin_id = ?1) AND deleted_at IS NULL
+         )
+         ORDER BY day DESC
+         LIMIT 1",
+    )?;
+
+    for blocker_id in blocker_ids {
+        let state_str: Option<String> = stmt
+            .query_row([blocker_id.to_string()], |row| row.get(0))
+            .optional()?;
+        if let Some(state_str) = state_str {
+            let state_char = state_str.chars().next().unwrap_or(' ');
+            let state = TodoState::from_char(state_char).unwrap_or(TodoState::Custom(state_char));
+            if !state.is_complete() {
+                open.insert(*blocker_id);
+            }
+        }
+    }
+
+    Ok(open)
+}
+
 pub fn has_todos_for_date(date: NaiveDate) -> Result<bool> {
     let conn = get_connection()?;
     let date_str = date.format("%Y-%m-%d").to_string();
@@ -281,17 +942,36 @@ pub fn has_todos_for_date(date: NaiveDate) -> Result<bool> {
 
 pub fn archive_todos_for_date(date: NaiveDate) -> Result<usize> {
     let conn = get_connection()?;
-    let date_str = date.format("%Y-%m-%d").to_string();
+    archive_day(&conn, &date.format("%Y-%m-%d").to_string())
+}
+
+/// Store `list`, read from the markdown file of a past day, as that day and move the day into
+/// the archive, inside the caller's transaction. The file wins over whatever the day held.
+/// Unlike a save this starts no time tracking, as a `[*]` todo of a past day is not being
+/// worked on now. Returns how many todos were archived.
+pub(crate) fn archive_imported_day(conn: &Connection, list: &mut TodoList) -> Result<usize> {
+    let date_str = list.date.format("%Y-%m-%d").to_string();
+    list.revision = query_revision(conn, &revision_key(list))?;
+    bump_revision(conn, list)?;
+    sync_todo_rows(conn, "todos", "date", &date_str, list)?;
+    save_notes(conn, list)?;
+    archive_day(conn, &date_str)
+}
+
+fn archive_day(conn: &Connection, date_str: &str) -> Result<usize> {
     let now = chrono::Utc::now().to_rfc3339();
 
     let count = conn.execute(
-        "INSERT INTO archived_todos (id, original_date, archived_at, content, state, indent_level, parent_id, due_date, description, collapsed, position, created_at, updated_at, completed_at, deleted_at)
-         SELECT id, date, ?1, content, state, indent_level, parent_id, due_date, description, collapsed, position, created_at, updated_at, completed_at, deleted_at
-         FROM todos WHERE date = ?2",
+        "INSERT INTO archived_todos (id, original_date, archived_at, content, state, indent_level, parent_id, due_date, description, collapsed, position, created_at, updated_at, completed_at, deleted_at, recurrence, series_id, origin_id, carry_count, start_date, section)
+         SELECT id, date, ?1, content, state, indent_level, parent_id, due_date, description, collapsed, position, created_at, updated_at, completed_at, deleted_at, recurrence, series_id, origin_id, carry_count, start_date, section
+         FROM todos WHERE date = ?2 AND deleted_at IS NULL",
         params![now, date_str],
     )?;
 
-    conn.execute("DELETE FROM todos WHERE date = ?1", [&date_str])?;
+    // Deleted todos stay behind in the trash
+    conn.execute("DELETE FROM todos WHERE date = ?1 AND deleted_at IS NULL", [date_str])?;
+    // The file of an archived day is no longer kept in sync
+    conn.execute("DELETE FROM markdown_snapshots WHERE list_key = ?1", [date_str])?;
 
     Ok(count)
 }
@@ -300,12 +980,13 @@ pub fn load_archived_todos_for_date(date: NaiveDate) -> Result<Vec<TodoItem>> {
     let conn = get_connection()?;
     let date_str = date.format("%Y-%m-%d").to_string();
 
-    let mut stmt = conn.prepare(
-        "SELECT id, content, state, indent_level, parent_id, due_date, description, collapsed, created_at, updated_at, completed_at, deleted_at
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}
          FROM archived_todos
          WHERE original_date = ?1 AND deleted_at IS NULL
          ORDER BY position ASC",
-    )?;
+        todo_columns("archived_todos"),
+    ))?;
 
     let items = stmt.query_map([&date_str], TodoRowData::from_row)?;
 
@@ -317,6 +998,163 @@ pub fn load_archived_todos_for_date(date: NaiveDate) -> Result<Vec<TodoItem>> {
     Ok(result)
 }
 
+/// Every deleted todo of the daily and project lists, most recently deleted first.
+pub fn load_trash() -> Result<Vec<TrashedTodo>> {
+    let conn = get_connection()?;
+    query_trash(&conn)
+}
+
+fn query_trash(conn: &Connection) -> Result<Vec<TrashedTodo>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}, date, NULL, position FROM todos WHERE deleted_at IS NOT NULL
+         UNION ALL
+         SELECT {}, NULL, list_name, position FROM list_todos WHERE deleted_at IS NOT NULL
+         ORDER BY deleted_at DESC, position ASC",
+        todo_columns("todos"),
+        todo_columns("list_todos"),
+    ))?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            TodoRowData::from_row(row)?,
+            row.get::<_, Option<String>>(20)?,
+            row.get::<_, Option<String>>(21)?,
+            row.get::<_, i64>(22)?,
+        ))
+    })?;
+
+    let mut trash = Vec::new();
+    for row in rows {
+        let (data, date, list, position) = row?;
+        trash.push(TrashedTodo {
+            item: data.into_todo_item(),
+            date: date.and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()),
+            list,
+            position: position.max(0) as usize,
+        });
+    }
+    Ok(trash)
+}
+
+/// Remove the deleted todos `ids` for good, with their tags and dependencies. Todos that are
+/// not in the trash are left alone. Returns how many were removed.
+pub fn purge_trash(ids: &[Uuid]) -> Result<usize> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let mut conn = get_connection()?;
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let purged = delete_trash_rows(&tx, ids)?;
+    tx.commit()?;
+    Ok(purged)
+}
+
+fn delete_trash_rows(conn: &Connection, ids: &[Uuid]) -> Result<usize> {
+    let trash = query_trash(conn)?;
+    let mut drop_row = conn.prepare_cached("DELETE FROM todos WHERE id = ?1 AND deleted_at IS NOT NULL")?;
+    let mut drop_list_row =
+        conn.prepare_cached("DELETE FROM list_todos WHERE id = ?1 AND deleted_at IS NOT NULL")?;
+    let mut drop_tags = conn.prepare_cached("DELETE FROM todo_tags WHERE todo_id = ?1")?;
+    let mut drop_dependencies = conn.prepare_cached("DELETE FROM todo_dependencies WHERE todo_id = ?1")?;
+
+    let mut events = Vec::new();
+    for entry in trash.iter().filter(|entry| ids.contains(&entry.item.id)) {
+        let id_str = entry.item.id.to_string();
+        if drop_row.execute([&id_str])? + drop_list_row.execute([&id_str])? == 0 {
+            continue;
+        }
+        drop_tags.execute([&id_str])?;
+        drop_dependencies.execute([&id_str])?;
+        let list_key = match entry.list {
+            Some(ref name) => format!("list:{name}"),
+            None => entry.origin_label(),
+        };
+        events.push(Event::new(EventKind::Purge, &entry.item, &list_key));
+    }
+    insert_events(conn, &events)?;
+
+    Ok(events.len())
+}
+
+/// Lineage id of the todo with `id`, looked up in the live, archived and scheduled tables.
+pub(crate) fn resolve_origin_id(conn: &Connection, id: Uuid) -> Result<Option<Uuid>> {
+    let origin: Option<String> = conn
+        .query_row(
+            "SELECT COALESCE(origin_id, id) FROM todos WHERE id = ?1
+             UNION ALL
+             SELECT COALESCE(origin_id, id) FROM archived_todos WHERE id = ?1
+             UNION ALL
+             SELECT COALESCE(origin_id, id) FROM scheduled_todos WHERE id = ?1
+             UNION ALL
+             SELECT COALESCE(origin_id, id) FROM list_todos WHERE id = ?1
+             LIMIT 1",
+            [id.to_string()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(origin.and_then(|s| Uuid::parse_str(&s).ok()))
+}
+
+/// Lineage id of the todo with `id`, wherever it is stored. `None` if it is unknown.
+pub fn load_origin_id(id: Uuid) -> Result<Option<Uuid>> {
+    let conn = get_connection()?;
+    resolve_origin_id(&conn, id)
+}
+
+/// Every occurrence of the task that `id` belongs to, oldest first: live and archived days,
+/// the start date of a scheduled item, and today for an item on a project list.
+/// Returns an empty list if `id` is unknown.
+pub fn load_todo_history(id: Uuid) -> Result<Vec<(NaiveDate, TodoItem)>> {
+    let conn = get_connection()?;
+    query_todo_history(&conn, id)
+}
+
+fn query_todo_history(conn: &Connection, id: Uuid) -> Result<Vec<(NaiveDate, TodoItem)>> {
+    let Some(origin) = resolve_origin_id(conn, id)? else {
+        return Ok(Vec::new());
+    };
+    let origin = origin.to_string();
+
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {}, date
+         FROM todos
+         WHERE (id = ?1 OR origin_id = ?1) AND deleted_at IS NULL
+         UNION ALL
+         SELECT {}, original_date
+         FROM archived_todos
+         WHERE (id = ?1 OR origin_id = ?1) AND deleted_at IS NULL
+         UNION ALL
+         SELECT {}, start_date
+         FROM scheduled_todos
+         WHERE id = ?1 OR origin_id = ?1
+         UNION ALL
+         SELECT {}, date('now', 'localtime')
+         FROM list_todos
+         WHERE (id = ?1 OR origin_id = ?1) AND deleted_at IS NULL
+         ORDER BY 21 ASC",
+        todo_columns("todos"),
+        todo_columns("archived_todos"),
+        todo_columns("scheduled_todos"),
+        todo_columns("list_todos"),
+    ))?;
+
+    let rows = stmt.query_map([&origin], |row| {
+        let date_str: String = row.get(20)?;
+        Ok((date_str, TodoRowData::from_row(row)?))
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        let (date_str, data) = row?;
+        if let Ok(date) = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d") {
+            result.push((date, data.into_todo_item()));
+        }
+    }
+
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -468,6 +1306,35 @@ mod tests {
         result
     }
 
+    #[test]
+    fn test_pooled_connections_are_reused_in_wal_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("pool.db");
+        let pool = ConnectionPool::new();
+
+        let first = pool.checkout(&path).unwrap();
+        let mode: String = first
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+        // Temporary tables are private to one connection
+        first.execute("CREATE TEMP TABLE marker (x)", []).unwrap();
+
+        let second = pool.checkout(&path).unwrap();
+        assert!(second.prepare("SELECT x FROM marker").is_err());
+        drop(second);
+        drop(first);
+
+        let again = pool.checkout(&path).unwrap();
+        assert!(again.prepare("SELECT x FROM marker").is_ok());
+
+        // Switching databases drops the idle connections to the old one
+        drop(again);
+        drop(pool.checkout(&temp_dir.path().join("other.db")).unwrap());
+        let fresh = pool.checkout(&path).unwrap();
+        assert!(fresh.prepare("SELECT x FROM marker").is_err());
+    }
+
     #[test]
     fn test_database_schema() {
         let (_temp_dir, conn) = setup_test_db();
@@ -951,4 +1818,256 @@ mod tests {
             Some("This is a description".to_string())
         );
     }
+
+    #[test]
+    fn test_sync_todo_rows_only_rewrites_changed_rows() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::storage::migrations::migrate(&mut conn).unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+
+        let mut list = create_test_list(date);
+        for content in ["First", "Second", "Third"] {
+            list.items.push(TodoItem::new(content.to_string(), 0));
+        }
+        list.items[1].tags = vec!["work".to_string()];
+        sync_todo_rows(&conn, "todos", "date", "2026-01-05", &list).unwrap();
+
+        // Saving the same list again writes nothing
+        let before = conn.total_changes();
+        let origins = sync_todo_rows(&conn, "todos", "date", "2026-01-05", &list).unwrap();
+        assert_eq!(conn.total_changes(), before);
+        assert_eq!(origins.len(), 3);
+
+        // Toggling one item rewrites only its row, plus one event for the change log
+        list.items[2].state = TodoState::Checked;
+        let before = conn.total_changes();
+        sync_todo_rows(&conn, "todos", "date", "2026-01-05", &list).unwrap();
+        assert_eq!(conn.total_changes(), before + 2);
+        let kind: String = conn
+            .query_row("SELECT kind FROM events ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(kind, "state");
+
+        // Removing an item puts its row in the trash with its tags; the others only move up
+        let removed = list.items.remove(1).id.to_string();
+        sync_todo_rows(&conn, "todos", "date", "2026-01-05", &list).unwrap();
+        let tags: i64 = conn
+            .query_row("SELECT COUNT(*) FROM todo_tags WHERE todo_id = ?1", [&removed], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(tags, 1);
+        let positions: Vec<(String, i64)> = conn
+            .prepare("SELECT content, position FROM todos WHERE deleted_at IS NULL ORDER BY position")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            positions,
+            vec![("First".to_string(), 0), ("Third".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_bump_revision_refuses_stale_list() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::storage::migrations::migrate(&mut conn).unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+
+        let mut mine = create_test_list(date);
+        let theirs = create_test_list(date);
+
+        assert_eq!(bump_revision(&conn, &theirs).unwrap(), 1);
+
+        let err = bump_revision(&conn, &mine).unwrap_err();
+        let conflict = err.downcast_ref::<SaveConflict>().unwrap();
+        assert_eq!((conflict.expected, conflict.found), (0, 1));
+
+        mine.revision = 1;
+        assert_eq!(bump_revision(&conn, &mine).unwrap(), 2);
+
+        // Project lists count separately from the day
+        let named = TodoList::named("backlog", date, PathBuf::from("/tmp/backlog.md"), Vec::new());
+        assert_eq!(bump_revision(&conn, &named).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_deleted_merged_scheduled_item_goes_to_the_trash() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::storage::migrations::migrate(&mut conn).unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+
+        let mut waiting = TodoItem::new("Renew passport".to_string(), 0);
+        waiting.start_date = Some(date);
+        conn.execute(
+            "INSERT INTO scheduled_todos (id, start_date, content, state, indent_level, position, created_at, updated_at)
+             VALUES (?1, '2026-01-05', ?2, ' ', 0, 0, ?3, ?3)",
+            params![waiting.id.to_string(), waiting.content, waiting.created_at.to_rfc3339()],
+        )
+        .unwrap();
+
+        // Merged on load, deleted before the first save
+        let mut list = create_test_list(date);
+        list.add_item("Call bank".to_string());
+        list.merge_scheduled(vec![waiting.clone()]);
+        list.items.pop();
+        sync_todo_rows(&conn, "todos", "date", "2026-01-05", &list).unwrap();
+        trash_dropped_scheduled(&conn, "2026-01-05", &list).unwrap();
+
+        let scheduled: i64 = conn
+            .query_row("SELECT COUNT(*) FROM scheduled_todos", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(scheduled, 0);
+        let trash = query_trash(&conn).unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].item.id, waiting.id);
+        assert_eq!(trash[0].date, Some(date));
+        assert_eq!(query_todo_rows(&conn, "todos", "date", "2026-01-05").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_removed_items_go_to_the_trash_until_restored_or_purged() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::storage::migrations::migrate(&mut conn).unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+
+        let mut list = create_test_list(date);
+        list.add_item_with_indent("Plan".to_string(), 0);
+        list.add_item_with_indent("Draft".to_string(), 1);
+        list.add_item_with_indent("Ship".to_string(), 0);
+        list.recalculate_parent_ids();
+        list.items[1].tags = vec!["writing".to_string()];
+        sync_todo_rows(&conn, "todos", "date", "2026-01-05", &list).unwrap();
+
+        let mut named = TodoList::named("backlog", date, PathBuf::from("/tmp/backlog.md"), Vec::new());
+        named.add_item("Someday".to_string());
+        sync_todo_rows(&conn, "list_todos", "list_name", "backlog", &named).unwrap();
+        named.items.clear();
+        sync_todo_rows(&conn, "list_todos", "list_name", "backlog", &named).unwrap();
+
+        let deleted = list.take_subtree(0);
+        sync_todo_rows(&conn, "todos", "date", "2026-01-05", &list).unwrap();
+        assert_eq!(query_todo_rows(&conn, "todos", "date", "2026-01-05").unwrap().len(), 1);
+
+        let trash = query_trash(&conn).unwrap();
+        assert_eq!(trash.len(), 3);
+        let draft = trash.iter().find(|entry| entry.item.content == "Draft").unwrap();
+        assert_eq!((draft.date, draft.position), (Some(date), 1));
+        assert_eq!(draft.item.parent_id, Some(deleted[0].id));
+        assert_eq!(draft.item.tags, vec!["writing"]);
+        let someday = trash.iter().find(|entry| entry.item.content == "Someday").unwrap();
+        assert_eq!((someday.list.as_deref(), someday.date), (Some("backlog"), None));
+
+        // Saving the items again takes them out of the trash
+        let entries: Vec<TrashedTodo> = crate::storage::trash::subtree(&trash, deleted[0].id)
+            .into_iter()
+            .cloned()
+            .collect();
+        assert_eq!(crate::storage::trash::reinsert(&mut list, entries), 0..2);
+        sync_todo_rows(&conn, "todos", "date", "2026-01-05", &list).unwrap();
+        let live = query_todo_rows(&conn, "todos", "date", "2026-01-05").unwrap();
+        let contents: Vec<&str> = live.iter().map(|item| item.content.as_str()).collect();
+        assert_eq!(contents, vec!["Plan", "Draft", "Ship"]);
+        assert_eq!(live[1].tags, vec!["writing"]);
+        let kind: String = conn
+            .query_row("SELECT kind FROM events ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(kind, "restore");
+
+        // Purging only touches todos that are in the trash
+        assert_eq!(delete_trash_rows(&conn, &[someday.item.id, live[0].id]).unwrap(), 1);
+        assert!(query_trash(&conn).unwrap().is_empty());
+        assert_eq!(query_todo_rows(&conn, "todos", "date", "2026-01-05").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_blocker_lookups_report_sorted_blockers_and_real_errors() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::storage::migrations::migrate(&mut conn).unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+
+        let mut list = create_test_list(date);
+        list.add_item("Book venue".to_string());
+        list.add_item("Order catering".to_string());
+        list.add_item("Send invites".to_string());
+        list.items[1].state = TodoState::Checked;
+        let (open, done) = (list.items[0].id, list.items[1].id);
+        let mut blockers = vec![open, done];
+        blockers.sort();
+        blockers.reverse();
+        list.items[2].blocked_by = blockers.clone();
+        sync_todo_rows(&conn, "todos", "date", "2026-01-05", &list).unwrap();
+
+        blockers.sort();
+        let loaded = query_todo_rows(&conn, "todos", "date", "2026-01-05").unwrap();
+        assert_eq!(loaded[2].blocked_by, blockers);
+
+        let unknown = Uuid::new_v4();
+        let found = query_open_blockers(&conn, &[open, done, unknown]).unwrap();
+        assert_eq!(found, HashSet::from([open]));
+        assert_eq!(resolve_origin_id(&conn, unknown).unwrap(), None);
+
+        // A broken database is an error, not a resolved blocker
+        let broken = Connection::open_in_memory().unwrap();
+        assert!(query_open_blockers(&broken, &[open]).is_err());
+        assert!(resolve_origin_id(&broken, open).is_err());
+    }
+
+    #[test]
+    fn test_history_covers_project_lists_and_scheduled_items() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::storage::migrations::migrate(&mut conn).unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+
+        let mut named = TodoList::named("backlog", date, PathBuf::from("/tmp/backlog.md"), Vec::new());
+        named.add_item("Refactor parser".to_string());
+        named.items[0].tags = vec!["code".to_string()];
+        sync_todo_rows(&conn, "list_todos", "list_name", "backlog", &named).unwrap();
+        let on_list = named.items[0].id;
+
+        let history = query_todo_history(&conn, on_list).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].1.content, "Refactor parser");
+        assert_eq!(history[0].1.tags, vec!["code"]);
+        // What `totui report` names time tracked on the item after
+        let latest = query_todo_history(&conn, on_list).unwrap().pop().unwrap();
+        assert_eq!(latest.1.content_with_tags(), "Refactor parser #code");
+
+        let waiting = Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO scheduled_todos (id, start_date, content, state, indent_level, position, created_at, updated_at)
+             VALUES (?1, '2026-02-01', 'Renew passport', ' ', 0, 0, ?2, ?2)",
+            params![waiting.to_string(), Utc::now().to_rfc3339()],
+        )
+        .unwrap();
+        let history = query_todo_history(&conn, waiting).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].0, NaiveDate::from_ymd_opt(2026, 2, 1).unwrap());
+        assert_eq!(history[0].1.content, "Renew passport");
+    }
+
+    #[test]
+    fn test_notes_are_stored_above_and_below_the_todos() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::storage::migrations::migrate(&mut conn).unwrap();
+        let mut list = create_test_list(NaiveDate::from_ymd_opt(2026, 1, 5).unwrap());
+
+        list.trailing_notes = Some("Went fine.".to_string());
+        save_notes(&conn, &list).unwrap();
+        assert_eq!(query_notes(&conn, &list).unwrap(), (None, Some("Went fine.".to_string())));
+
+        list.notes = Some("Slept badly.".to_string());
+        save_notes(&conn, &list).unwrap();
+        assert_eq!(
+            query_notes(&conn, &list).unwrap(),
+            (Some("Slept badly.".to_string()), Some("Went fine.".to_string()))
+        );
+
+        list.notes = None;
+        list.trailing_notes = None;
+        save_notes(&conn, &list).unwrap();
+        assert_eq!(query_notes(&conn, &list).unwrap(), (None, None));
+    }
 }
@@ -0,0 +1,521 @@
+use super::database::{get_connection, init_database, resolve_origin_id};
+use crate::todo::TodoItem;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{Connection, params};
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// Which program made a change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventSource {
+    Tui,
+    Cli,
+    Api,
+    Mcp,
+    Plugin,
+    /// A markdown file edited outside the app.
+    File,
+}
+
+impl EventSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventSource::Tui => "tui",
+            EventSource::Cli => "cli",
+            EventSource::Api => "api",
+            EventSource::Mcp => "mcp",
+            EventSource::Plugin => "plugin",
+            EventSource::File => "file",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "tui" => Some(EventSource::Tui),
+            "cli" => Some(EventSource::Cli),
+            "api" => Some(EventSource::Api),
+            "mcp" => Some(EventSource::Mcp),
+            "plugin" => Some(EventSource::Plugin),
+            "file" => Some(EventSource::File),
+            _ => None,
+        }
+    }
+}
+
+static EVENT_SOURCE: RwLock<EventSource> = RwLock::new(EventSource::Cli);
+
+/// Set the source recorded for changes saved by this process from now on.
+pub fn set_event_source(source: EventSource) {
+    *EVENT_SOURCE.write().unwrap_or_else(|e| e.into_inner()) = source;
+}
+
+pub fn event_source() -> EventSource {
+    *EVENT_SOURCE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Create,
+    Update,
+    State,
+    Move,
+    Indent,
+    Delete,
+    Rollover,
+    Restore,
+    Purge,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Create => "create",
+            EventKind::Update => "update",
+            EventKind::State => "state",
+            EventKind::Move => "move",
+            EventKind::Indent => "indent",
+            EventKind::Delete => "delete",
+            EventKind::Rollover => "rollover",
+            EventKind::Restore => "restore",
+            EventKind::Purge => "purge",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "create" => Some(EventKind::Create),
+            "update" => Some(EventKind::Update),
+            "state" => Some(EventKind::State),
+            "move" => Some(EventKind::Move),
+            "indent" => Some(EventKind::Indent),
+            "delete" => Some(EventKind::Delete),
+            "rollover" => Some(EventKind::Rollover),
+            "restore" => Some(EventKind::Restore),
+            "purge" => Some(EventKind::Purge),
+            _ => None,
+        }
+    }
+}
+
+/// One recorded change to a todo. Events are only ever appended.
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub occurred_at: DateTime<Utc>,
+    pub source: String,
+    pub kind: EventKind,
+    pub todo_id: Uuid,
+    /// Lineage id, so the history of a task reads across rollovers.
+    pub origin_id: Uuid,
+    /// Day (YYYY-MM-DD) or `list:<name>` the change was made on.
+    pub list_key: String,
+    /// Content of the todo at the time, for display.
+    pub content: String,
+    /// Changed field for `update` events, and what `before`/`after` describe for moves.
+    pub field: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl Event {
+    pub(crate) fn new(kind: EventKind, item: &TodoItem, list_key: &str) -> Self {
+        Self {
+            occurred_at: Utc::now(),
+            source: event_source().as_str().to_string(),
+            kind,
+            todo_id: item.id,
+            origin_id: item.origin(),
+            list_key: list_key.to_string(),
+            content: item.content.clone(),
+            field: None,
+            before: None,
+            after: None,
+        }
+    }
+
+    fn change(mut self, field: &str, before: impl Into<String>, after: impl Into<String>) -> Self {
+        self.field = Some(field.to_string());
+        self.before = Some(before.into());
+        self.after = Some(after.into());
+        self
+    }
+
+    /// Short description of what changed, e.g. `state [ ] → [x]`.
+    pub fn describe(&self) -> String {
+        let before = self.before.as_deref().unwrap_or("");
+        let after = self.after.as_deref().unwrap_or("");
+        match (self.kind, self.field.as_deref()) {
+            (EventKind::Create, _) => format!("created on {}", self.list_key),
+            (EventKind::Delete, _) => format!("deleted from {}", self.list_key),
+            (EventKind::Restore, _) => format!("restored to {}", self.list_key),
+            (EventKind::Purge, _) => format!("purged from the trash of {}", self.list_key),
+            (EventKind::State, _) => format!("state [{before}] → [{after}]"),
+            (EventKind::Indent, _) => format!("indent {before} → {after}"),
+            (EventKind::Rollover, _) => format!("carried over from {before} to {after}"),
+            (EventKind::Move, Some("position")) => format!("moved from #{before} to #{after}"),
+            (EventKind::Move, _) => format!("moved from {before} to {after}"),
+            (EventKind::Update, field) => {
+                format!("{} '{before}' → '{after}'", field.unwrap_or("field"))
+            }
+        }
+    }
+}
+
+fn join_ids(ids: &[Uuid]) -> String {
+    let mut ids: Vec<String> = ids.iter().map(Uuid::to_string).collect();
+    ids.sort();
+    ids.join(",")
+}
+
+fn format_date(date: Option<NaiveDate>) -> String {
+    date.map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// Field changes between two versions of the same todo. Position is handled separately;
+/// collapsing and timestamps are not recorded.
+fn item_changes(before: &TodoItem, after: &TodoItem, list_key: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let event = |kind| Event::new(kind, after, list_key);
+
+    if before.state != after.state {
+        events.push(event(EventKind::State).change(
+            "state",
+            before.state.to_char(),
+            after.state.to_char(),
+        ));
+    }
+    if before.indent_level != after.indent_level {
+        events.push(event(EventKind::Indent).change(
+            "indent",
+            before.indent_level.to_string(),
+            after.indent_level.to_string(),
+        ));
+    }
+
+    let fields: [(&str, String, String); 8] = [
+        ("content", before.content.clone(), after.content.clone()),
+        (
+            "description",
+            before.description.clone().unwrap_or_default(),
+            after.description.clone().unwrap_or_default(),
+        ),
+        ("due_date", format_date(before.due_date), format_date(after.due_date)),
+        ("start_date", format_date(before.start_date), format_date(after.start_date)),
+        ("tags", before.tags.join(" "), after.tags.join(" ")),
+        (
+            "recurrence",
+            before.recurrence.as_ref().map(|r| r.to_string()).unwrap_or_default(),
+            after.recurrence.as_ref().map(|r| r.to_string()).unwrap_or_default(),
+        ),
+        ("blocked_by", join_ids(&before.blocked_by), join_ids(&after.blocked_by)),
+        (
+            "section",
+            before.section.clone().unwrap_or_default(),
+            after.section.clone().unwrap_or_default(),
+        ),
+    ];
+    for (field, old, new) in fields {
+        if old != new {
+            events.push(event(EventKind::Update).change(field, old, new));
+        }
+    }
+
+    events
+}
+
+/// Indices into `sequence` of a longest strictly increasing subsequence.
+fn longest_increasing(sequence: &[usize]) -> Vec<usize> {
+    // tails[k]: index of the smallest tail of an increasing run of length k + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; sequence.len()];
+    for (i, &value) in sequence.iter().enumerate() {
+        let k = tails.partition_point(|&t| sequence[t] < value);
+        previous[i] = k.checked_sub(1).map(|k| tails[k]);
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut cursor = tails.last().copied();
+    while let Some(i) = cursor {
+        result.push(i);
+        cursor = previous[i];
+    }
+    result.reverse();
+    result
+}
+
+/// Where items that are new to a list came from and where items that left it went, looked up
+/// before the save moves any rows.
+#[derive(Debug, Default)]
+pub(crate) struct Whereabouts {
+    /// Items that were live in another list, a day, or the scheduled area.
+    pub arrived: HashMap<Uuid, String>,
+    /// Items that are now live somewhere else. `None` when the other list logs the move as
+    /// an arrival.
+    pub departed: HashMap<Uuid, Option<String>>,
+    /// New occurrences of a task carried over from an earlier day.
+    pub rolled_from: HashMap<Uuid, String>,
+    /// Items brought back out of the trash.
+    pub restored: HashSet<Uuid>,
+}
+
+/// Events for saving `new` over the `old` contents of one day or project list.
+/// Reordering only logs the items that actually moved relative to the others.
+pub(crate) fn diff_events(list_key: &str, old: &[TodoItem], new: &[TodoItem], whereabouts: &Whereabouts) -> Vec<Event> {
+    let old_index: HashMap<Uuid, usize> =
+        old.iter().enumerate().map(|(i, item)| (item.id, i)).collect();
+    let mut events = Vec::new();
+
+    // Old positions of the items that stayed, in their new order
+    let mut kept: Vec<(usize, usize)> = Vec::new();
+
+    for (position, item) in new.iter().enumerate() {
+        match old_index.get(&item.id) {
+            Some(&i) => {
+                events.extend(item_changes(&old[i], item, list_key));
+                kept.push((i, position));
+            }
+            None => events.push(match (whereabouts.arrived.get(&item.id), whereabouts.rolled_from.get(&item.id)) {
+                (Some(from), _) => {
+                    Event::new(EventKind::Move, item, list_key).change("list", from.as_str(), list_key)
+                }
+                _ if whereabouts.restored.contains(&item.id) => Event::new(EventKind::Restore, item, list_key),
+                (None, Some(day)) => Event::new(EventKind::Rollover, item, list_key)
+                    .change("date", day.as_str(), list_key),
+                (None, None) => Event::new(EventKind::Create, item, list_key),
+            }),
+        }
+    }
+
+    let old_positions: Vec<usize> = kept.iter().map(|(i, _)| *i).collect();
+    let in_place = longest_increasing(&old_positions);
+    for (n, &(i, position)) in kept.iter().enumerate() {
+        if in_place.binary_search(&n).is_err() {
+            events.push(Event::new(EventKind::Move, &new[position], list_key).change(
+                "position",
+                (i + 1).to_string(),
+                (position + 1).to_string(),
+            ));
+        }
+    }
+
+    let new_ids: HashSet<Uuid> = new.iter().map(|item| item.id).collect();
+    for item in old.iter().filter(|item| !new_ids.contains(&item.id)) {
+        match whereabouts.departed.get(&item.id) {
+            None => events.push(Event::new(EventKind::Delete, item, list_key)),
+            Some(Some(to)) => {
+                events.push(Event::new(EventKind::Move, item, list_key).change("list", list_key, to.as_str()))
+            }
+            Some(None) => {}
+        }
+    }
+
+    events
+}
+
+pub(crate) fn insert_events(conn: &Connection, events: &[Event]) -> Result<()> {
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO events (occurred_at, source, kind, todo_id, origin_id, list_key, content, field, before, after)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+    )?;
+    for event in events {
+        stmt.execute(params![
+            event.occurred_at.to_rfc3339(),
+            event.source,
+            event.kind.as_str(),
+            event.todo_id.to_string(),
+            event.origin_id.to_string(),
+            event.list_key,
+            event.content,
+            event.field,
+            event.before,
+            event.after,
+        ])?;
+    }
+    Ok(())
+}
+
+/// Which events to read; unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub source: Option<EventSource>,
+    /// A single task, including the days it was carried over to.
+    pub todo_id: Option<Uuid>,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+pub(crate) fn query_events(conn: &Connection, filter: &EventFilter) -> Result<Vec<Event>> {
+    // A todo that was removed for good is only known by its events
+    let origin = match filter.todo_id {
+        Some(id) => Some(resolve_origin_id(conn, id)?.unwrap_or(id)),
+        None => None,
+    };
+
+    // Newest first so the limit keeps the latest events, then flipped to read oldest first
+    let mut stmt = conn.prepare_cached(
+        "SELECT occurred_at, source, kind, todo_id, origin_id, list_key, content, field, before, after
+         FROM events
+         WHERE (?1 IS NULL OR source = ?1)
+           AND (?2 IS NULL OR origin_id = ?2 OR todo_id = ?2)
+           AND (?3 IS NULL OR occurred_at >= ?3)
+         ORDER BY id DESC
+         LIMIT ?4",
+    )?;
+
+    let rows = stmt.query_map(
+        params![
+            filter.source.map(|s| s.as_str()),
+            origin.map(|id| id.to_string()),
+            filter.since.map(|dt| dt.to_rfc3339()),
+            filter.limit.map(|l| l as i64).unwrap_or(-1),
+        ],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, Option<String>>(7)?,
+                row.get::<_, Option<String>>(8)?,
+                row.get::<_, Option<String>>(9)?,
+            ))
+        },
+    )?;
+
+    let mut events = Vec::new();
+    for row in rows {
+        let (occurred_at, source, kind, todo_id, origin_id, list_key, content, field, before, after) =
+            row?;
+        let (Ok(occurred_at), Some(kind), Ok(todo_id), Ok(origin_id)) = (
+            DateTime::parse_from_rfc3339(&occurred_at),
+            EventKind::parse(&kind),
+            Uuid::parse_str(&todo_id),
+            Uuid::parse_str(&origin_id),
+        ) else {
+            continue;
+        };
+        events.push(Event {
+            occurred_at: occurred_at.with_timezone(&Utc),
+            source,
+            kind,
+            todo_id,
+            origin_id,
+            list_key,
+            content,
+            field,
+            before,
+            after,
+        });
+    }
+    events.reverse();
+
+    Ok(events)
+}
+
+/// Recorded events matching `filter`, oldest first.
+pub fn load_events(filter: &EventFilter) -> Result<Vec<Event>> {
+    init_database()?;
+    let conn = get_connection()?;
+    query_events(&conn, filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::TodoState;
+
+    fn kinds(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|e| e.kind.as_str()).collect()
+    }
+
+    #[test]
+    fn test_diff_events_records_each_kind_of_change() {
+        let old: Vec<TodoItem> = ["A", "B", "C", "D"]
+            .iter()
+            .map(|c| TodoItem::new(c.to_string(), 0))
+            .collect();
+        let none = Whereabouts::default();
+
+        let mut new = old.clone();
+        new[0].state = TodoState::Checked;
+        new[1].content = "B renamed".to_string();
+        new.remove(2);
+        new.push(TodoItem::new("E".to_string(), 0));
+        let events = diff_events("2026-01-05", &old, &new, &none);
+        assert_eq!(kinds(&events), vec!["state", "update", "create", "delete"]);
+        assert_eq!(events[1].field.as_deref(), Some("content"));
+        assert_eq!(events[1].before.as_deref(), Some("B"));
+
+        // Moving one item to the top logs only that item, not the ones it pushed down
+        let mut moved = old.clone();
+        let d = moved.remove(3);
+        moved.insert(0, d);
+        let events = diff_events("2026-01-05", &old, &moved, &none);
+        assert_eq!(kinds(&events), vec!["move"]);
+        assert_eq!(events[0].content, "D");
+        assert_eq!(events[0].describe(), "moved from #4 to #1");
+    }
+
+    #[test]
+    fn test_diff_events_logs_moves_between_lists_once() {
+        let item = TodoItem::new("Moved".to_string(), 0);
+        let arrived = Whereabouts {
+            arrived: HashMap::from([(item.id, "2026-01-05".to_string())]),
+            ..Default::default()
+        };
+        let into_backlog = diff_events("list:backlog", &[], std::slice::from_ref(&item), &arrived);
+        assert_eq!(into_backlog[0].describe(), "moved from 2026-01-05 to list:backlog");
+
+        let departed = Whereabouts {
+            departed: HashMap::from([(item.id, None)]),
+            ..Default::default()
+        };
+        assert!(diff_events("2026-01-05", std::slice::from_ref(&item), &[], &departed).is_empty());
+
+        // The scheduled area keeps no log of its own, so leaving for it is logged here
+        let scheduled = Whereabouts {
+            departed: HashMap::from([(item.id, Some("scheduled:2026-02-01".to_string()))]),
+            ..Default::default()
+        };
+        let out_of_day = diff_events("2026-01-05", &[item], &[], &scheduled);
+        assert_eq!(out_of_day[0].describe(), "moved from 2026-01-05 to scheduled:2026-02-01");
+    }
+
+    #[test]
+    fn test_query_events_filters_and_orders() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::storage::migrations::migrate(&mut conn).unwrap();
+
+        let item = TodoItem::new("Write report".to_string(), 0);
+        set_event_source(EventSource::Mcp);
+        let created = Event::new(EventKind::Create, &item, "2026-01-05");
+        set_event_source(EventSource::Cli);
+        let deleted = Event::new(EventKind::Delete, &item, "2026-01-05");
+        insert_events(&conn, &[created, deleted]).unwrap();
+
+        let all = query_events(&conn, &EventFilter::default()).unwrap();
+        assert_eq!(kinds(&all), vec!["create", "delete"]);
+
+        let by_mcp = EventFilter {
+            source: Some(EventSource::Mcp),
+            ..Default::default()
+        };
+        assert_eq!(kinds(&query_events(&conn, &by_mcp).unwrap()), vec!["create"]);
+
+        let latest = EventFilter {
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(kinds(&query_events(&conn, &latest).unwrap()), vec!["delete"]);
+    }
+}
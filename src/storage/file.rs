@@ -1,46 +1,228 @@
 use super::database;
-use super::markdown::{parse_todo_list, serialize_todo_list_clean};
-use crate::todo::TodoList;
-use crate::utils::paths::{ensure_directories_exist, get_daily_file_path};
+use super::markdown::{parse_todo_list, serialize_todo_list};
+use super::sync::{SyncOutcome, sync_daily_file};
+use crate::todo::rebase::rebase;
+use crate::todo::{TodoItem, TodoList};
+use crate::utils::paths::{
+    ensure_directories_exist, get_daily_file_path, get_list_file_path, validate_list_name,
+};
 use anyhow::{Context, Result};
 use chrono::{Local, NaiveDate};
 use std::fs;
 
+/// Load the daily list of `date`, merging in edits made to its markdown file first.
+/// If those edits conflict with the stored list, the stored list is returned with
+/// `file_conflict` set (see `sync::conflict_message`).
 pub fn load_todo_list(date: NaiveDate) -> Result<TodoList> {
     ensure_directories_exist()?;
     database::init_database()?;
 
     let file_path = get_daily_file_path(date)?;
 
+    // Pick up edits made to the file in an editor; on a conflict the stored list wins
+    // until the file is fixed
+    let outcome = sync_daily_file(date)?;
+    let mut list = load_stored_list(date, file_path)?;
+    list.file_conflict = outcome == SyncOutcome::Conflict;
+    merge_due_scheduled(&mut list)?;
+    Ok(list)
+}
+
+fn load_stored_list(date: NaiveDate, file_path: std::path::PathBuf) -> Result<TodoList> {
+    let mut list = TodoList::new(date, file_path);
+    let revision = database::load_revision(&list)?;
+    (list.notes, list.trailing_notes) = database::load_notes(&list)?;
+
     if database::has_todos_for_date(date)? {
-        let items = database::load_todos_for_date(date)?;
-        return Ok(TodoList::with_items(date, file_path, items));
+        list.items = database::load_todos_for_date(date)?;
+        list.mark_stored(revision);
+        return Ok(list);
+    }
+
+    list.mark_stored(revision);
+    if list.file_path.exists() {
+        let content = fs::read_to_string(&list.file_path)
+            .with_context(|| format!("Failed to read file: {}", list.file_path.display()))?;
+
+        let parsed = parse_todo_list(&content, date, list.file_path.clone());
+
+        let stored_notes = list.notes.is_some() || list.trailing_notes.is_some();
+        let file_notes = parsed.notes.is_some() || parsed.trailing_notes.is_some();
+        if !parsed.items.is_empty() || (!stored_notes && file_notes) {
+            list.items = parsed.items;
+            if !stored_notes {
+                list.notes = parsed.notes;
+                list.trailing_notes = parsed.trailing_notes;
+            }
+            let revision = database::save_todo_list(&list)?;
+            list.mark_stored(revision);
+        }
     }
 
-    if file_path.exists() {
-        let content = fs::read_to_string(&file_path)
-            .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+    Ok(list)
+}
 
-        let list = parse_todo_list(&content, date, file_path.clone())
-            .with_context(|| "Failed to parse todo list")?;
+/// Load the project list `name`. A list that does not exist yet is returned empty and is
+/// created when first saved; a hand-written `lists/<name>.md` is imported on first load.
+pub fn load_named_list(name: &str) -> Result<TodoList> {
+    ensure_directories_exist()?;
+    database::init_database()?;
 
-        if !list.items.is_empty() {
-            database::save_todo_list(&list)?;
-        }
+    let name = validate_list_name(name)?;
+    let today = Local::now().date_naive();
+    let file_path = get_list_file_path(name)?;
+
+    let mut list = TodoList::named(name, today, file_path, Vec::new());
+    let revision = database::load_revision(&list)?;
+    (list.notes, list.trailing_notes) = database::load_notes(&list)?;
+    list.mark_stored(revision);
 
+    if database::has_named_list(name)? {
+        list.items = database::load_named_list_items(name)?;
+        list.mark_stored(revision);
         return Ok(list);
     }
 
-    Ok(TodoList::new(date, file_path))
+    if list.file_path.exists() {
+        let content = fs::read_to_string(&list.file_path)
+            .with_context(|| format!("Failed to read file: {}", list.file_path.display()))?;
+
+        let parsed = parse_todo_list(&content, today, list.file_path.clone());
+        list.items = parsed.items;
+        if list.notes.is_none() && list.trailing_notes.is_none() {
+            list.notes = parsed.notes;
+            list.trailing_notes = parsed.trailing_notes;
+        }
+        let revision = database::save_named_list(&list)?;
+        list.mark_stored(revision);
+    }
+
+    Ok(list)
+}
+
+/// Load today's list when `name` is `None`, otherwise the project list `name`.
+pub fn load_list(name: Option<&str>) -> Result<TodoList> {
+    match name {
+        Some(name) => load_named_list(name),
+        None => load_todo_list(Local::now().date_naive()),
+    }
 }
 
-pub fn save_todo_list(list: &TodoList) -> Result<()> {
+/// Names of all project lists, alphabetically.
+pub fn list_names() -> Result<Vec<String>> {
+    database::init_database()?;
+    database::load_list_names()
+}
+
+/// Create an empty project list so it can be picked before anything is added to it.
+pub fn create_named_list(name: &str) -> Result<()> {
     ensure_directories_exist()?;
     database::init_database()?;
+    let name = validate_list_name(name)?;
+    database::create_named_list(name)
+}
+
+/// Add scheduled items that are actionable on `list.date`. Today's list picks up everything
+/// that is due, including items whose start date passed while the app was closed; a future
+/// list only picks up items starting on that exact day. Items leave the scheduled area when
+/// the list is next saved.
+pub fn merge_due_scheduled(list: &mut TodoList) -> Result<()> {
+    let today = Local::now().date_naive();
+    let from = match list.date.cmp(&today) {
+        std::cmp::Ordering::Less => return Ok(()),
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Greater => Some(list.date),
+    };
+
+    let due = database::load_scheduled_todos(from, Some(list.date))?;
+    let due = due
+        .into_iter()
+        .filter(|item| !list.items.iter().any(|existing| existing.id == item.id))
+        .collect();
+    list.merge_scheduled(due);
+    Ok(())
+}
 
-    database::save_todo_list(list)?;
+/// Move items that start after `list.date`, with their subtasks, out of `list` and into the
+/// scheduled area. The caller still saves `list`. Returns the moved items.
+/// Project lists keep their items whatever their start date.
+pub fn schedule_future_items(list: &mut TodoList) -> Result<Vec<TodoItem>> {
+    if list.name.is_some() {
+        return Ok(Vec::new());
+    }
+    let scheduled = list.take_scheduled();
+    if !scheduled.is_empty() {
+        database::init_database()?;
+        database::save_scheduled_todos(&scheduled)?;
+    }
+    Ok(scheduled)
+}
 
-    let content = serialize_todo_list_clean(list);
+/// How often a save is rebased onto concurrent saves before the conflict is reported.
+pub(crate) const SAVE_ATTEMPTS: usize = 3;
+
+/// Save `list` to the database and its markdown file.
+///
+/// If another writer saved the same list since it was loaded, the local edits are replayed
+/// onto the stored items and the save is retried, leaving the combined items in `list`.
+/// Fails with `database::SaveConflict` when both sides edited the same items.
+pub fn save_todo_list(list: &mut TodoList) -> Result<()> {
+    ensure_directories_exist()?;
+    database::init_database()?;
+    list.normalize_sections();
+
+    let mut attempt = 1;
+    loop {
+        let saved = match list.name {
+            Some(_) => database::save_named_list(list),
+            None => database::save_todo_list(list),
+        };
+        let error = match saved {
+            Ok(revision) => {
+                list.mark_stored(revision);
+                break;
+            }
+            Err(e) => e,
+        };
+        if !error.is::<database::SaveConflict>() || attempt == SAVE_ATTEMPTS {
+            return Err(error);
+        }
+        attempt += 1;
+
+        let revision = database::load_revision(list)?;
+        let theirs = match list.name {
+            Some(ref name) => database::load_named_list_items(name)?,
+            None => database::load_todos_for_date(list.date)?,
+        };
+        let Some(merged) = rebase(&list.base_items, &list.items, &theirs) else {
+            return Err(error);
+        };
+        list.revision = revision;
+        list.base_items = theirs;
+        list.items = merged;
+        list.recalculate_parent_ids();
+        list.normalize_sections();
+    }
+
+    write_markdown(list)
+}
+
+/// Write `list` to its markdown file, unless the file was edited since it was last written.
+/// Those edits are left for `sync::sync_daily_file` to merge rather than overwritten.
+fn write_markdown(list: &TodoList) -> Result<()> {
+    if let Some(snapshot) = database::load_markdown_snapshot(list)?
+        && let Ok(on_disk) = fs::read_to_string(&list.file_path)
+        && on_disk != snapshot.content
+    {
+        return Ok(());
+    }
+
+    replace_markdown(list, &serialize_todo_list(list))
+}
+
+/// Write `content` to the markdown file of `list` and remember it as the file's snapshot.
+pub(crate) fn replace_markdown(list: &TodoList, content: &str) -> Result<()> {
+    database::save_markdown_snapshot(list, content)?;
 
     let temp_path = list.file_path.with_extension("tmp");
 
@@ -81,12 +263,16 @@ pub fn load_todos_for_viewing(date: NaiveDate) -> Result<TodoList> {
 
     let items = database::load_archived_todos_for_date(date)?;
     if !items.is_empty() {
-        return Ok(TodoList::with_items(date, file_path, items));
+        let mut list = TodoList::with_items(date, file_path, items);
+        (list.notes, list.trailing_notes) = database::load_notes(&list)?;
+        return Ok(list);
     }
 
     if database::has_todos_for_date(date)? {
         let items = database::load_todos_for_date(date)?;
-        return Ok(TodoList::with_items(date, file_path, items));
+        let mut list = TodoList::with_items(date, file_path, items);
+        (list.notes, list.trailing_notes) = database::load_notes(&list)?;
+        return Ok(list);
     }
 
     Ok(TodoList::new(date, file_path))
@@ -117,7 +303,7 @@ mod tests {
         fs::write(&file_path, content).unwrap();
 
         let loaded_content = fs::read_to_string(&file_path).unwrap();
-        let loaded_list = parse_todo_list(&loaded_content, date, file_path).unwrap();
+        let loaded_list = parse_todo_list(&loaded_content, date, file_path);
 
         assert_eq!(loaded_list.items.len(), 2);
         assert_eq!(loaded_list.items[0].content, "Test task 1");
@@ -136,7 +322,7 @@ mod tests {
         list.items[1].state = crate::todo::TodoState::Checked;
 
         let markdown = serialize_todo_list_clean(&list);
-        let parsed = parse_todo_list(&markdown, date, file_path).unwrap();
+        let parsed = parse_todo_list(&markdown, date, file_path);
 
         assert_eq!(parsed.items.len(), 2);
         assert_eq!(parsed.items[0].content, "Parent");
@@ -0,0 +1,451 @@
+use super::database::{
+    archive_imported_day, get_connection, live_elsewhere, query_todo_rows, save_dependencies,
+    save_tags,
+};
+use crate::todo::{TodoItem, TodoList};
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior, params};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// What `import-archive` does with a day that is already in the archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportPolicy {
+    /// Leave the archived day alone.
+    Skip,
+    /// Update the archived todos from the file by id and add the ones the archive lacks.
+    Merge,
+}
+
+impl ImportPolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "skip" => Some(ImportPolicy::Skip),
+            "merge" => Some(ImportPolicy::Merge),
+            _ => None,
+        }
+    }
+}
+
+/// What importing the markdown file of a past day does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportOutcome {
+    /// The day is not archived yet; its todos are moved into the archive.
+    Import { todos: usize },
+    /// The day is archived already and the file is merged into it.
+    Merge {
+        added: usize,
+        updated: usize,
+        unchanged: usize,
+    },
+    /// The file is left alone, for the given reason.
+    Skip(String),
+    /// Todos of the file are stored somewhere else, so nothing of it is imported.
+    Conflict(Vec<String>),
+}
+
+/// The changes importing one file makes, worked out before anything is written.
+#[derive(Debug)]
+pub struct ImportPlan {
+    pub outcome: ImportOutcome,
+    list: TodoList,
+    updates: Vec<TodoItem>,
+    additions: Vec<TodoItem>,
+}
+
+/// Work out what importing `list`, read from the markdown file of a past day, would do.
+///
+/// Todos whose id is not stored for the day are matched to stored ones by content, so a
+/// file without ids can be imported twice without duplicating anything. `claimed` collects
+/// the ids of the files planned so far, so a todo that is in two files is a conflict.
+pub fn plan_import(
+    conn: &Connection,
+    mut list: TodoList,
+    policy: ImportPolicy,
+    claimed: &mut HashMap<Uuid, NaiveDate>,
+) -> Result<ImportPlan> {
+    let skip = |list: TodoList, reason: &str| ImportPlan {
+        outcome: ImportOutcome::Skip(reason.to_string()),
+        list,
+        updates: Vec::new(),
+        additions: Vec::new(),
+    };
+    if list.items.is_empty() {
+        return Ok(skip(list, "empty"));
+    }
+
+    let date_str = list.date.format("%Y-%m-%d").to_string();
+    let archived = query_todo_rows(conn, "archived_todos", "original_date", &date_str)?;
+    let is_archived = !archived.is_empty();
+    if is_archived && policy == ImportPolicy::Skip {
+        return Ok(skip(list, "already archived"));
+    }
+
+    let stored = if is_archived {
+        archived
+    } else {
+        query_todo_rows(conn, "todos", "date", &date_str)?
+    };
+    adopt_ids(&mut list, &stored);
+
+    let stored_by_id: HashMap<Uuid, &TodoItem> = stored.iter().map(|item| (item.id, item)).collect();
+    let mut archived_on = conn.prepare_cached("SELECT original_date FROM archived_todos WHERE id = ?1")?;
+    let mut conflicts = Vec::new();
+    let mut seen = HashSet::new();
+    for item in &list.items {
+        let reason = if !seen.insert(item.id) {
+            Some("appears twice in the file".to_string())
+        } else if let Some(other) = claimed.get(&item.id) {
+            Some(format!("is also in the file of {}", other.format("%Y-%m-%d")))
+        } else if let Some(day) = archived_on
+            .query_row([item.id.to_string()], |row| row.get::<_, String>(0))
+            .optional()?
+            .filter(|_| !stored_by_id.contains_key(&item.id))
+        {
+            Some(format!("is archived on {day}"))
+        } else {
+            live_elsewhere(conn, item.id, "todos", &date_str)?
+                .map(|location| format!("is stored in {location}"))
+        };
+        if let Some(reason) = reason {
+            conflicts.push(format!("\"{}\" {reason}", item.content));
+        }
+    }
+    if !conflicts.is_empty() {
+        return Ok(ImportPlan {
+            outcome: ImportOutcome::Conflict(conflicts),
+            list,
+            updates: Vec::new(),
+            additions: Vec::new(),
+        });
+    }
+    claimed.extend(list.items.iter().map(|item| (item.id, list.date)));
+
+    if !is_archived {
+        return Ok(ImportPlan {
+            outcome: ImportOutcome::Import {
+                todos: list.items.len(),
+            },
+            list,
+            updates: Vec::new(),
+            additions: Vec::new(),
+        });
+    }
+
+    let mut updates = Vec::new();
+    let mut additions = Vec::new();
+    let mut unchanged = 0;
+    for item in &list.items {
+        match stored_by_id.get(&item.id) {
+            Some(row) if matches_row(row, item) => unchanged += 1,
+            Some(row) => updates.push(merge_into_row(row, item)),
+            None => additions.push(item.clone()),
+        }
+    }
+    Ok(ImportPlan {
+        outcome: ImportOutcome::Merge {
+            added: additions.len(),
+            updated: updates.len(),
+            unchanged,
+        },
+        list,
+        updates,
+        additions,
+    })
+}
+
+/// Give todos whose id is not among `stored` the id of a stored todo with the same content
+/// that nothing else in the list claims, then relink parents.
+fn adopt_ids(list: &mut TodoList, stored: &[TodoItem]) {
+    let known: HashSet<Uuid> = list
+        .items
+        .iter()
+        .map(|item| item.id)
+        .filter(|id| stored.iter().any(|row| row.id == *id))
+        .collect();
+    let mut unclaimed: Vec<&TodoItem> = stored.iter().filter(|row| !known.contains(&row.id)).collect();
+
+    for item in list.items.iter_mut().filter(|item| !known.contains(&item.id)) {
+        let Some(index) = unclaimed.iter().position(|row| row.content == item.content) else {
+            continue;
+        };
+        let id = unclaimed.remove(index).id;
+        if item.series_id == Some(item.id) {
+            item.series_id = Some(id);
+        }
+        item.id = id;
+    }
+    list.recalculate_parent_ids();
+}
+
+/// Whether the file shows `item` exactly as the archive has it.
+fn matches_row(row: &TodoItem, item: &TodoItem) -> bool {
+    let mut blocked_by = item.blocked_by.clone();
+    blocked_by.sort();
+    row.content == item.content
+        && row.state == item.state
+        && row.indent_level == item.indent_level
+        && row.parent_id == item.parent_id
+        && row.due_date == item.due_date
+        && row.description == item.description
+        && row.tags == item.tags
+        && row.blocked_by == blocked_by
+        && row.recurrence == item.recurrence
+        && row.start_date == item.start_date
+        && row.section == item.section
+}
+
+/// The archived `row` with what the file shows of `item`. Timestamps and lineage stay those
+/// of the archive.
+fn merge_into_row(row: &TodoItem, item: &TodoItem) -> TodoItem {
+    TodoItem {
+        content: item.content.clone(),
+        state: item.state,
+        indent_level: item.indent_level,
+        parent_id: item.parent_id,
+        due_date: item.due_date,
+        description: item.description.clone(),
+        tags: item.tags.clone(),
+        blocked_by: item.blocked_by.clone(),
+        recurrence: item.recurrence.clone(),
+        start_date: item.start_date,
+        section: item.section.clone(),
+        completed_at: if row.state == item.state {
+            row.completed_at
+        } else {
+            item.completed_at
+        },
+        modified_at: Utc::now(),
+        ..row.clone()
+    }
+}
+
+/// Carry out `plan`. Returns how many todos were added to or changed in the archive.
+pub fn apply_import(plan: ImportPlan) -> Result<usize> {
+    if matches!(plan.outcome, ImportOutcome::Skip(_) | ImportOutcome::Conflict(_)) {
+        return Ok(0);
+    }
+    let mut conn = get_connection()?;
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let written = write_plan(&tx, plan)?;
+    tx.commit()?;
+    Ok(written)
+}
+
+fn write_plan(conn: &Connection, mut plan: ImportPlan) -> Result<usize> {
+    match plan.outcome {
+        ImportOutcome::Import { .. } => archive_imported_day(conn, &mut plan.list),
+        ImportOutcome::Merge { .. } => write_merge(conn, &plan.list, &plan.updates, &plan.additions),
+        ImportOutcome::Skip(_) | ImportOutcome::Conflict(_) => Ok(0),
+    }
+}
+
+/// Rewrite `updates` and add `additions` to the archive of `list.date`, ordered as in
+/// `list`. Archived todos the file no longer has are kept, after the others.
+fn write_merge(
+    conn: &Connection,
+    list: &TodoList,
+    updates: &[TodoItem],
+    additions: &[TodoItem],
+) -> Result<usize> {
+    let date_str = list.date.format("%Y-%m-%d").to_string();
+    let now = Utc::now().to_rfc3339();
+
+    let mut update = conn.prepare_cached(
+        "UPDATE archived_todos
+         SET content = ?2, state = ?3, indent_level = ?4, parent_id = ?5, due_date = ?6,
+             description = ?7, updated_at = ?8, completed_at = ?9, recurrence = ?10,
+             start_date = ?11, section = ?12
+         WHERE id = ?1",
+    )?;
+    for item in updates {
+        update.execute(params![
+            item.id.to_string(),
+            item.content,
+            item.state.to_char().to_string(),
+            item.indent_level as i64,
+            item.parent_id.map(|id| id.to_string()),
+            item.due_date.map(|d| d.format("%Y-%m-%d").to_string()),
+            item.description,
+            item.modified_at.to_rfc3339(),
+            item.completed_at.map(|dt| dt.to_rfc3339()),
+            item.recurrence.as_ref().map(|r| r.to_string()),
+            item.start_date.map(|d| d.format("%Y-%m-%d").to_string()),
+            item.section,
+        ])?;
+    }
+
+    let mut insert = conn.prepare_cached(
+        "INSERT INTO archived_todos (id, original_date, archived_at, content, state, indent_level, parent_id, due_date, description, collapsed, position, created_at, updated_at, completed_at, recurrence, series_id, origin_id, carry_count, start_date, section)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 0, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+    )?;
+    for item in additions {
+        insert.execute(params![
+            item.id.to_string(),
+            date_str,
+            now,
+            item.content,
+            item.state.to_char().to_string(),
+            item.indent_level as i64,
+            item.parent_id.map(|id| id.to_string()),
+            item.due_date.map(|d| d.format("%Y-%m-%d").to_string()),
+            item.description,
+            item.collapsed as i32,
+            item.created_at.to_rfc3339(),
+            item.modified_at.to_rfc3339(),
+            item.completed_at.map(|dt| dt.to_rfc3339()),
+            item.recurrence.as_ref().map(|r| r.to_string()),
+            item.series_id.map(|id| id.to_string()),
+            item.origin_id.map(|id| id.to_string()),
+            item.carry_count as i64,
+            item.start_date.map(|d| d.format("%Y-%m-%d").to_string()),
+            item.section,
+        ])?;
+    }
+
+    for item in updates.iter().chain(additions) {
+        let id_str = item.id.to_string();
+        save_tags(conn, &id_str, &item.tags)?;
+        save_dependencies(conn, &id_str, &item.blocked_by)?;
+    }
+
+    let in_file: HashSet<Uuid> = list.items.iter().map(|item| item.id).collect();
+    let left_over: Vec<Uuid> = query_todo_rows(conn, "archived_todos", "original_date", &date_str)?
+        .into_iter()
+        .map(|row| row.id)
+        .filter(|id| !in_file.contains(id))
+        .collect();
+    let mut reposition = conn.prepare_cached(
+        "UPDATE archived_todos SET position = ?2 WHERE id = ?1 AND position IS NOT ?2",
+    )?;
+    let order = list.items.iter().map(|item| item.id).chain(left_over);
+    for (position, id) in order.enumerate() {
+        reposition.execute(params![id.to_string(), position as i64])?;
+    }
+
+    Ok(updates.len() + additions.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::markdown::{parse_todo_list, serialize_todo_list_lossless};
+    use crate::storage::migrations::migrate;
+    use std::path::PathBuf;
+
+    const DAY: &str = "- [x] Plan\n  - [ ] Draft #writing\n- [ ] Ship\n";
+
+    fn setup() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        conn
+    }
+
+    fn parse(content: &str, date: NaiveDate) -> TodoList {
+        parse_todo_list(content, date, PathBuf::new())
+    }
+
+    fn archive(conn: &Connection, list: &TodoList) {
+        write_merge(conn, list, &[], &list.items).unwrap();
+    }
+
+    fn archived_contents(conn: &Connection, date: NaiveDate) -> Vec<String> {
+        query_todo_rows(conn, "archived_todos", "original_date", &date.format("%Y-%m-%d").to_string())
+            .unwrap()
+            .into_iter()
+            .map(|item| item.content)
+            .collect()
+    }
+
+    #[test]
+    fn test_importing_a_file_again_merges_instead_of_duplicating() {
+        let conn = setup();
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        archive(&conn, &parse(DAY, date));
+
+        let plan = plan_import(&conn, parse(DAY, date), ImportPolicy::Skip, &mut HashMap::new()).unwrap();
+        assert_eq!(plan.outcome, ImportOutcome::Skip("already archived".to_string()));
+
+        let plan = plan_import(&conn, parse(DAY, date), ImportPolicy::Merge, &mut HashMap::new()).unwrap();
+        assert_eq!(
+            plan.outcome,
+            ImportOutcome::Merge {
+                added: 0,
+                updated: 0,
+                unchanged: 3
+            }
+        );
+
+        let edited = "- [x] Plan\n  - [x] Draft #writing\n  - [ ] Review\n- [ ] Ship\n";
+        let plan = plan_import(&conn, parse(edited, date), ImportPolicy::Merge, &mut HashMap::new()).unwrap();
+        assert_eq!(
+            plan.outcome,
+            ImportOutcome::Merge {
+                added: 1,
+                updated: 1,
+                unchanged: 2
+            }
+        );
+        write_merge(&conn, &plan.list, &plan.updates, &plan.additions).unwrap();
+        assert_eq!(archived_contents(&conn, date), vec!["Plan", "Draft", "Review", "Ship"]);
+
+        let plan = plan_import(&conn, parse(edited, date), ImportPolicy::Merge, &mut HashMap::new()).unwrap();
+        assert!(matches!(plan.outcome, ImportOutcome::Merge { unchanged: 4, .. }));
+    }
+
+    #[test]
+    fn test_importing_an_in_progress_todo_tracks_no_time() {
+        let conn = setup();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+
+        let file = "- [*] Old in-progress task\n- [ ] Ship\n";
+        let plan = plan_import(&conn, parse(file, date), ImportPolicy::Skip, &mut HashMap::new()).unwrap();
+        assert_eq!(plan.outcome, ImportOutcome::Import { todos: 2 });
+        assert_eq!(write_plan(&conn, plan).unwrap(), 2);
+
+        assert_eq!(archived_contents(&conn, date), vec!["Old in-progress task", "Ship"]);
+        let entries: i64 = conn
+            .query_row("SELECT COUNT(*) FROM time_entries", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(entries, 0);
+        let live: i64 = conn
+            .query_row("SELECT COUNT(*) FROM todos", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(live, 0);
+    }
+
+    #[test]
+    fn test_todos_stored_elsewhere_are_conflicts() {
+        let conn = setup();
+        let monday = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        let tuesday = NaiveDate::from_ymd_opt(2025, 3, 4).unwrap();
+        let list = parse(DAY, monday);
+        archive(&conn, &list);
+
+        // The same todos, by id, in the file of another day
+        let mut copy = list.clone();
+        copy.date = tuesday;
+        let lossless = serialize_todo_list_lossless(&copy);
+        let plan = plan_import(&conn, parse(&lossless, tuesday), ImportPolicy::Merge, &mut HashMap::new()).unwrap();
+        let ImportOutcome::Conflict(conflicts) = plan.outcome else {
+            panic!("expected a conflict, got {:?}", plan.outcome);
+        };
+        assert_eq!(conflicts.len(), 3);
+        assert_eq!(conflicts[0], "\"Plan\" is archived on 2025-03-03");
+
+        // Two files of days that are not archived yet, sharing a todo
+        let wednesday = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+        let thursday = NaiveDate::from_ymd_opt(2025, 3, 6).unwrap();
+        let mut shared = parse("- [ ] Call the bank\n", wednesday);
+        let mut claimed = HashMap::new();
+        let plan = plan_import(&conn, shared.clone(), ImportPolicy::Skip, &mut claimed).unwrap();
+        assert_eq!(plan.outcome, ImportOutcome::Import { todos: 1 });
+        shared.date = thursday;
+        let plan = plan_import(&conn, shared, ImportPolicy::Skip, &mut claimed).unwrap();
+        assert_eq!(
+            plan.outcome,
+            ImportOutcome::Conflict(vec!["\"Call the bank\" is also in the file of 2025-03-05".to_string()])
+        );
+    }
+}
@@ -1,54 +1,281 @@
-use crate::todo::{TodoItem, TodoList, TodoState};
-use anyhow::{anyhow, Result};
-use chrono::NaiveDate;
+use crate::todo::{TodoItem, TodoList, TodoState, split_recurrence, split_start_date, split_tags};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// `[markdown]` section of the config file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarkdownConfig {
+    /// Also write each todo's id, timestamps and collapsed state, so a file can be re-imported
+    /// without losing them. They go in an HTML comment at the end of the line, which
+    /// markdown viewers do not show.
+    #[serde(default)]
+    pub lossless: bool,
+}
+
+static LOSSLESS: AtomicBool = AtomicBool::new(false);
+
+/// Choose how `serialize_todo_list` writes files from now on.
+pub fn install_markdown_config(config: &MarkdownConfig) {
+    LOSSLESS.store(config.lossless, Ordering::Relaxed);
+}
+
+/// Serialize `list` the way the config asks for: clean, or lossless.
+pub fn serialize_todo_list(list: &TodoList) -> String {
+    if LOSSLESS.load(Ordering::Relaxed) {
+        serialize_todo_list_lossless(list)
+    } else {
+        serialize_todo_list_clean(list)
+    }
+}
 
 pub fn serialize_todo_list_clean(list: &TodoList) -> String {
+    render_list(list, false)
+}
+
+/// Like `serialize_todo_list_clean`, plus the metadata `parse_todo_list` needs to restore
+/// every item exactly.
+pub fn serialize_todo_list_lossless(list: &TodoList) -> String {
+    render_list(list, true)
+}
+
+fn render_list(list: &TodoList, lossless: bool) -> String {
+    let mut output = String::new();
+
+    match list.name {
+        Some(ref name) => output.push_str(&format!("# {name}\n\n")),
+        None => output.push_str(&format!(
+            "# Todo List - {}\n\n",
+            list.date.format("%B %d, %Y")
+        )),
+    }
+
+    if let Some(ref notes) = list.notes {
+        output.push_str(&format!("{notes}\n\n"));
+    }
+
+    let mut section = None;
+    for item in &list.items {
+        if item.section.is_some() && item.section != section {
+            section = item.section.clone();
+            if !output.ends_with("\n\n") {
+                output.push('\n');
+            }
+            output.push_str(&format!("## {}\n\n", item.section.as_deref().unwrap_or_default()));
+        }
+        output.push_str(&render_item(item, lossless));
+    }
+
+    if let Some(ref notes) = list.trailing_notes {
+        output.push_str(&format!("\n{notes}\n"));
+    }
+
+    output
+}
+
+/// Markdown lines for one item as the clean serialization writes them: its checkbox line
+/// and any description lines.
+pub(crate) fn serialize_item(item: &TodoItem) -> String {
+    render_item(item, false)
+}
+
+fn render_item(item: &TodoItem, lossless: bool) -> String {
     let mut output = String::new();
+    let indent = "  ".repeat(item.indent_level);
+
+    let due_suffix = item
+        .due_date
+        .map(|d| format!(" @due({})", d.format("%Y-%m-%d")))
+        .unwrap_or_default();
+
+    let metadata = if lossless {
+        format!(" <!-- {} -->", metadata(item))
+    } else {
+        String::new()
+    };
 
     output.push_str(&format!(
-        "# Todo List - {}\n\n",
-        list.date.format("%B %d, %Y")
+        "{}- [{}] {}{}{}\n",
+        indent,
+        item.state.to_char(),
+        item.editable_content(),
+        due_suffix,
+        metadata
     ));
 
-    for item in &list.items {
-        let indent = "  ".repeat(item.indent_level);
-
-        let due_suffix = item
-            .due_date
-            .map(|d| format!(" @due({})", d.format("%Y-%m-%d")))
-            .unwrap_or_default();
-
-        output.push_str(&format!(
-            "{}- [{}] {}{}\n",
-            indent,
-            item.state.to_char(),
-            item.content,
-            due_suffix
-        ));
+    if let Some(ref desc) = item.description {
+        for line in desc.lines() {
+            output.push_str(&format!("{indent}  > {line}\n"));
+        }
+    }
+
+    output
+}
+
+/// `key:value` pairs for what the checkbox line does not show.
+fn metadata(item: &TodoItem) -> String {
+    let mut fields = vec![
+        format!("id:{}", item.id),
+        format!("created:{}", item.created_at.to_rfc3339()),
+        format!("updated:{}", item.modified_at.to_rfc3339()),
+    ];
+    if let Some(completed) = item.completed_at {
+        fields.push(format!("completed:{}", completed.to_rfc3339()));
+    }
+    if item.collapsed {
+        fields.push("collapsed".to_string());
+    }
+    // The parser assumes a recurring item starts its own series
+    if item.series_id != item.recurrence.as_ref().map(|_| item.id)
+        && let Some(series_id) = item.series_id
+    {
+        fields.push(format!("series:{series_id}"));
+    }
+    if let Some(origin_id) = item.origin_id {
+        fields.push(format!("origin:{origin_id}"));
+        fields.push(format!("carried:{}", item.carry_count));
+    }
+    fields.join(" ")
+}
 
-        if let Some(ref desc) = item.description {
-            for line in desc.lines() {
-                output.push_str(&format!("{indent}  > {line}\n"));
+/// Split a trailing `<!-- ... -->` metadata comment off a checkbox line's content. Only a
+/// comment carrying an `id:` field is ours; any other comment stays part of the content.
+fn take_metadata(content: &str) -> (&str, Option<&str>) {
+    let trimmed = content.trim_end();
+    if let Some(body) = trimmed.strip_suffix("-->")
+        && let Some(start) = body.rfind("<!--")
+    {
+        let metadata = body[start + 4..].trim();
+        let ours = metadata.split_whitespace().any(|field| {
+            field
+                .strip_prefix("id:")
+                .is_some_and(|id| uuid::Uuid::parse_str(id).is_ok())
+        });
+        if ours {
+            return (body[..start].trim_end(), Some(metadata));
+        }
+    }
+    (content, None)
+}
+
+fn apply_metadata(item: &mut TodoItem, metadata: &str) {
+    let timestamp = |value: &str| {
+        DateTime::parse_from_rfc3339(value)
+            .ok()
+            .map(|dt| dt.with_timezone(&Utc))
+    };
+    for field in metadata.split_whitespace() {
+        let (key, value) = field.split_once(':').unwrap_or((field, ""));
+        match key {
+            "id" => {
+                if let Ok(id) = uuid::Uuid::parse_str(value) {
+                    if item.series_id == Some(item.id) {
+                        item.series_id = Some(id);
+                    }
+                    item.id = id;
+                }
             }
+            "created" => {
+                if let Some(dt) = timestamp(value) {
+                    item.created_at = dt;
+                }
+            }
+            "updated" => {
+                if let Some(dt) = timestamp(value) {
+                    item.modified_at = dt;
+                }
+            }
+            // Unchecking the box by hand wins over a stale completion time
+            "completed" if item.state.is_complete() => item.completed_at = timestamp(value),
+            "collapsed" => item.collapsed = true,
+            "series" => item.series_id = uuid::Uuid::parse_str(value).ok(),
+            "origin" => item.origin_id = uuid::Uuid::parse_str(value).ok(),
+            "carried" => item.carry_count = value.parse().unwrap_or(0),
+            _ => {}
         }
     }
+}
 
-    output
+/// Something in a markdown file that the parser read differently from how it was written,
+/// or skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    /// 1-based line number.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parse a list written by `serialize_todo_list`, or by hand, dropping any warnings.
+pub fn parse_todo_list(content: &str, date: NaiveDate, file_path: PathBuf) -> TodoList {
+    parse_todo_list_with_warnings(content, date, file_path).0
 }
 
-pub fn parse_todo_list(content: &str, date: NaiveDate, file_path: PathBuf) -> Result<TodoList> {
+/// Parse a list written by `serialize_todo_list`, or by hand.
+///
+/// Checkboxes may use `-`, `*`, `+` or numbered bullets, and be indented with spaces or tabs
+/// by any consistent width. A `##` (or deeper) heading files the todos below it under that
+/// section. Any other text, including a heading with no todos under it, is kept as the
+/// list's notes: above the todos if it came before the first one, below them otherwise.
+/// Lines that are odd but readable are kept and reported as warnings rather than failing
+/// the whole file.
+pub fn parse_todo_list_with_warnings(
+    content: &str,
+    date: NaiveDate,
+    file_path: PathBuf,
+) -> (TodoList, Vec<ParseWarning>) {
     let mut items: Vec<TodoItem> = Vec::new();
+    let mut warnings = Vec::new();
     let mut pending_description: Option<String> = None;
+    let mut section: Option<String> = None;
+    let mut notes = String::new();
+    let mut trailing_notes = String::new();
+    let mut blank_before = false;
+    let mut seen_title = false;
+    let lines: Vec<&str> = content.lines().collect();
+    let indent_width = detect_indent_width(&lines);
 
-    for line in content.lines() {
-        if line.trim().is_empty() || line.trim().starts_with('#') {
+    for (index, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            blank_before = true;
             continue;
         }
 
         let trimmed = line.trim_start();
 
-        if let Some(stripped) = trimmed.strip_prefix('>') {
+        if let Some(level) = heading_level(trimmed) {
+            // The title is written from the list itself
+            if level == 1 && !seen_title && items.is_empty() && notes.is_empty() {
+                seen_title = true;
+                continue;
+            }
+            let heading = trimmed[level..].trim();
+            let opens_section = !heading.is_empty()
+                && lines[index + 1..]
+                    .iter()
+                    .find(|next| !next.trim().is_empty())
+                    .is_some_and(|next| is_todo_line(next));
+            if opens_section {
+                if let Some(desc) = pending_description.take()
+                    && let Some(last_item) = items.last_mut()
+                {
+                    last_item.description = Some(desc);
+                }
+                section = Some(heading.to_string());
+                continue;
+            }
+        }
+
+        if let Some(stripped) = trimmed.strip_prefix('>')
+            && !items.is_empty()
+        {
             if let Some(ref mut desc) = pending_description {
                 desc.push('\n');
                 desc.push_str(stripped.trim());
@@ -63,10 +290,25 @@ pub fn parse_todo_list(content: &str, date: NaiveDate, file_path: PathBuf) -> Re
                 last_item.description = Some(desc);
             }
 
-        if let Some(mut item) = parse_todo_line(line)? {
-            let parent_id = find_parent_id(&items, item.indent_level);
-            item.parent_id = parent_id;
+        if let Some(checkbox) = Checkbox::parse(line) {
+            let mut messages = Vec::new();
+            let max_level = items.last().map_or(0, |last| last.indent_level + 1);
+            let mut item = parse_todo_line(&checkbox, indent_width, max_level, &mut messages);
+            item.parent_id = find_parent_id(&items, item.indent_level);
+            item.section = section.clone();
             items.push(item);
+            warnings.extend(messages.into_iter().map(|message| ParseWarning {
+                line: index + 1,
+                message,
+            }));
+        } else {
+            // Text above the first todo stays above the todos, anything later goes below
+            let notes = if items.is_empty() { &mut notes } else { &mut trailing_notes };
+            if !notes.is_empty() {
+                notes.push_str(if blank_before { "\n\n" } else { "\n" });
+            }
+            notes.push_str(line.trim_end());
+            blank_before = false;
         }
     }
 
@@ -75,7 +317,87 @@ pub fn parse_todo_list(content: &str, date: NaiveDate, file_path: PathBuf) -> Re
             last_item.description = Some(desc);
         }
 
-    Ok(TodoList::with_items(date, file_path, items))
+    let mut list = TodoList::with_items(date, file_path, items);
+    list.notes = (!notes.is_empty()).then_some(notes);
+    list.trailing_notes = (!trailing_notes.is_empty()).then_some(trailing_notes);
+    (list, warnings)
+}
+
+/// Number of `#`s of a markdown heading line.
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    (level > 0 && (rest.is_empty() || rest.starts_with(' '))).then_some(level)
+}
+
+fn is_todo_line(line: &str) -> bool {
+    Checkbox::parse(line).is_some()
+}
+
+/// Columns a tab advances to the next multiple of.
+const TAB_WIDTH: usize = 4;
+
+/// Width of the leading whitespace of `line`, in columns.
+fn indent_columns(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .fold(0, |columns, c| match c {
+            '\t' => (columns / TAB_WIDTH + 1) * TAB_WIDTH,
+            _ => columns + 1,
+        })
+}
+
+/// The indent of one nesting level: the smallest indent of any checkbox line, or two
+/// columns when nothing is indented.
+fn detect_indent_width(lines: &[&str]) -> usize {
+    lines
+        .iter()
+        .filter_map(|line| Checkbox::parse(line))
+        .map(|checkbox| checkbox.indent)
+        .filter(|&indent| indent > 0)
+        .min()
+        .unwrap_or(2)
+}
+
+/// A `- [x] content` line, or one of its GFM and Obsidian variants.
+struct Checkbox<'a> {
+    /// Leading whitespace, in columns.
+    indent: usize,
+    /// Text between the brackets; at most one character.
+    marker: &'a str,
+    content: &'a str,
+}
+
+impl<'a> Checkbox<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let trimmed = line.trim_start();
+        let after_bullet = match trimmed.strip_prefix(['-', '*', '+']) {
+            Some(rest) => rest,
+            None => {
+                let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+                if digits == 0 {
+                    return None;
+                }
+                trimmed[digits..].strip_prefix(['.', ')'])?
+            }
+        };
+        if !after_bullet.starts_with([' ', '\t']) {
+            return None;
+        }
+        let inside = after_bullet.trim_start().strip_prefix('[')?;
+        let close = inside.find(']')?;
+        let marker = &inside[..close];
+        let content = &inside[close + 1..];
+        // Anything longer is a link such as `- [docs](https://...)`
+        if marker.chars().count() > 1 || !(content.is_empty() || content.starts_with([' ', '\t'])) {
+            return None;
+        }
+        Some(Self {
+            indent: indent_columns(line),
+            marker,
+            content: content.trim(),
+        })
+    }
 }
 
 fn find_parent_id(items: &[TodoItem], indent_level: usize) -> Option<uuid::Uuid> {
@@ -91,72 +413,119 @@ fn find_parent_id(items: &[TodoItem], indent_level: usize) -> Option<uuid::Uuid>
     None
 }
 
-fn parse_todo_line(line: &str) -> Result<Option<TodoItem>> {
-    let indent_level = line.len() - line.trim_start().len();
-    let indent_level = indent_level / 2;
-
-    let trimmed = line.trim_start();
-
-    if !trimmed.starts_with("- [") {
-        return Ok(None);
+/// Read a checkbox line into an item nested at most `max_level` deep, pushing anything that
+/// had to be read loosely onto `warnings`.
+fn parse_todo_line(
+    checkbox: &Checkbox,
+    indent_width: usize,
+    max_level: usize,
+    warnings: &mut Vec<String>,
+) -> TodoItem {
+    if !checkbox.indent.is_multiple_of(indent_width) {
+        warnings.push(format!(
+            "indented by {} columns, which is not a multiple of {indent_width}",
+            checkbox.indent
+        ));
     }
-
-    if trimmed.len() < 5 {
-        return Err(anyhow!("Invalid checkbox format"));
+    let mut indent_level = checkbox.indent / indent_width;
+    if indent_level > max_level {
+        warnings.push("indented more than one level below the todo above".to_string());
+        indent_level = max_level;
     }
 
-    let state_char = trimmed
-        .chars()
-        .nth(3)
-        .ok_or_else(|| anyhow!("Missing state character"))?;
-    let state = TodoState::from_char(state_char)
-        .ok_or_else(|| anyhow!("Invalid state character: {state_char}"))?;
-
-    let raw_content = if trimmed.len() > 5 {
-        trimmed[5..].trim()
-    } else {
-        ""
+    let state = match checkbox.marker.chars().next() {
+        None => {
+            warnings.push("empty checkbox `[]` read as `[ ]`".to_string());
+            TodoState::Empty
+        }
+        // Keep unknown markers rather than losing them, as the database does
+        Some(c) => TodoState::from_char(c).unwrap_or_else(|| {
+            warnings.push(format!("unknown state `[{c}]`, kept as is"));
+            TodoState::Custom(c)
+        }),
     };
 
+    let (raw_content, metadata) = take_metadata(checkbox.content);
+
     let (content, id) = parse_id(raw_content);
-    let (content, due_date) = parse_due_date(&content);
+    let (content, due_date) = parse_due_date(&content, warnings);
+    let (content, origin_id, carry_count) = parse_lineage(&content);
+    let (content, blocked_by) = parse_after(&content);
+    let (content, recurrence) = split_recurrence(&content);
+    let (content, start_date) = split_start_date(&content);
+    let (content, tags) = split_tags(&content);
 
     let mut item = TodoItem::full(content, state, indent_level, None, due_date, None, false);
+    item.tags = tags;
 
     if let Some(parsed_id) = id {
         item.id = parsed_id;
     }
 
-    Ok(Some(item))
+    if recurrence.is_some() {
+        item.series_id = Some(item.id);
+    }
+    item.recurrence = recurrence;
+    item.origin_id = origin_id;
+    item.carry_count = carry_count;
+    item.blocked_by = blocked_by;
+    item.start_date = start_date;
+    if let Some(metadata) = metadata {
+        apply_metadata(&mut item, metadata);
+    }
+
+    item
 }
 
 fn parse_id(content: &str) -> (String, Option<uuid::Uuid>) {
-    if let Some(start) = content.find("@id(")
-        && let Some(end) = content[start..].find(')') {
-            let id_str = &content[start + 4..start + end];
-            let id = uuid::Uuid::parse_str(id_str).ok();
+    let (cleaned, value) = take_annotation(content, "id");
+    match value {
+        Some(id_str) => (cleaned, uuid::Uuid::parse_str(&id_str).ok()),
+        None => (cleaned, None),
+    }
+}
 
-            let mut cleaned = String::new();
-            cleaned.push_str(content[..start].trim());
-            if start + end + 1 < content.len() {
-                let suffix = content[start + end + 1..].trim();
-                if !suffix.is_empty() {
-                    if !cleaned.is_empty() {
-                        cleaned.push(' ');
-                    }
-                    cleaned.push_str(suffix);
-                }
-            }
-            return (cleaned, id);
+fn parse_due_date(content: &str, warnings: &mut Vec<String>) -> (String, Option<NaiveDate>) {
+    let (cleaned, value) = take_annotation(content, "due");
+    let due_date = value.and_then(|date_str| {
+        let parsed = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d").ok();
+        if parsed.is_none() {
+            warnings.push(format!("invalid date in `@due({date_str})`, ignored"));
         }
-    (content.to_string(), None)
+        parsed
+    });
+    (cleaned, due_date)
+}
+
+/// Lineage annotations written by older versions; lineage now lives in the database and
+/// in the metadata comment of lossless files.
+fn parse_lineage(content: &str) -> (String, Option<uuid::Uuid>, u32) {
+    let (cleaned, origin) = take_annotation(content, "origin");
+    let (cleaned, carried) = take_annotation(&cleaned, "carried");
+    let origin_id = origin.and_then(|s| uuid::Uuid::parse_str(&s).ok());
+    let carry_count = carried.and_then(|s| s.parse().ok()).unwrap_or(0);
+    (cleaned, origin_id, carry_count)
 }
 
-fn parse_due_date(content: &str) -> (String, Option<NaiveDate>) {
-    if let Some(start) = content.find("@due(")
+fn parse_after(content: &str) -> (String, Vec<uuid::Uuid>) {
+    let (cleaned, value) = take_annotation(content, "after");
+    let blocked_by = value
+        .map(|ids| {
+            ids.split(',')
+                .filter_map(|id| uuid::Uuid::parse_str(id.trim()).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    (cleaned, blocked_by)
+}
+
+/// Remove the first `@name(value)` annotation from `content`, returning the cleaned
+/// content and the raw value.
+fn take_annotation(content: &str, name: &str) -> (String, Option<String>) {
+    let marker = format!("@{name}(");
+    if let Some(start) = content.find(&marker)
         && let Some(end) = content[start..].find(')') {
-            let date_str = &content[start + 5..start + end];
-            let due_date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").ok();
+            let value = content[start + marker.len()..start + end].to_string();
 
             let mut cleaned = String::new();
             cleaned.push_str(content[..start].trim());
@@ -169,7 +538,7 @@ fn parse_due_date(content: &str) -> (String, Option<NaiveDate>) {
                     cleaned.push_str(suffix);
                 }
             }
-            return (cleaned, due_date);
+            return (cleaned, Some(value));
         }
     (content.to_string(), None)
 }
@@ -197,6 +566,18 @@ mod tests {
         assert!(markdown.contains("# Todo List - December 31, 2025"));
     }
 
+    #[test]
+    fn test_serialize_named_list() {
+        let mut list = TodoList::named("backlog", create_test_date(), create_test_path(), Vec::new());
+        list.add_item("Someday".to_string());
+
+        let markdown = serialize_todo_list_clean(&list);
+        assert!(markdown.starts_with("# backlog\n"));
+
+        let parsed = parse_todo_list(&markdown, create_test_date(), create_test_path());
+        assert_eq!(parsed.items[0].content, "Someday");
+    }
+
     #[test]
     fn test_serialize_simple_list() {
         let date = create_test_date();
@@ -267,7 +648,7 @@ mod tests {
 
         let date = create_test_date();
         let path = create_test_path();
-        let list = parse_todo_list(content, date, path).unwrap();
+        let list = parse_todo_list(content, date, path);
 
         assert_eq!(list.items.len(), 5);
         assert_eq!(list.items[0].content, "Task 1");
@@ -294,7 +675,7 @@ mod tests {
 
         let date = create_test_date();
         let path = create_test_path();
-        let list = parse_todo_list(content, date, path).unwrap();
+        let list = parse_todo_list(content, date, path);
 
         assert_eq!(list.items.len(), 4);
         assert_eq!(list.items[0].indent_level, 0);
@@ -314,7 +695,7 @@ mod tests {
         list.items[1].state = TodoState::Checked;
 
         let markdown = serialize_todo_list_clean(&list);
-        let parsed = parse_todo_list(&markdown, date, path).unwrap();
+        let parsed = parse_todo_list(&markdown, date, path);
 
         assert_eq!(parsed.items.len(), 2);
         assert_eq!(parsed.items[0].content, "Parent");
@@ -339,10 +720,437 @@ Empty line above
 
         let date = create_test_date();
         let path = create_test_path();
-        let list = parse_todo_list(content, date, path).unwrap();
+        let list = parse_todo_list(content, date, path);
 
         assert_eq!(list.items.len(), 2);
         assert_eq!(list.items[0].content, "Task 1");
         assert_eq!(list.items[1].content, "Task 2");
     }
+
+    #[test]
+    fn test_parse_tags() {
+        let content = "- [ ] Review PR #review #team @due(2026-01-05)\n";
+
+        let list = parse_todo_list(content, create_test_date(), create_test_path());
+
+        assert_eq!(list.items[0].content, "Review PR");
+        assert_eq!(list.items[0].tags, vec!["review", "team"]);
+        assert_eq!(
+            list.items[0].due_date,
+            NaiveDate::from_ymd_opt(2026, 1, 5)
+        );
+    }
+
+    #[test]
+    fn test_tags_round_trip() {
+        let date = create_test_date();
+        let path = create_test_path();
+        let mut list = TodoList::new(date, path.clone());
+        list.add_item("Review PR".to_string());
+        list.items[0].tags = vec!["review".to_string()];
+
+        let markdown = serialize_todo_list_clean(&list);
+        assert!(markdown.contains("- [ ] Review PR #review\n"));
+
+        let parsed = parse_todo_list(&markdown, date, path);
+        assert_eq!(parsed.items[0].content, "Review PR");
+        assert_eq!(parsed.items[0].tags, vec!["review"]);
+    }
+
+    #[test]
+    fn test_recurrence_round_trip() {
+        let date = create_test_date();
+        let path = create_test_path();
+        let mut list = TodoList::new(date, path.clone());
+        list.add_item("Standup #team @every(weekday)".to_string());
+
+        let markdown = serialize_todo_list_clean(&list);
+        assert!(markdown.contains("- [ ] Standup #team @every(weekday)\n"));
+
+        let parsed = parse_todo_list(&markdown, date, path);
+        let item = &parsed.items[0];
+        assert_eq!(item.content, "Standup");
+        assert_eq!(item.recurrence, Some(crate::todo::Recurrence::Weekdays));
+        assert_eq!(item.series_id, Some(item.id));
+    }
+
+    #[test]
+    fn test_lineage_stays_out_of_clean_files() {
+        let date = create_test_date();
+        let path = create_test_path();
+        let origin_id = uuid::Uuid::new_v4();
+        let mut list = TodoList::new(date, path.clone());
+        list.add_item("Write migration".to_string());
+        list.items[0].origin_id = Some(origin_id);
+        list.items[0].carry_count = 6;
+
+        let markdown = serialize_todo_list_clean(&list);
+        assert!(markdown.contains("- [ ] Write migration\n"));
+
+        let lossless = serialize_todo_list_lossless(&list);
+        assert!(lossless.contains(&format!("origin:{origin_id} carried:6 -->")));
+        let parsed = parse_todo_list(&lossless, date, path.clone());
+        assert_eq!(parsed.items[0].content, "Write migration");
+        assert_eq!(parsed.items[0].origin_id, Some(origin_id));
+        assert_eq!(parsed.items[0].carry_count, 6);
+
+        // Files written before lineage moved out of the checkbox line still parse
+        let old = format!(
+            "# Todo List - {}\n\n- [ ] Write migration @carried(6) @origin({origin_id})\n",
+            date.format("%B %d, %Y")
+        );
+        let parsed = parse_todo_list(&old, date, path);
+        assert_eq!(parsed.items[0].content, "Write migration");
+        assert_eq!(parsed.items[0].origin_id, Some(origin_id));
+        assert_eq!(parsed.items[0].carry_count, 6);
+    }
+
+    #[test]
+    fn test_after_round_trip() {
+        let date = create_test_date();
+        let path = create_test_path();
+        let blockers = vec![uuid::Uuid::new_v4(), uuid::Uuid::new_v4()];
+        let mut list = TodoList::new(date, path.clone());
+        list.add_item("Deploy".to_string());
+        list.items[0].blocked_by = blockers.clone();
+
+        let markdown = serialize_todo_list_clean(&list);
+        assert!(markdown.contains(&format!(
+            "- [ ] Deploy @after({},{})\n",
+            blockers[0], blockers[1]
+        )));
+
+        let parsed = parse_todo_list(&markdown, date, path);
+        assert_eq!(parsed.items[0].content, "Deploy");
+        assert_eq!(parsed.items[0].blocked_by, blockers);
+    }
+
+    #[test]
+    fn test_start_date_round_trip() {
+        let date = create_test_date();
+        let path = create_test_path();
+        let mut list = TodoList::new(date, path.clone());
+        list.add_item("Renew passport @start(2025-12-31) #admin".to_string());
+
+        let markdown = serialize_todo_list_clean(&list);
+        assert!(markdown.contains("- [ ] Renew passport #admin @start(2025-12-31)\n"));
+
+        let parsed = parse_todo_list(&markdown, date, path);
+        assert_eq!(parsed.items[0].content, "Renew passport");
+        assert_eq!(parsed.items[0].start_date, NaiveDate::from_ymd_opt(2025, 12, 31));
+    }
+    #[test]
+    fn test_sections_and_notes_round_trip() {
+        let date = create_test_date();
+        let path = create_test_path();
+        let content = [
+            "# Todo List - December 31, 2025",
+            "",
+            "Slept badly, keep it light.",
+            "",
+            "#tags at the start are not headings",
+            "",
+            "- [ ] Stretch",
+            "",
+            "## Work",
+            "",
+            "- [ ] Review PR",
+            "  - [x] Read the diff",
+            "",
+            "### Ideas",
+            "",
+            "- [ ] Try the new profiler",
+            "",
+        ]
+        .join("\n");
+
+        let parsed = parse_todo_list(&content, date, path.clone());
+        assert_eq!(
+            parsed.notes.as_deref(),
+            Some("Slept badly, keep it light.\n\n#tags at the start are not headings")
+        );
+        let sections: Vec<Option<&str>> = parsed.items.iter().map(|i| i.section.as_deref()).collect();
+        assert_eq!(sections, vec![None, Some("Work"), Some("Work"), Some("Ideas")]);
+        assert_eq!(parsed.items[2].parent_id, Some(parsed.items[1].id));
+
+        let markdown = serialize_todo_list_clean(&parsed);
+        assert_eq!(markdown, content.replace("### Ideas", "## Ideas"));
+        let reparsed = parse_todo_list(&markdown, date, path);
+        assert_eq!(reparsed.notes, parsed.notes);
+    }
+
+    #[test]
+    fn test_heading_without_todos_is_kept_as_notes() {
+        let content = "# Todo List - December 31, 2025\n\n## Journal\n\nA quiet day.\n\n- [ ] Stretch\n";
+        let parsed = parse_todo_list(content, create_test_date(), create_test_path());
+        assert_eq!(parsed.notes.as_deref(), Some("## Journal\n\nA quiet day."));
+        assert_eq!(parsed.items[0].section, None);
+        assert_eq!(serialize_todo_list_clean(&parsed), content);
+    }
+
+    #[test]
+    fn test_notes_keep_their_place_around_the_todos() {
+        let content = [
+            "# Todo List - December 31, 2025",
+            "",
+            "Slept badly.",
+            "",
+            "- [ ] Stretch",
+            "Between the todos",
+            "- [ ] Walk",
+            "",
+            "## Retro",
+            "",
+            "Went fine.",
+            "",
+        ]
+        .join("\n");
+
+        let parsed = parse_todo_list(&content, create_test_date(), create_test_path());
+        assert_eq!(parsed.notes.as_deref(), Some("Slept badly."));
+        assert_eq!(
+            parsed.trailing_notes.as_deref(),
+            Some("Between the todos\n\n## Retro\n\nWent fine.")
+        );
+
+        // Text between two todos is the only thing that moves, to below the todos
+        let markdown = serialize_todo_list_clean(&parsed);
+        assert_eq!(
+            markdown,
+            "# Todo List - December 31, 2025\n\nSlept badly.\n\n- [ ] Stretch\n- [ ] Walk\n\n\
+             Between the todos\n\n## Retro\n\nWent fine.\n"
+        );
+        let reparsed = parse_todo_list(&markdown, create_test_date(), create_test_path());
+        assert_eq!(serialize_todo_list_clean(&reparsed), markdown);
+
+        // A file written with notes in both places is written back unchanged
+        let kept = content.replace("Between the todos\n", "");
+        let parsed = parse_todo_list(&kept, create_test_date(), create_test_path());
+        assert_eq!(serialize_todo_list_clean(&parsed), kept);
+    }
+
+    #[test]
+    fn test_parse_accepts_gfm_and_obsidian_variants() {
+        let content = [
+            "* [ ] Star",
+            "\t+ [X] Plus, tab indented",
+            "\t\t1. [ ] Numbered",
+            "2) [*] Paren",
+            "- [docs](https://example.com)",
+        ]
+        .join("\n");
+        let (list, warnings) =
+            parse_todo_list_with_warnings(&content, create_test_date(), create_test_path());
+
+        assert!(warnings.is_empty(), "{warnings:?}");
+        let read: Vec<_> = list
+            .items
+            .iter()
+            .map(|item| (item.content.as_str(), item.state, item.indent_level))
+            .collect();
+        assert_eq!(
+            read,
+            vec![
+                ("Star", TodoState::Empty, 0),
+                ("Plus, tab indented", TodoState::Checked, 1),
+                ("Numbered", TodoState::Empty, 2),
+                ("Paren", TodoState::InProgress, 0),
+            ]
+        );
+        assert_eq!(list.items[2].parent_id, Some(list.items[1].id));
+        assert_eq!(list.trailing_notes.as_deref(), Some("- [docs](https://example.com)"));
+    }
+
+    #[test]
+    fn test_parse_detects_indent_width() {
+        let content = "- [ ] Parent\n    - [ ] Child\n        - [ ] Grandchild\n";
+        let (list, warnings) =
+            parse_todo_list_with_warnings(content, create_test_date(), create_test_path());
+
+        assert!(warnings.is_empty(), "{warnings:?}");
+        let levels: Vec<_> = list.items.iter().map(|item| item.indent_level).collect();
+        assert_eq!(levels, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_parse_warns_instead_of_failing() {
+        let content = [
+            "# Todo List",
+            "- [o] Odd marker",
+            "  - [] No marker",
+            "      - [ ] Too deep",
+            "- [ ] Bad date @due(tomorrow)",
+        ]
+        .join("\n");
+        let (list, warnings) =
+            parse_todo_list_with_warnings(&content, create_test_date(), create_test_path());
+
+        assert_eq!(list.items.len(), 4);
+        assert_eq!(list.items[0].state, TodoState::Custom('o'));
+        assert_eq!(list.items[1].state, TodoState::Empty);
+        assert_eq!(list.items[2].indent_level, 2);
+        assert_eq!(list.items[2].parent_id, Some(list.items[1].id));
+        assert_eq!(list.items[3].content, "Bad date");
+        assert_eq!(list.items[3].due_date, None);
+
+        let lines: Vec<_> = warnings.iter().map(|warning| warning.line).collect();
+        assert_eq!(lines, vec![2, 3, 4, 5]);
+        assert_eq!(warnings[0].to_string(), "line 2: unknown state `[o]`, kept as is");
+    }
+
+    #[test]
+    fn test_lossless_keeps_metadata_in_a_comment() {
+        let date = create_test_date();
+        let path = create_test_path();
+        let mut list = TodoList::new(date, path.clone());
+        list.add_item("Parent #work".to_string());
+        list.add_item_with_indent("Child".to_string(), 1);
+        list.items[0].collapsed = true;
+        list.items[1].set_state(TodoState::Checked);
+        list.recalculate_parent_ids();
+
+        let markdown = serialize_todo_list_lossless(&list);
+        let first_line = markdown.lines().nth(2).unwrap();
+        assert!(first_line.starts_with("- [ ] Parent #work <!-- id:"));
+        assert!(first_line.ends_with(" collapsed -->"));
+        // The clean form is what remains without the comments
+        assert_eq!(
+            markdown
+                .lines()
+                .map(|line| take_metadata(line).0)
+                .collect::<Vec<_>>()
+                .join("\n")
+                + "\n",
+            serialize_todo_list_clean(&list)
+        );
+
+        let parsed = parse_todo_list(&markdown, date, path);
+        assert_eq!(parsed.items, list.items);
+    }
+
+    #[test]
+    fn test_user_comments_survive_a_round_trip() {
+        let date = create_test_date();
+        let path = create_test_path();
+        let markdown = "# Todo List - December 31, 2025\n\n- [ ] Call the bank <!-- ask about fees -->\n";
+
+        let parsed = parse_todo_list(markdown, date, path.clone());
+        assert_eq!(parsed.items[0].content, "Call the bank <!-- ask about fees -->");
+        assert_eq!(serialize_todo_list_clean(&parsed), markdown);
+
+        // The metadata comment goes after the user's own one
+        let lossless = serialize_todo_list_lossless(&parsed);
+        assert!(lossless.contains("Call the bank <!-- ask about fees --> <!-- id:"));
+        let reparsed = parse_todo_list(&lossless, date, path);
+        assert_eq!(reparsed.items, parsed.items);
+    }
+
+    #[test]
+    fn test_lossless_round_trip_of_random_lists() {
+        // xorshift, so failures reproduce from the seed
+        struct Rng(u64);
+        impl Rng {
+            fn next(&mut self) -> u64 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                self.0
+            }
+            fn below(&mut self, n: usize) -> usize {
+                (self.next() % n as u64) as usize
+            }
+            fn chance(&mut self, one_in: usize) -> bool {
+                self.below(one_in) == 0
+            }
+            fn timestamp(&mut self) -> DateTime<Utc> {
+                DateTime::from_timestamp(
+                    1_600_000_000 + self.below(200_000_000) as i64,
+                    self.below(1_000_000_000) as u32,
+                )
+                .unwrap()
+            }
+            fn date(&mut self) -> NaiveDate {
+                NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()
+                    + chrono::Days::new(self.below(700) as u64)
+            }
+        }
+
+        const WORDS: &[&str] = &[
+            "buy", "milk", "review", "PR", "call", "Anna", "fix", "bug", "42", "deploy",
+        ];
+        const STATES: &[char] = &[' ', 'x', '*', '?', '!'];
+        let date = create_test_date();
+        let path = create_test_path();
+
+        for seed in 1..=200u64 {
+            let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let mut list = TodoList::new(date, path.clone());
+            for _ in 0..rng.below(12) {
+                let words = (0..1 + rng.below(4)).map(|_| WORDS[rng.below(WORDS.len())]);
+                let mut item = TodoItem::new(words.collect::<Vec<_>>().join(" "), 0);
+                let max_indent = list.items.last().map_or(0, |last| last.indent_level + 1);
+                item.indent_level = rng.below(max_indent + 1);
+                item.state = TodoState::from_char(STATES[rng.below(STATES.len())]).unwrap();
+                item.created_at = rng.timestamp();
+                item.modified_at = rng.timestamp();
+                if item.state.is_complete() {
+                    item.completed_at = Some(rng.timestamp());
+                }
+                item.collapsed = rng.chance(3);
+                if rng.chance(3) {
+                    item.tags = vec![["home", "work", "urgent"][rng.below(3)].to_string()];
+                }
+                if rng.chance(4) {
+                    item.due_date = Some(rng.date());
+                }
+                if rng.chance(4) {
+                    item.start_date = Some(rng.date());
+                }
+                if rng.chance(4) {
+                    item.recurrence = crate::todo::Recurrence::parse("mon,thu");
+                    item.series_id = Some(if rng.chance(2) {
+                        item.id
+                    } else {
+                        uuid::Uuid::new_v4()
+                    });
+                }
+                if rng.chance(4) {
+                    item.origin_id = Some(uuid::Uuid::new_v4());
+                    item.carry_count = 1 + rng.below(9) as u32;
+                }
+                if rng.chance(5) {
+                    item.blocked_by = vec![uuid::Uuid::new_v4()];
+                }
+                if rng.chance(4) {
+                    item.description = Some(format!(
+                        "{}\n{}",
+                        WORDS[rng.below(WORDS.len())],
+                        WORDS[rng.below(WORDS.len())]
+                    ));
+                }
+                list.items.push(item);
+            }
+            // Sections only ever start, as that is all a heading can say
+            let mut section = None;
+            for item in &mut list.items {
+                if rng.chance(4) {
+                    section = Some(["Work", "Home", "Ideas"][rng.below(3)].to_string());
+                }
+                item.section = section.clone();
+            }
+            if rng.chance(3) {
+                list.notes = Some(format!("{}\n\n{} {}", WORDS[rng.below(WORDS.len())], WORDS[0], WORDS[1]));
+            }
+            if !list.items.is_empty() && rng.chance(3) {
+                list.trailing_notes = Some(format!("{} {}", WORDS[rng.below(WORDS.len())], WORDS[1]));
+            }
+            list.recalculate_parent_ids();
+
+            let markdown = serialize_todo_list_lossless(&list);
+            let parsed = parse_todo_list(&markdown, date, path.clone());
+            assert_eq!(parsed.items, list.items, "seed {seed}:\n{markdown}");
+            assert_eq!(parsed.notes, list.notes, "seed {seed}:\n{markdown}");
+            assert_eq!(parsed.trailing_notes, list.trailing_notes, "seed {seed}:\n{markdown}");
+        }
+    }
 }
@@ -0,0 +1,833 @@
+use super::comments::Comment;
+use super::database::SaveConflict;
+use super::events::{Event, EventFilter, EventKind, Whereabouts, diff_events};
+use super::search::SearchHit;
+use super::store::TodoStore;
+use super::sync::SyncOutcome;
+use super::time_tracking::{TimeEntry, TrackedTime};
+use super::trash::TrashedTodo;
+use crate::todo::rebase::rebase;
+use crate::todo::{TodoItem, TodoList, TodoState};
+use crate::utils::paths::validate_list_name;
+use anyhow::Result;
+use chrono::{Local, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// A `TodoStore` that keeps everything in memory and writes no files.
+///
+/// Saves are checked and rebased by revision like the database does, deleted todos go to the
+/// trash, time is tracked and changes are logged, and lists have an empty `file_path`.
+#[derive(Debug, Default)]
+pub struct InMemoryStore {
+    inner: Mutex<Lists>,
+}
+
+#[derive(Debug, Default)]
+struct Lists {
+    days: HashMap<NaiveDate, StoredList>,
+    named: BTreeMap<String, StoredList>,
+    archived: HashMap<NaiveDate, Vec<TodoItem>>,
+    /// Scheduled items with the start date of the subtree they belong to.
+    scheduled: Vec<(NaiveDate, TodoItem)>,
+    trash: Vec<TrashedTodo>,
+    comments: Vec<Comment>,
+    time_entries: Vec<TimeEntry>,
+    events: Vec<Event>,
+}
+
+/// What changes to `list` are logged under: its day, or `list:<name>`.
+fn list_key(list: &TodoList) -> String {
+    match list.name {
+        Some(ref name) => format!("list:{name}"),
+        None => list.date.format("%Y-%m-%d").to_string(),
+    }
+}
+
+impl Lists {
+    /// Whether `id` is on any list or waiting in the scheduled area.
+    fn is_live(&self, id: Uuid) -> bool {
+        self.days
+            .values()
+            .chain(self.named.values())
+            .any(|stored| stored.items.iter().any(|item| item.id == id))
+            || self.scheduled.iter().any(|(_, item)| item.id == id)
+    }
+
+    /// Every stored todo with the day it counts for: live and archived days, the start date
+    /// of a scheduled item, and today for a project list. The trash is left out.
+    fn occurrences(&self) -> impl Iterator<Item = (NaiveDate, &TodoItem)> {
+        let today = Local::now().date_naive();
+        let days = self
+            .days
+            .iter()
+            .flat_map(|(date, stored)| stored.items.iter().map(move |item| (*date, item)));
+        let archived = self
+            .archived
+            .iter()
+            .flat_map(|(date, items)| items.iter().map(move |item| (*date, item)));
+        let scheduled = self.scheduled.iter().map(|(start, item)| (*start, item));
+        let named = self
+            .named
+            .values()
+            .flat_map(move |stored| stored.items.iter().map(move |item| (today, item)));
+        days.chain(archived).chain(scheduled).chain(named)
+    }
+
+    /// Occurrences of the task `origin`, oldest first.
+    fn lineage(&self, origin: Uuid) -> Vec<(NaiveDate, &TodoItem)> {
+        let mut found: Vec<(NaiveDate, &TodoItem)> = self
+            .occurrences()
+            .filter(|(_, item)| item.id == origin || item.origin_id == Some(origin))
+            .collect();
+        found.sort_by_key(|(date, _)| *date);
+        found
+    }
+
+    /// Lineage id of the todo `id`, including one in the trash.
+    fn origin_of(&self, id: Uuid) -> Option<Uuid> {
+        self.occurrences()
+            .map(|(_, item)| item)
+            .chain(self.trash.iter().map(|entry| &entry.item))
+            .find(|item| item.id == id)
+            .map(TodoItem::origin)
+    }
+
+    /// Where `id` is live other than under `key`: a day, `list:<name>` or
+    /// `scheduled:<start date>`.
+    fn live_elsewhere(&self, id: Uuid, key: &str) -> Option<String> {
+        let days = self
+            .days
+            .iter()
+            .map(|(date, stored)| (date.format("%Y-%m-%d").to_string(), stored));
+        let named = self.named.iter().map(|(name, stored)| (format!("list:{name}"), stored));
+        days.chain(named)
+            .filter(|(other, _)| other != key)
+            .find(|(_, stored)| stored.items.iter().any(|item| item.id == id))
+            .map(|(other, _)| other)
+            .or_else(|| {
+                self.scheduled
+                    .iter()
+                    .find(|(_, item)| item.id == id)
+                    .map(|(start, _)| format!("scheduled:{}", start.format("%Y-%m-%d")))
+            })
+    }
+
+    /// Latest day another occurrence of the task `item` continues was on.
+    fn previous_occurrence(&self, item: &TodoItem) -> Option<String> {
+        let days = self
+            .days
+            .iter()
+            .flat_map(|(date, stored)| stored.items.iter().map(move |other| (*date, other)));
+        let archived = self
+            .archived
+            .iter()
+            .flat_map(|(date, items)| items.iter().map(move |other| (*date, other)));
+        days.chain(archived)
+            .filter(|(_, other)| other.origin() == item.origin() && other.id != item.id)
+            .map(|(date, _)| date)
+            .max()
+            .map(|date| date.format("%Y-%m-%d").to_string())
+    }
+
+    /// Log saving `items` over the `previous` contents of the list `key`. Returns the lineage
+    /// ids stored there before, except for items that moved to another list.
+    fn record_save(&mut self, key: &str, previous: &[TodoItem], items: &[TodoItem]) -> HashSet<Uuid> {
+        let mut whereabouts = Whereabouts::default();
+        for item in items.iter().filter(|item| !previous.iter().any(|old| old.id == item.id)) {
+            if let Some(from) = self.live_elsewhere(item.id, key) {
+                whereabouts.arrived.insert(item.id, from);
+            } else if self.trash.iter().any(|entry| entry.item.id == item.id) {
+                whereabouts.restored.insert(item.id);
+            } else if item.origin() != item.id
+                && let Some(day) = self.previous_occurrence(item)
+            {
+                whereabouts.rolled_from.insert(item.id, day);
+            }
+        }
+        for old in previous.iter().filter(|old| !items.iter().any(|item| item.id == old.id)) {
+            if let Some(to) = self.live_elsewhere(old.id, key) {
+                // A move between lists is logged once, when the item arrives
+                whereabouts
+                    .departed
+                    .insert(old.id, to.starts_with("scheduled:").then_some(to));
+            }
+        }
+
+        self.events.extend(diff_events(key, previous, items, &whereabouts));
+        previous
+            .iter()
+            .filter(|item| !matches!(whereabouts.departed.get(&item.id), Some(None)))
+            .map(TodoItem::origin)
+            .collect()
+    }
+
+    /// Open or close intervals so they match the in-progress `items` of a saved list, the way
+    /// `time_tracking::sync_time_entries` does.
+    fn sync_time_entries(&mut self, list_origins: &HashSet<Uuid>, items: &[TodoItem]) {
+        let now = Utc::now();
+        let running: HashSet<Uuid> = items
+            .iter()
+            .filter(|item| item.state == TodoState::InProgress)
+            .map(TodoItem::origin)
+            .collect();
+        let stopped: HashSet<Uuid> = list_origins
+            .iter()
+            .copied()
+            .chain(items.iter().map(TodoItem::origin))
+            .filter(|origin| !running.contains(origin))
+            .collect();
+
+        for entry in &mut self.time_entries {
+            if entry.stopped_at.is_none() && stopped.contains(&entry.origin_id) {
+                entry.stopped_at = Some(now);
+            }
+        }
+        for origin in running {
+            if !self
+                .time_entries
+                .iter()
+                .any(|entry| entry.origin_id == origin && entry.stopped_at.is_none())
+            {
+                self.time_entries.push(TimeEntry {
+                    origin_id: origin,
+                    started_at: now,
+                    stopped_at: None,
+                });
+            }
+        }
+    }
+
+    /// Put `removed` items, with the index each had, in the trash unless they moved to
+    /// another list.
+    fn trash(&mut self, removed: Vec<(usize, TodoItem)>, date: Option<NaiveDate>, list: Option<&str>) {
+        let now = Utc::now();
+        for (position, mut item) in removed {
+            if self.is_live(item.id) {
+                continue;
+            }
+            item.deleted_at = Some(now);
+            self.trash.insert(
+                0,
+                TrashedTodo {
+                    item,
+                    date,
+                    list: list.map(str::to_string),
+                    position,
+                },
+            );
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct StoredList {
+    items: Vec<TodoItem>,
+    notes: Option<String>,
+    trailing_notes: Option<String>,
+    revision: u64,
+}
+
+impl StoredList {
+    fn to_list(&self, mut list: TodoList) -> TodoList {
+        list.items = self.items.clone();
+        list.notes = self.notes.clone();
+        list.trailing_notes = self.trailing_notes.clone();
+        list.mark_stored(self.revision);
+        list
+    }
+}
+
+impl InMemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lists(&self) -> std::sync::MutexGuard<'_, Lists> {
+        // A panic elsewhere cannot leave the maps half-updated, so keep going
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl TodoStore for InMemoryStore {
+    fn load_todo_list(&self, date: NaiveDate) -> Result<TodoList> {
+        let list = TodoList::new(date, PathBuf::new());
+        let mut list = match self.lists().days.get(&date) {
+            Some(stored) => stored.to_list(list),
+            None => list,
+        };
+        self.merge_due_scheduled(&mut list)?;
+        Ok(list)
+    }
+
+    fn load_named_list(&self, name: &str) -> Result<TodoList> {
+        let name = validate_list_name(name)?;
+        let list = TodoList::named(name, Local::now().date_naive(), PathBuf::new(), Vec::new());
+        Ok(match self.lists().named.get(name) {
+            Some(stored) => stored.to_list(list),
+            None => list,
+        })
+    }
+
+    fn load_todos_for_viewing(&self, date: NaiveDate) -> Result<TodoList> {
+        if date == Local::now().date_naive() {
+            return self.load_todo_list(date);
+        }
+        let lists = self.lists();
+        if let Some(items) = lists.archived.get(&date).filter(|items| !items.is_empty()) {
+            return Ok(TodoList::with_items(date, PathBuf::new(), items.clone()));
+        }
+        let list = TodoList::new(date, PathBuf::new());
+        Ok(match lists.days.get(&date) {
+            Some(stored) => stored.to_list(list),
+            None => list,
+        })
+    }
+
+    fn list_names(&self) -> Result<Vec<String>> {
+        Ok(self.lists().named.keys().cloned().collect())
+    }
+
+    fn create_named_list(&self, name: &str) -> Result<()> {
+        let name = validate_list_name(name)?;
+        self.lists().named.entry(name.to_string()).or_default();
+        Ok(())
+    }
+
+    fn save_todo_list(&self, list: &mut TodoList) -> Result<()> {
+        list.normalize_sections();
+        let mut lists = self.lists();
+        let stored = match list.name {
+            Some(ref name) => lists.named.entry(name.clone()).or_default(),
+            None => lists.days.entry(list.date).or_default(),
+        };
+
+        if stored.revision != list.revision {
+            let conflict = SaveConflict {
+                list: list.title(),
+                expected: list.revision,
+                found: stored.revision,
+            };
+            let Some(merged) = rebase(&list.base_items, &list.items, &stored.items) else {
+                return Err(conflict.into());
+            };
+            list.base_items = stored.items.clone();
+            list.items = merged;
+            list.recalculate_parent_ids();
+            list.normalize_sections();
+        }
+
+        let previous = std::mem::replace(&mut stored.items, list.items.clone());
+        stored.notes = list.notes.clone();
+        stored.trailing_notes = list.trailing_notes.clone();
+        stored.revision += 1;
+        let merged = std::mem::take(&mut list.merged_scheduled);
+        list.mark_stored(stored.revision);
+
+        let list_origins = lists.record_save(&list_key(list), &previous, &list.items);
+        lists.sync_time_entries(&list_origins, &list.items);
+
+        let mut removed: Vec<(usize, TodoItem)> = previous
+            .into_iter()
+            .enumerate()
+            .filter(|(_, item)| !list.items.iter().any(|saved| saved.id == item.id))
+            .collect();
+        // Scheduled items merged into the list and deleted before this save
+        for item in merged {
+            let scheduled = lists.scheduled.len();
+            lists.scheduled.retain(|(_, waiting)| waiting.id != item.id);
+            if lists.scheduled.len() < scheduled && !list.items.iter().any(|saved| saved.id == item.id) {
+                removed.push((list.items.len(), item));
+            }
+        }
+        let (date, name) = match list.name {
+            Some(ref name) => (None, Some(name.as_str())),
+            None => (Some(list.date), None),
+        };
+        lists.trash(removed, date, name);
+        lists
+            .trash
+            .retain(|entry| !list.items.iter().any(|saved| saved.id == entry.item.id));
+
+        // Saving a scheduled item into a day claims it from the scheduled area
+        if list.name.is_none() {
+            lists
+                .scheduled
+                .retain(|(_, item)| !list.items.iter().any(|saved| saved.id == item.id));
+        }
+        Ok(())
+    }
+
+    fn has_todos_for_date(&self, date: NaiveDate) -> Result<bool> {
+        Ok(self
+            .lists()
+            .days
+            .get(&date)
+            .is_some_and(|stored| !stored.items.is_empty()))
+    }
+
+    fn archive_todos_for_date(&self, date: NaiveDate) -> Result<usize> {
+        let mut lists = self.lists();
+        let Some(stored) = lists.days.remove(&date) else {
+            return Ok(0);
+        };
+        let count = stored.items.len();
+        lists.archived.entry(date).or_default().extend(stored.items);
+        Ok(count)
+    }
+
+    fn soft_delete_todos(&self, ids: &[Uuid], date: NaiveDate) -> Result<()> {
+        let mut lists = self.lists();
+        let Some(stored) = lists.days.get_mut(&date) else {
+            return Ok(());
+        };
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut stored.items)
+            .into_iter()
+            .enumerate()
+            .partition(|(_, item)| ids.contains(&item.id));
+        stored.items = kept.into_iter().map(|(_, item)| item).collect();
+        let key = date.format("%Y-%m-%d").to_string();
+        lists
+            .events
+            .extend(removed.iter().map(|(_, item)| Event::new(EventKind::Delete, item, &key)));
+        lists.trash(removed, Some(date), None);
+        Ok(())
+    }
+
+    fn load_trash(&self) -> Result<Vec<TrashedTodo>> {
+        Ok(self.lists().trash.clone())
+    }
+
+    fn purge_trash(&self, ids: &[Uuid]) -> Result<usize> {
+        let mut lists = self.lists();
+        let (purged, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut lists.trash)
+            .into_iter()
+            .partition(|entry| ids.contains(&entry.item.id));
+        lists.trash = kept;
+        for entry in &purged {
+            let list_key = match entry.list {
+                Some(ref name) => format!("list:{name}"),
+                None => entry.origin_label(),
+            };
+            lists.events.push(Event::new(EventKind::Purge, &entry.item, &list_key));
+        }
+        Ok(purged.len())
+    }
+
+    fn schedule_future_items(&self, list: &mut TodoList) -> Result<Vec<TodoItem>> {
+        if list.name.is_some() {
+            return Ok(Vec::new());
+        }
+        let taken = list.take_scheduled();
+        let mut lists = self.lists();
+        lists
+            .scheduled
+            .retain(|(_, item)| !taken.iter().any(|moved| moved.id == item.id));
+        let mut start = list.date;
+        for item in &taken {
+            if item.indent_level == 0 {
+                start = item.start_date.unwrap_or(start);
+            }
+            lists.scheduled.push((start, item.clone()));
+        }
+        // Stable, so each subtree stays in order behind its root
+        lists.scheduled.sort_by_key(|(start, _)| *start);
+        Ok(taken)
+    }
+
+    fn merge_due_scheduled(&self, list: &mut TodoList) -> Result<()> {
+        let today = Local::now().date_naive();
+        if list.name.is_some() || list.date < today {
+            return Ok(());
+        }
+        let due: Vec<TodoItem> = self
+            .lists()
+            .scheduled
+            .iter()
+            .filter(|(start, _)| *start <= list.date && (list.date == today || *start == list.date))
+            .filter(|(_, item)| !list.items.iter().any(|existing| existing.id == item.id))
+            .map(|(_, item)| item.clone())
+            .collect();
+        list.merge_scheduled(due);
+        Ok(())
+    }
+
+    fn load_scheduled_todos(&self, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<Vec<TodoItem>> {
+        Ok(self
+            .lists()
+            .scheduled
+            .iter()
+            .filter(|(start, _)| from.is_none_or(|from| *start >= from) && to.is_none_or(|to| *start <= to))
+            .map(|(_, item)| item.clone())
+            .collect())
+    }
+
+    /// No markdown files are written, so there is never anything to take in.
+    fn sync_daily_file(&self, _date: NaiveDate) -> Result<SyncOutcome> {
+        Ok(SyncOutcome::Unchanged)
+    }
+
+    fn load_open_blockers(&self, blocker_ids: &[Uuid]) -> Result<HashSet<Uuid>> {
+        let lists = self.lists();
+        Ok(blocker_ids
+            .iter()
+            .copied()
+            .filter(|&id| lists.lineage(id).last().is_some_and(|(_, item)| !item.is_complete()))
+            .collect())
+    }
+
+    fn resolve_origin_id(&self, id: Uuid) -> Result<Option<Uuid>> {
+        Ok(self.lists().origin_of(id))
+    }
+
+    fn load_todo_history(&self, id: Uuid) -> Result<Vec<(NaiveDate, TodoItem)>> {
+        let lists = self.lists();
+        let Some(origin) = lists.origin_of(id) else {
+            return Ok(Vec::new());
+        };
+        Ok(lists
+            .lineage(origin)
+            .into_iter()
+            .map(|(date, item)| (date, item.clone()))
+            .collect())
+    }
+
+    /// A case-insensitive substring match stands in for the full-text index, so hits are
+    /// not ranked, only ordered latest day first.
+    fn search_todos(&self, text: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let words: Vec<String> = text
+            .split_whitespace()
+            .map(|word| word.trim_start_matches('#').to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+        let matches = |item: &TodoItem| {
+            let text = format!("{} {}", item.content, item.description.as_deref().unwrap_or(""));
+            let text = text.to_lowercase();
+            words.iter().all(|word| text.contains(word.as_str()))
+        };
+
+        let lists = self.lists();
+        let days = lists
+            .days
+            .iter()
+            .flat_map(|(date, stored)| stored.items.iter().map(move |item| (Some(*date), None, false, item)));
+        let archived = lists
+            .archived
+            .iter()
+            .flat_map(|(date, items)| items.iter().map(move |item| (Some(*date), None, true, item)));
+        let named = lists
+            .named
+            .iter()
+            .flat_map(|(name, stored)| stored.items.iter().map(move |item| (None, Some(name), false, item)));
+        let mut hits: Vec<SearchHit> = days
+            .chain(archived)
+            .chain(named)
+            .filter(|(.., item)| matches(item))
+            .map(|(date, list, archived, item)| SearchHit {
+                id: item.id,
+                content: item.content.clone(),
+                state: item.state,
+                date,
+                list: list.cloned(),
+                archived,
+                snippet: item.content.clone(),
+                rank: 0.0,
+            })
+            .collect();
+        hits.sort_by_key(|hit| std::cmp::Reverse(hit.date));
+        hits.truncate(limit);
+        Ok(hits)
+    }
+
+    fn load_comments(&self, todo_id: Uuid) -> Result<Option<Vec<Comment>>> {
+        let lists = self.lists();
+        Ok(lists.origin_of(todo_id).map(|origin| {
+            lists
+                .comments
+                .iter()
+                .filter(|comment| comment.todo_id == origin)
+                .cloned()
+                .collect()
+        }))
+    }
+
+    fn add_comment(&self, todo_id: Uuid, author: &str, body: &str) -> Result<Option<Comment>> {
+        let mut lists = self.lists();
+        let Some(origin) = lists.origin_of(todo_id) else {
+            return Ok(None);
+        };
+        let comment = Comment {
+            id: lists.comments.len() as i64 + 1,
+            todo_id: origin,
+            author: author.to_string(),
+            body: body.to_string(),
+            created_at: Utc::now(),
+        };
+        lists.comments.push(comment.clone());
+        Ok(Some(comment))
+    }
+
+    fn load_tracked_time(&self, origin_ids: &[Uuid]) -> Result<HashMap<Uuid, TrackedTime>> {
+        let mut result: HashMap<Uuid, TrackedTime> = HashMap::new();
+        for entry in self
+            .lists()
+            .time_entries
+            .iter()
+            .filter(|entry| origin_ids.contains(&entry.origin_id))
+        {
+            let tracked = result.entry(entry.origin_id).or_default();
+            match entry.stopped_at {
+                Some(stopped) => tracked.closed += stopped - entry.started_at,
+                None => tracked.running_since = Some(entry.started_at),
+            }
+        }
+        Ok(result)
+    }
+
+    fn load_events(&self, filter: &EventFilter) -> Result<Vec<Event>> {
+        let lists = self.lists();
+        // A todo that was removed for good is only known by its events
+        let origin = filter.todo_id.map(|id| lists.origin_of(id).unwrap_or(id));
+        let mut events: Vec<Event> = lists
+            .events
+            .iter()
+            .rev()
+            .filter(|event| filter.source.is_none_or(|source| event.source == source.as_str()))
+            .filter(|event| origin.is_none_or(|id| event.origin_id == id || event.todo_id == id))
+            .filter(|event| filter.since.is_none_or(|since| event.occurred_at >= since))
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect();
+        events.reverse();
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::TodoState;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let store = InMemoryStore::new();
+        let mut list = store.load_todo_list(date(1)).unwrap();
+        list.add_item("Write report".to_string());
+        list.notes = Some("Quiet day".to_string());
+        list.trailing_notes = Some("Early night".to_string());
+        store.save_todo_list(&mut list).unwrap();
+
+        let loaded = store.load_todo_list(date(1)).unwrap();
+        assert_eq!(loaded.items.len(), 1);
+        assert_eq!(loaded.items[0].content, "Write report");
+        assert_eq!(loaded.notes.as_deref(), Some("Quiet day"));
+        assert_eq!(loaded.trailing_notes.as_deref(), Some("Early night"));
+        assert_eq!(loaded.revision, 1);
+        assert!(store.has_todos_for_date(date(1)).unwrap());
+        assert!(!store.has_todos_for_date(date(2)).unwrap());
+    }
+
+    #[test]
+    fn test_concurrent_saves_are_rebased_or_refused() {
+        let store = InMemoryStore::new();
+        let mut list = store.load_todo_list(date(1)).unwrap();
+        list.add_item("One".to_string());
+        list.add_item("Two".to_string());
+        store.save_todo_list(&mut list).unwrap();
+
+        let mut mine = store.load_todo_list(date(1)).unwrap();
+        let mut theirs = store.load_todo_list(date(1)).unwrap();
+        theirs.items[1].state = TodoState::Checked;
+        store.save_todo_list(&mut theirs).unwrap();
+
+        // Different items combine
+        mine.items[0].content = "One, edited".to_string();
+        store.save_todo_list(&mut mine).unwrap();
+        let loaded = store.load_todo_list(date(1)).unwrap();
+        assert_eq!(loaded.items[0].content, "One, edited");
+        assert_eq!(loaded.items[1].state, TodoState::Checked);
+
+        // The same item does not
+        let mut stale = loaded.clone();
+        let mut fresh = loaded;
+        fresh.items[0].content = "Theirs".to_string();
+        store.save_todo_list(&mut fresh).unwrap();
+        stale.items[0].content = "Mine".to_string();
+        let err = store.save_todo_list(&mut stale).unwrap_err();
+        assert!(err.is::<SaveConflict>());
+    }
+
+    #[test]
+    fn test_archive_and_soft_delete() {
+        let store = InMemoryStore::new();
+        let mut list = store.load_todo_list(date(1)).unwrap();
+        list.add_item("Keep".to_string());
+        list.add_item("Drop".to_string());
+        store.save_todo_list(&mut list).unwrap();
+
+        store.soft_delete_todos(&[list.items[1].id], date(1)).unwrap();
+        assert_eq!(store.archive_todos_for_date(date(1)).unwrap(), 1);
+        assert!(!store.has_todos_for_date(date(1)).unwrap());
+
+        let archived = store.load_todos_for_viewing(date(1)).unwrap();
+        assert_eq!(archived.items.len(), 1);
+        assert_eq!(archived.items[0].content, "Keep");
+
+        let trash = store.load_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!((trash[0].date, trash[0].position), (Some(date(1)), 1));
+    }
+
+    #[test]
+    fn test_deleted_items_are_restored_or_purged() {
+        let store = InMemoryStore::new();
+        let today = Local::now().date_naive();
+        let mut list = store.load_todo_list(today).unwrap();
+        list.add_item_with_indent("Plan".to_string(), 0);
+        list.add_item_with_indent("Draft".to_string(), 1);
+        list.add_item_with_indent("Review".to_string(), 1);
+        list.add_item_with_indent("Ship".to_string(), 0);
+        list.recalculate_parent_ids();
+        store.save_todo_list(&mut list).unwrap();
+
+        // Deleting by saving without the items; moving one does not count
+        let draft = list.items[1].id;
+        list.items.remove(1);
+        store.save_todo_list(&mut list).unwrap();
+        let mut backlog = store.load_named_list("backlog").unwrap();
+        store.move_to_list(&mut list, 2, &mut backlog).unwrap();
+        assert_eq!(store.load_trash().unwrap().len(), 1);
+
+        let (restored, range) = store.restore_from_trash(draft).unwrap().unwrap();
+        assert_eq!(range, 1..2);
+        let contents: Vec<&str> = restored.items.iter().map(|item| item.content.as_str()).collect();
+        assert_eq!(contents, vec!["Plan", "Draft", "Review"]);
+        assert_eq!(restored.items[1].parent_id, Some(restored.items[0].id));
+        assert!(store.load_trash().unwrap().is_empty());
+        assert!(store.restore_from_trash(draft).unwrap().is_none());
+
+        // A past day's todo comes back on today's list
+        let mut old = store.load_todo_list(date(1)).unwrap();
+        old.add_item("Old".to_string());
+        store.save_todo_list(&mut old).unwrap();
+        store.soft_delete_todos(&[old.items[0].id], date(1)).unwrap();
+        let (restored, _) = store.restore_from_trash(old.items[0].id).unwrap().unwrap();
+        assert_eq!(restored.date, today);
+
+        let mut list = store.load_todo_list(today).unwrap();
+        list.items.clear();
+        store.save_todo_list(&mut list).unwrap();
+        assert_eq!(store.load_trash().unwrap().len(), 4);
+        assert_eq!(store.purge_trash_before(chrono::Utc::now() - chrono::Duration::hours(1)).unwrap(), 0);
+        assert_eq!(store.purge_trash_before(chrono::Utc::now() + chrono::Duration::seconds(1)).unwrap(), 4);
+        assert!(store.load_trash().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_named_lists_and_moves() {
+        let store = InMemoryStore::new();
+        store.create_named_list("backlog").unwrap();
+        assert!(store.create_named_list("../escape").is_err());
+        assert_eq!(store.list_names().unwrap(), vec!["backlog"]);
+
+        let mut day = store.load_todo_list(date(1)).unwrap();
+        day.add_item_with_indent("Parent".to_string(), 0);
+        day.add_item_with_indent("Child".to_string(), 1);
+        store.save_todo_list(&mut day).unwrap();
+
+        let mut backlog = store.load_named_list("backlog").unwrap();
+        assert_eq!(store.move_to_list(&mut day, 0, &mut backlog).unwrap(), 2);
+
+        assert!(store.load_todo_list(date(1)).unwrap().items.is_empty());
+        let backlog = store.load_list(Some("backlog")).unwrap();
+        assert_eq!(backlog.items.len(), 2);
+        assert_eq!(backlog.items[1].parent_id, Some(backlog.items[0].id));
+    }
+
+    #[test]
+    fn test_future_items_wait_until_their_start_date() {
+        let store = InMemoryStore::new();
+        let today = Local::now().date_naive();
+        let tomorrow = today.succ_opt().unwrap();
+
+        let mut list = store.load_todo_list(today).unwrap();
+        list.add_item("Later".to_string());
+        list.items[0].start_date = Some(tomorrow);
+        let scheduled = store.schedule_future_items(&mut list).unwrap();
+        store.save_todo_list(&mut list).unwrap();
+        assert_eq!(scheduled.len(), 1);
+        assert!(list.items.is_empty());
+
+        assert!(store.load_todo_list(today).unwrap().items.is_empty());
+        let mut next = store.load_todo_list(tomorrow).unwrap();
+        assert_eq!(next.items[0].content, "Later");
+        assert_eq!(next.items[0].start_date, None);
+
+        // Saving the day claims the item
+        store.save_todo_list(&mut next).unwrap();
+        assert!(store.lists().scheduled.is_empty());
+    }
+
+    #[test]
+    fn test_merged_scheduled_item_deleted_before_saving_stays_deleted() {
+        let store = InMemoryStore::new();
+        let today = Local::now().date_naive();
+
+        let mut list = store.load_todo_list(today).unwrap();
+        list.add_item("Later".to_string());
+        list.items[0].start_date = today.succ_opt();
+        store.schedule_future_items(&mut list).unwrap();
+        store.lists().scheduled[0].0 = today;
+
+        let mut list = store.load_todo_list(today).unwrap();
+        assert_eq!(list.items[0].content, "Later");
+        list.items.clear();
+        store.save_todo_list(&mut list).unwrap();
+
+        assert!(store.load_todo_list(today).unwrap().items.is_empty());
+        assert!(store.lists().scheduled.is_empty());
+        assert_eq!(store.load_trash().unwrap()[0].item.content, "Later");
+    }
+
+    #[test]
+    fn test_time_events_and_blockers_follow_saves() {
+        let store = InMemoryStore::new();
+        let mut list = store.load_named_list("work").unwrap();
+        list.add_item("Draft".to_string());
+        store.save_todo_list(&mut list).unwrap();
+        let draft = list.items[0].id;
+        assert_eq!(store.load_open_blockers(&[draft]).unwrap(), HashSet::from([draft]));
+
+        list.items[0].set_state(TodoState::InProgress);
+        store.save_todo_list(&mut list).unwrap();
+        let tracked = store.load_tracked_time(&[draft]).unwrap();
+        assert!(tracked[&draft].running_since.is_some());
+
+        list.items[0].set_state(TodoState::Checked);
+        store.save_todo_list(&mut list).unwrap();
+        let tracked = store.load_tracked_time(&[draft]).unwrap();
+        assert!(tracked[&draft].running_since.is_none());
+        assert!(store.load_open_blockers(&[draft]).unwrap().is_empty());
+
+        let filter = EventFilter {
+            todo_id: Some(draft),
+            ..Default::default()
+        };
+        let kinds: Vec<&str> = store
+            .load_events(&filter)
+            .unwrap()
+            .iter()
+            .map(|event| event.kind.as_str())
+            .collect();
+        assert_eq!(kinds, vec!["create", "state", "state"]);
+    }
+}
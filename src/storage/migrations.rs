@@ -0,0 +1,506 @@
+use anyhow::{Context, Result, bail};
+use rusqlite::{Connection, Transaction};
+
+/// One schema change. The database's `PRAGMA user_version` records the last applied step.
+#[derive(Debug)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+/// Every schema change in order. Append new steps at the end and never edit a shipped one.
+///
+/// Databases created before versioning report version 0 but may already have any of these
+/// tables, so the early steps only create what is missing.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "daily and archived todos",
+        apply: create_todos,
+    },
+    Migration {
+        version: 2,
+        description: "tags and dependencies",
+        apply: create_tags_and_dependencies,
+    },
+    Migration {
+        version: 3,
+        description: "scheduled todos",
+        apply: create_scheduled_todos,
+    },
+    Migration {
+        version: 4,
+        description: "time entries",
+        apply: create_time_entries,
+    },
+    Migration {
+        version: 5,
+        description: "comments",
+        apply: create_comments,
+    },
+    Migration {
+        version: 6,
+        description: "project lists",
+        apply: create_project_lists,
+    },
+    Migration {
+        version: 7,
+        description: "list revisions",
+        apply: create_list_revisions,
+    },
+    Migration {
+        version: 8,
+        description: "full-text search",
+        apply: create_search_index,
+    },
+    Migration {
+        version: 9,
+        description: "change events",
+        apply: create_events,
+    },
+    Migration {
+        version: 10,
+        description: "markdown snapshots",
+        apply: create_markdown_snapshots,
+    },
+    Migration {
+        version: 11,
+        description: "sections and notes",
+        apply: create_sections_and_notes,
+    },
+    Migration {
+        version: 12,
+        description: "trailing notes",
+        apply: add_trailing_notes,
+    },
+];
+
+/// Schema version this build writes.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .context("Failed to read schema version")
+}
+
+/// Migrations not yet applied to this database, oldest first.
+pub fn pending_migrations(conn: &Connection) -> Result<Vec<&'static Migration>> {
+    let current = schema_version(conn)?;
+    Ok(MIGRATIONS.iter().filter(|m| m.version > current).collect())
+}
+
+/// Bring the schema up to date. Each step runs in its own transaction together with the
+/// version bump, so a failed step leaves the database at the previous version.
+/// Refuses databases written by a newer build. Returns the steps that were applied.
+pub fn migrate(conn: &mut Connection) -> Result<Vec<&'static Migration>> {
+    let current = schema_version(conn)?;
+    let latest = latest_version();
+    if current > latest {
+        bail!(
+            "Database schema version {current} is newer than this build supports ({latest}). \
+             Upgrade totui before opening this database."
+        );
+    }
+
+    let pending = pending_migrations(conn)?;
+    for migration in &pending {
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx).with_context(|| {
+            format!(
+                "Migration {} ({}) failed",
+                migration.version, migration.description
+            )
+        })?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+
+    Ok(pending)
+}
+
+/// Add a column unless the table already has it. Used for tables that predate versioning
+/// and were widened in place.
+fn add_column_if_missing(
+    tx: &Transaction,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<()> {
+    let exists: bool = tx.query_row(
+        "SELECT EXISTS (SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+        [table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        tx.execute(&format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"), [])?;
+    }
+    Ok(())
+}
+
+fn create_todos(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS todos (
+            id TEXT PRIMARY KEY,
+            date TEXT NOT NULL,
+            content TEXT NOT NULL,
+            state TEXT NOT NULL,
+            indent_level INTEGER NOT NULL,
+            parent_id TEXT,
+            due_date TEXT,
+            description TEXT,
+            collapsed INTEGER NOT NULL DEFAULT 0,
+            position INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            completed_at TEXT,
+            deleted_at TEXT,
+            recurrence TEXT,
+            series_id TEXT,
+            origin_id TEXT,
+            carry_count INTEGER NOT NULL DEFAULT 0,
+            start_date TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS archived_todos (
+            id TEXT PRIMARY KEY,
+            original_date TEXT NOT NULL,
+            archived_at TEXT NOT NULL,
+            content TEXT NOT NULL,
+            state TEXT NOT NULL,
+            indent_level INTEGER NOT NULL,
+            parent_id TEXT,
+            due_date TEXT,
+            description TEXT,
+            collapsed INTEGER NOT NULL DEFAULT 0,
+            position INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            completed_at TEXT,
+            deleted_at TEXT,
+            recurrence TEXT,
+            series_id TEXT,
+            origin_id TEXT,
+            carry_count INTEGER NOT NULL DEFAULT 0,
+            start_date TEXT
+        );",
+    )?;
+
+    // Columns added to both tables before the schema was versioned
+    for table in ["todos", "archived_todos"] {
+        add_column_if_missing(tx, table, "collapsed", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(tx, table, "completed_at", "TEXT")?;
+        add_column_if_missing(tx, table, "deleted_at", "TEXT")?;
+        add_column_if_missing(tx, table, "recurrence", "TEXT")?;
+        add_column_if_missing(tx, table, "series_id", "TEXT")?;
+        add_column_if_missing(tx, table, "origin_id", "TEXT")?;
+        add_column_if_missing(tx, table, "carry_count", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(tx, table, "start_date", "TEXT")?;
+    }
+
+    tx.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_todos_date ON todos(date);
+        CREATE INDEX IF NOT EXISTS idx_todos_parent_id ON todos(parent_id);
+        CREATE INDEX IF NOT EXISTS idx_todos_origin_id ON todos(origin_id);
+        CREATE INDEX IF NOT EXISTS idx_archived_todos_original_date ON archived_todos(original_date);
+        CREATE INDEX IF NOT EXISTS idx_archived_todos_origin_id ON archived_todos(origin_id);",
+    )
+}
+
+fn create_tags_and_dependencies(tx: &Transaction) -> rusqlite::Result<()> {
+    // Tags are keyed by todo id, so they follow a row from todos into archived_todos.
+    // blocker_id is the blocker's lineage id, so links survive rollover on both ends.
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS todo_tags (
+            todo_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (todo_id, tag)
+        );
+        CREATE INDEX IF NOT EXISTS idx_todo_tags_tag ON todo_tags(tag COLLATE NOCASE);
+
+        CREATE TABLE IF NOT EXISTS todo_dependencies (
+            todo_id TEXT NOT NULL,
+            blocker_id TEXT NOT NULL,
+            PRIMARY KEY (todo_id, blocker_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_todo_dependencies_blocker_id ON todo_dependencies(blocker_id);",
+    )
+}
+
+fn create_scheduled_todos(tx: &Transaction) -> rusqlite::Result<()> {
+    // Todos whose start date has not arrived yet. They belong to no day until they are
+    // merged into the list for their start date; tags and dependencies stay keyed by id.
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS scheduled_todos (
+            id TEXT PRIMARY KEY,
+            start_date TEXT NOT NULL,
+            content TEXT NOT NULL,
+            state TEXT NOT NULL,
+            indent_level INTEGER NOT NULL,
+            parent_id TEXT,
+            due_date TEXT,
+            description TEXT,
+            collapsed INTEGER NOT NULL DEFAULT 0,
+            position INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            recurrence TEXT,
+            series_id TEXT,
+            origin_id TEXT,
+            carry_count INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE INDEX IF NOT EXISTS idx_scheduled_todos_start_date ON scheduled_todos(start_date);",
+    )
+}
+
+fn create_time_entries(tx: &Transaction) -> rusqlite::Result<()> {
+    // Intervals spent in [*], keyed by lineage id so they outlive rollover and archiving
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS time_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            origin_id TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            stopped_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_time_entries_origin_id ON time_entries(origin_id);
+        CREATE INDEX IF NOT EXISTS idx_time_entries_started_at ON time_entries(started_at);",
+    )
+}
+
+fn create_comments(tx: &Transaction) -> rusqlite::Result<()> {
+    // Append-only comment threads, keyed by lineage id like time entries
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS comments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            todo_id TEXT NOT NULL,
+            author TEXT NOT NULL,
+            body TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_comments_todo_id ON comments(todo_id);",
+    )
+}
+
+fn create_project_lists(tx: &Transaction) -> rusqlite::Result<()> {
+    // Named project lists such as a backlog. Items live outside any day and never roll over;
+    // tags and dependencies stay keyed by id like everywhere else.
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS lists (
+            name TEXT PRIMARY KEY,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS list_todos (
+            id TEXT PRIMARY KEY,
+            list_name TEXT NOT NULL,
+            content TEXT NOT NULL,
+            state TEXT NOT NULL,
+            indent_level INTEGER NOT NULL,
+            parent_id TEXT,
+            due_date TEXT,
+            description TEXT,
+            collapsed INTEGER NOT NULL DEFAULT 0,
+            position INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            completed_at TEXT,
+            deleted_at TEXT,
+            recurrence TEXT,
+            series_id TEXT,
+            origin_id TEXT,
+            carry_count INTEGER NOT NULL DEFAULT 0,
+            start_date TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_list_todos_list_name ON list_todos(list_name);",
+    )
+}
+
+fn create_list_revisions(tx: &Transaction) -> rusqlite::Result<()> {
+    // One counter per day or project list, bumped on every save so concurrent writers
+    // notice each other. A missing row is revision 0.
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS list_revisions (
+            list_key TEXT PRIMARY KEY,
+            revision INTEGER NOT NULL
+        );",
+    )
+}
+
+/// Tables whose live rows are searchable, each indexed under its own name.
+const SEARCHABLE_TABLES: [&str; 3] = ["todos", "archived_todos", "list_todos"];
+
+fn create_search_index(tx: &Transaction) -> rusqlite::Result<()> {
+    // One FTS5 table over content and description of every live todo, kept in sync by
+    // triggers so saves, archiving and deletes need no extra code
+    tx.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS todo_search USING fts5(
+            content,
+            description,
+            todo_id UNINDEXED,
+            source UNINDEXED,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );",
+    )?;
+
+    for table in SEARCHABLE_TABLES {
+        tx.execute_batch(&format!(
+            "CREATE TRIGGER IF NOT EXISTS {table}_search_insert AFTER INSERT ON {table}
+             WHEN new.deleted_at IS NULL
+             BEGIN
+                 INSERT INTO todo_search (content, description, todo_id, source)
+                 VALUES (new.content, new.description, new.id, '{table}');
+             END;
+
+             CREATE TRIGGER IF NOT EXISTS {table}_search_update
+             AFTER UPDATE OF content, description, deleted_at ON {table}
+             WHEN old.content IS NOT new.content
+               OR old.description IS NOT new.description
+               OR old.deleted_at IS NOT new.deleted_at
+             BEGIN
+                 DELETE FROM todo_search WHERE todo_id = old.id AND source = '{table}';
+                 INSERT INTO todo_search (content, description, todo_id, source)
+                 SELECT new.content, new.description, new.id, '{table}'
+                 WHERE new.deleted_at IS NULL;
+             END;
+
+             CREATE TRIGGER IF NOT EXISTS {table}_search_delete AFTER DELETE ON {table}
+             BEGIN
+                 DELETE FROM todo_search WHERE todo_id = old.id AND source = '{table}';
+             END;
+
+             INSERT INTO todo_search (content, description, todo_id, source)
+             SELECT content, description, id, '{table}' FROM {table} WHERE deleted_at IS NULL;"
+        ))?;
+    }
+
+    Ok(())
+}
+
+fn create_events(tx: &Transaction) -> rusqlite::Result<()> {
+    // Append-only log of every change; rows are never updated or deleted
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            occurred_at TEXT NOT NULL,
+            source TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            todo_id TEXT NOT NULL,
+            origin_id TEXT NOT NULL,
+            list_key TEXT NOT NULL,
+            content TEXT NOT NULL,
+            field TEXT,
+            before TEXT,
+            after TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_events_origin_id ON events(origin_id);
+        CREATE INDEX IF NOT EXISTS idx_events_occurred_at ON events(occurred_at);",
+    )
+}
+
+fn create_markdown_snapshots(tx: &Transaction) -> rusqlite::Result<()> {
+    // What was last written to each markdown file, with the id of every item in file order,
+    // so edits made in an editor can be told apart from our own writes and merged by id
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS markdown_snapshots (
+            list_key TEXT PRIMARY KEY,
+            content TEXT NOT NULL,
+            item_ids TEXT NOT NULL
+        );",
+    )
+}
+
+fn create_sections_and_notes(tx: &Transaction) -> rusqlite::Result<()> {
+    // The heading a todo is filed under, which it keeps across rollover and archiving
+    for table in ["todos", "archived_todos", "scheduled_todos", "list_todos"] {
+        add_column_if_missing(tx, table, "section", "TEXT")?;
+    }
+    // Free-form text of a day or project list, keyed like list_revisions
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS list_notes (
+            list_key TEXT PRIMARY KEY,
+            notes TEXT NOT NULL
+        );",
+    )
+}
+
+fn add_trailing_notes(tx: &Transaction) -> rusqlite::Result<()> {
+    // Text written below the todos, kept apart so it is written back there
+    add_column_if_missing(tx, "list_notes", "trailing_notes", "TEXT")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        conn.prepare("SELECT name FROM pragma_table_info(?1)")
+            .unwrap()
+            .query_map([table], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_versions_are_ordered() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, i + 1);
+        }
+    }
+
+    #[test]
+    fn test_migrate_fresh_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(pending_migrations(&conn).unwrap().len(), MIGRATIONS.len());
+
+        let applied = migrate(&mut conn).unwrap();
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+        assert!(columns(&conn, "list_todos").contains(&"list_name".to_string()));
+
+        // Already current: nothing to do
+        assert!(migrate(&mut conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_adopts_unversioned_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE todos (
+                id TEXT PRIMARY KEY,
+                date TEXT NOT NULL,
+                content TEXT NOT NULL,
+                state TEXT NOT NULL,
+                indent_level INTEGER NOT NULL,
+                parent_id TEXT,
+                due_date TEXT,
+                description TEXT,
+                position INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )
+        .unwrap();
+
+        migrate(&mut conn).unwrap();
+
+        let todo_columns = columns(&conn, "todos");
+        for column in ["collapsed", "completed_at", "origin_id", "carry_count", "start_date"] {
+            assert!(todo_columns.contains(&column.to_string()), "missing {column}");
+        }
+    }
+
+    #[test]
+    fn test_migrate_refuses_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+
+        let err = migrate(&mut conn).unwrap_err();
+        assert!(err.to_string().contains("newer than this build"));
+    }
+}
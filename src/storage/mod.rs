@@ -1,10 +1,24 @@
+pub mod backup;
+pub mod comments;
 pub mod database;
+pub mod events;
 pub mod file;
+pub mod import;
 pub mod markdown;
+// The totui binary only injects the SQLite store
+#[allow(dead_code)]
+pub mod memory;
+pub mod migrations;
 pub mod rollover;
+pub mod search;
+pub mod store;
+pub mod sync;
+pub mod time_tracking;
+pub mod trash;
 pub mod ui_cache;
 
-pub use database::{load_archived_todos_for_date, soft_delete_todos};
-pub use file::{load_todos_for_viewing, save_todo_list};
+pub use database::load_archived_todos_for_date;
+pub use file::save_todo_list;
 pub use rollover::{execute_rollover, find_rollover_candidates};
+pub use store::{SqliteStore, TodoStore};
 pub use ui_cache::UiCache;
@@ -1,5 +1,4 @@
-use super::database::archive_todos_for_date;
-use super::file::{file_exists, load_todo_list, save_todo_list};
+use super::store::TodoStore;
 use crate::todo::TodoList;
 use crate::utils::paths::get_daily_file_path;
 use anyhow::Result;
@@ -7,27 +6,31 @@ use chrono::{Local, NaiveDate};
 use std::collections::HashMap;
 use uuid::Uuid;
 
-/// Find incomplete items from the most recent previous day (up to 30 days back).
-/// Returns (source_date, incomplete_items) if found, None otherwise.
-pub fn find_rollover_candidates() -> Result<Option<(NaiveDate, Vec<crate::todo::TodoItem>)>> {
+/// Find incomplete items from the most recent previous day (up to 30 days back),
+/// plus fresh instances of that day's finished recurring items. Instances that are not due
+/// yet carry the start date they wait for.
+/// Returns (source_date, items) if found, None otherwise.
+pub fn find_rollover_candidates(
+    store: &dyn TodoStore,
+) -> Result<Option<(NaiveDate, Vec<crate::todo::TodoItem>)>> {
     let today = Local::now().date_naive();
 
     // Check if today's file already exists - no rollover needed
-    if file_exists(today)? {
+    if store.has_todos_for_date(today)? {
         return Ok(None);
     }
 
     // Look back up to 30 days for the most recent file with incomplete items
     for days_back in 1..=30 {
         if let Some(check_date) = today.checked_sub_days(chrono::Days::new(days_back))
-            && file_exists(check_date)? {
-                let list = load_todo_list(check_date)?;
-                let incomplete = list.get_incomplete_items();
+            && store.has_todos_for_date(check_date)? {
+                let list = store.load_todo_list(check_date)?;
+                let items = list.get_rollover_items(today);
 
-                if !incomplete.is_empty() {
-                    return Ok(Some((check_date, incomplete)));
+                if !items.is_empty() {
+                    return Ok(Some((check_date, items)));
                 }
-                // Found a file but no incomplete items, stop searching
+                // Found a file but nothing to carry over, stop searching
                 break;
             }
     }
@@ -35,15 +38,20 @@ pub fn find_rollover_candidates() -> Result<Option<(NaiveDate, Vec<crate::todo::
     Ok(None)
 }
 
-/// Execute the rollover: archive old todos and create new list with rolled-over items.
+/// Execute the rollover: archive old todos and create new list with rolled-over items
+/// and any scheduled items starting today. Recurring instances that are not due yet go to
+/// the scheduled area.
 pub fn execute_rollover(
+    store: &dyn TodoStore,
     source_date: NaiveDate,
     items: Vec<crate::todo::TodoItem>,
 ) -> Result<TodoList> {
     let today = Local::now().date_naive();
-    archive_todos_for_date(source_date)?;
-    let list = create_rolled_over_list(today, items)?;
-    save_todo_list(&list)?;
+    store.archive_todos_for_date(source_date)?;
+    let mut list = create_rolled_over_list(today, items)?;
+    store.schedule_future_items(&mut list)?;
+    store.merge_due_scheduled(&mut list)?;
+    store.save_todo_list(&mut list)?;
     Ok(list)
 }
 
@@ -58,6 +66,10 @@ pub fn create_rolled_over_list(
     for item in &mut items {
         let new_id = Uuid::new_v4();
         old_to_new_id.insert(item.id, new_id);
+        if item.recurrence.is_some() && item.series_id.is_none() {
+            item.series_id = Some(item.id);
+        }
+        item.origin_id = Some(item.origin());
         item.id = new_id;
     }
 
@@ -90,4 +102,33 @@ mod tests {
         assert_eq!(list.items[0].content, "Task 1");
         assert_eq!(list.items[1].content, "Task 2");
     }
+
+    #[test]
+    fn test_rolled_over_recurring_item_keeps_series() {
+        let today = Local::now().date_naive();
+        let mut item = TodoItem::with_state("Standup".to_string(), TodoState::Empty, 0);
+        item.recurrence = Some(crate::todo::Recurrence::Daily);
+        let original_id = item.id;
+
+        let list = create_rolled_over_list(today, vec![item]).unwrap();
+        assert_ne!(list.items[0].id, original_id);
+        assert_eq!(list.items[0].series_id, Some(original_id));
+
+        let again = create_rolled_over_list(today, list.items.clone()).unwrap();
+        assert_eq!(again.items[0].series_id, Some(original_id));
+    }
+
+    #[test]
+    fn test_rolled_over_item_keeps_origin() {
+        let today = Local::now().date_naive();
+        let item = TodoItem::with_state("Write migration".to_string(), TodoState::Empty, 0);
+        let original_id = item.id;
+
+        let list = create_rolled_over_list(today, vec![item]).unwrap();
+        let again = create_rolled_over_list(today, list.items.clone()).unwrap();
+
+        assert_ne!(again.items[0].id, list.items[0].id);
+        assert_eq!(list.items[0].origin_id, Some(original_id));
+        assert_eq!(again.items[0].origin_id, Some(original_id));
+    }
 }
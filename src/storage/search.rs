@@ -0,0 +1,168 @@
+use super::database::{get_connection, init_database};
+use crate::todo::TodoState;
+use anyhow::Result;
+use chrono::NaiveDate;
+use rusqlite::Connection;
+use uuid::Uuid;
+
+/// Default number of results returned by a search.
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// A todo matching a search, best match first.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub id: Uuid,
+    pub content: String,
+    pub state: TodoState,
+    /// Day the todo is (or was) on; `None` for project list items.
+    pub date: Option<NaiveDate>,
+    /// Project list holding the todo.
+    pub list: Option<String>,
+    pub archived: bool,
+    /// Matching fragment of the content or description, terms wrapped in `**`.
+    pub snippet: String,
+    /// BM25 score; lower is a better match.
+    pub rank: f64,
+}
+
+/// Turn free text into an FTS5 query: every word must appear, as a prefix, in the content or
+/// description. Quoting each word keeps punctuation such as `-` or `:` from being read as
+/// query syntax. Returns `None` when there is nothing to search for.
+pub fn to_match_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| word.trim_start_matches('#'))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+pub(crate) fn query_search(conn: &Connection, text: &str, limit: usize) -> Result<Vec<SearchHit>> {
+    let Some(query) = to_match_query(text) else {
+        return Ok(Vec::new());
+    };
+
+    let mut stmt = conn.prepare_cached(
+        "SELECT t.id, t.content, t.state, t.day, t.list_name, t.source,
+                snippet(todo_search, -1, '**', '**', '…', 12),
+                bm25(todo_search) AS rank
+         FROM todo_search
+         JOIN (SELECT id, content, state, date AS day, NULL AS list_name, 'todos' AS source FROM todos
+               UNION ALL
+               SELECT id, content, state, original_date, NULL, 'archived_todos' FROM archived_todos
+               UNION ALL
+               SELECT id, content, state, NULL, list_name, 'list_todos' FROM list_todos) t
+           ON t.id = todo_search.todo_id AND t.source = todo_search.source
+         WHERE todo_search MATCH ?1
+         ORDER BY rank, t.day DESC
+         LIMIT ?2",
+    )?;
+
+    let rows = stmt.query_map(rusqlite::params![query, limit as i64], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, String>(5)?,
+            row.get::<_, String>(6)?,
+            row.get::<_, f64>(7)?,
+        ))
+    })?;
+
+    let mut hits = Vec::new();
+    for row in rows {
+        let (id, content, state, day, list, source, snippet, rank) = row?;
+        let Ok(id) = Uuid::parse_str(&id) else {
+            continue;
+        };
+        let state_char = state.chars().next().unwrap_or(' ');
+        hits.push(SearchHit {
+            id,
+            content,
+            state: TodoState::from_char(state_char).unwrap_or(TodoState::Custom(state_char)),
+            date: day.and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()),
+            list,
+            archived: source == "archived_todos",
+            snippet,
+            rank,
+        });
+    }
+
+    Ok(hits)
+}
+
+/// Search the content and description of live, archived and project list todos.
+pub fn search_todos(text: &str, limit: usize) -> Result<Vec<SearchHit>> {
+    init_database()?;
+    let conn = get_connection()?;
+    query_search(&conn, text, limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::migrations::migrate;
+
+    fn insert(conn: &Connection, table: &str, key: &str, content: &str, description: Option<&str>) -> String {
+        let id = Uuid::new_v4().to_string();
+        let key_column = match table {
+            "archived_todos" => "original_date, archived_at",
+            "list_todos" => "list_name",
+            _ => "date",
+        };
+        let key_values = match table {
+            "archived_todos" => format!("'{key}', '2026-01-06T00:00:00Z'"),
+            _ => format!("'{key}'"),
+        };
+        conn.execute(
+            &format!(
+                "INSERT INTO {table} (id, {key_column}, content, state, indent_level, position, created_at, updated_at, description)
+                 VALUES (?1, {key_values}, ?2, ' ', 0, 0, '', '', ?3)"
+            ),
+            rusqlite::params![id, content, description],
+        )
+        .unwrap();
+        id
+    }
+
+    #[test]
+    fn test_to_match_query() {
+        assert_eq!(to_match_query("  "), None);
+        assert_eq!(
+            to_match_query("budget q3-report #work"),
+            Some("\"budget\"* \"q3-report\"* \"work\"*".to_string())
+        );
+        assert_eq!(to_match_query("say \"hi\""), Some("\"say\"* \"\"\"hi\"\"\"*".to_string()));
+    }
+
+    #[test]
+    fn test_search_covers_live_archived_and_list_todos() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+
+        insert(&conn, "todos", "2026-01-07", "Send budget to finance", None);
+        let archived = insert(&conn, "archived_todos", "2026-01-05", "Draft the plan", Some("Budget numbers from Q3"));
+        insert(&conn, "list_todos", "backlog", "Budgeting tool idea", None);
+        insert(&conn, "todos", "2026-01-07", "Unrelated", None);
+
+        let hits = query_search(&conn, "budget", 10).unwrap();
+        assert_eq!(hits.len(), 3);
+
+        let from_archive = hits.iter().find(|hit| hit.id.to_string() == archived).unwrap();
+        assert!(from_archive.archived);
+        assert_eq!(from_archive.date, NaiveDate::from_ymd_opt(2026, 1, 5));
+        assert!(from_archive.snippet.contains("**Budget**"));
+        assert!(hits.iter().any(|hit| hit.list.as_deref() == Some("backlog")));
+
+        // Edits and deletes keep the index in sync
+        conn.execute("UPDATE archived_todos SET description = NULL WHERE id = ?1", [&archived])
+            .unwrap();
+        conn.execute("UPDATE todos SET deleted_at = 'now' WHERE content LIKE 'Send%'", [])
+            .unwrap();
+        conn.execute("DELETE FROM list_todos", []).unwrap();
+        assert!(query_search(&conn, "budget", 10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,313 @@
+use super::comments::{self, Comment};
+use super::events::{self, Event, EventFilter};
+use super::search::{self, SearchHit};
+use super::sync::{self, SyncOutcome};
+use super::time_tracking::{self, TrackedTime};
+use super::trash::{self, TrashedTodo};
+use super::{database, file};
+use crate::todo::{TodoItem, TodoList};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use uuid::Uuid;
+
+/// Where the TUI, the API and the MCP server load and save lists.
+///
+/// `SqliteStore` is the real thing; `memory::InMemoryStore` keeps everything in memory for
+/// tests and for embedding the crate without touching `todos.db`.
+pub trait TodoStore: Send + Sync {
+    /// The daily list for `date`, including scheduled items that are actionable on it.
+    fn load_todo_list(&self, date: NaiveDate) -> Result<TodoList>;
+
+    /// The project list `name`. One that does not exist yet is returned empty.
+    fn load_named_list(&self, name: &str) -> Result<TodoList>;
+
+    /// A day as the TUI shows it when browsing: today's live list, the archive of a past
+    /// day, or whatever is still stored for it.
+    fn load_todos_for_viewing(&self, date: NaiveDate) -> Result<TodoList>;
+
+    /// Names of all project lists, alphabetically.
+    fn list_names(&self) -> Result<Vec<String>>;
+
+    /// Create an empty project list so it can be picked before anything is added to it.
+    fn create_named_list(&self, name: &str) -> Result<()>;
+
+    /// Save `list`. A save that raced with another writer is rebased onto it, leaving the
+    /// combined items in `list`; if both sides edited the same items it fails with
+    /// `database::SaveConflict`.
+    fn save_todo_list(&self, list: &mut TodoList) -> Result<()>;
+
+    /// Whether the day `date` was started, so a rollover into it is no longer offered.
+    fn has_todos_for_date(&self, date: NaiveDate) -> Result<bool>;
+
+    /// Move every todo of `date` into the archive. Returns how many were moved.
+    fn archive_todos_for_date(&self, date: NaiveDate) -> Result<usize>;
+
+    /// Mark the todos `ids` of `date` deleted. The caller still removes them from its list
+    /// and saves it. Saving a list without an item deletes it the same way.
+    fn soft_delete_todos(&self, ids: &[Uuid], date: NaiveDate) -> Result<()>;
+
+    /// Every deleted todo that has not been purged, most recently deleted first.
+    fn load_trash(&self) -> Result<Vec<TrashedTodo>>;
+
+    /// Remove the deleted todos `ids` for good. Returns how many were removed.
+    fn purge_trash(&self, ids: &[Uuid]) -> Result<usize>;
+
+    /// Move items that start after `list.date` out of `list` and into the scheduled area.
+    /// The caller still saves `list`. Returns the moved items.
+    fn schedule_future_items(&self, list: &mut TodoList) -> Result<Vec<TodoItem>>;
+
+    /// Add scheduled items that are actionable on `list.date`.
+    fn merge_due_scheduled(&self, list: &mut TodoList) -> Result<()>;
+
+    /// Scheduled items starting between `from` and `to` (inclusive, either bound optional),
+    /// ordered by start date. Subtasks follow their parent.
+    fn load_scheduled_todos(&self, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<Vec<TodoItem>>;
+
+    /// Take in edits made to the markdown file of `date` since it was last written.
+    fn sync_daily_file(&self, date: NaiveDate) -> Result<SyncOutcome>;
+
+    /// Of the given blocker lineage ids, those whose latest occurrence is not finished.
+    /// Unknown ids are treated as resolved so a deleted blocker never blocks.
+    fn load_open_blockers(&self, blocker_ids: &[Uuid]) -> Result<HashSet<Uuid>>;
+
+    /// Lineage id of the todo `id`, wherever it is stored. `None` if it is unknown.
+    fn resolve_origin_id(&self, id: Uuid) -> Result<Option<Uuid>>;
+
+    /// Every occurrence of the task that `id` belongs to, oldest first. Empty if `id` is
+    /// unknown.
+    fn load_todo_history(&self, id: Uuid) -> Result<Vec<(NaiveDate, TodoItem)>>;
+
+    /// Up to `limit` live, archived and project list todos whose content or description
+    /// holds every word of `text`, best match first.
+    fn search_todos(&self, text: &str, limit: usize) -> Result<Vec<SearchHit>>;
+
+    /// The comment thread of a todo, oldest first. `None` if no todo with this id is stored.
+    fn load_comments(&self, todo_id: Uuid) -> Result<Option<Vec<Comment>>>;
+
+    /// Append a comment to the thread of any occurrence of a todo. `None` if no todo with
+    /// this id is stored.
+    fn add_comment(&self, todo_id: Uuid, author: &str, body: &str) -> Result<Option<Comment>>;
+
+    /// Tracked time for each of the given lineage ids that has any.
+    fn load_tracked_time(&self, origin_ids: &[Uuid]) -> Result<HashMap<Uuid, TrackedTime>>;
+
+    /// Recorded changes matching `filter`, oldest first.
+    fn load_events(&self, filter: &EventFilter) -> Result<Vec<Event>>;
+
+    /// Indices of items in `list` that are waiting on an unfinished blocker.
+    fn find_blocked_indices(&self, list: &TodoList) -> Result<HashSet<usize>> {
+        let open_external = self.load_open_blockers(&list.external_blocker_ids())?;
+        Ok((0..list.items.len())
+            .filter(|&i| list.is_blocked(i, &open_external))
+            .collect())
+    }
+
+    /// Map blocker ids supplied by a client to lineage ids, so the link survives rollover.
+    /// Ids may refer to an item in `list` or to any stored todo, on any day.
+    fn resolve_blocker_ids(&self, list: &TodoList, todo_id: Uuid, blocker_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+        let own_origin = list
+            .items
+            .iter()
+            .find(|item| item.id == todo_id)
+            .map(|item| item.origin());
+        let mut resolved = Vec::new();
+
+        for &blocker_id in blocker_ids {
+            let origin = match list.items.iter().find(|item| item.id == blocker_id) {
+                Some(item) => item.origin(),
+                None => self
+                    .resolve_origin_id(blocker_id)?
+                    .ok_or_else(|| anyhow!("Blocker todo '{blocker_id}' not found"))?,
+            };
+            if blocker_id == todo_id || Some(origin) == own_origin {
+                return Err(anyhow!("A todo cannot block itself"));
+            }
+            if !resolved.contains(&origin) {
+                resolved.push(origin);
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// Today's list when `name` is `None`, otherwise the project list `name`.
+    fn load_list(&self, name: Option<&str>) -> Result<TodoList> {
+        match name {
+            Some(name) => self.load_named_list(name),
+            None => self.load_todo_list(chrono::Local::now().date_naive()),
+        }
+    }
+
+    /// Put the deleted todo `id` and the subtasks deleted with it back where they were, and
+    /// save that list. Todos deleted from a day before today go to today's list. Returns the
+    /// list and where the items are in it, or `None` if `id` is not in the trash.
+    fn restore_from_trash(&self, id: Uuid) -> Result<Option<(TodoList, Range<usize>)>> {
+        let trash = self.load_trash()?;
+        let entries: Vec<TrashedTodo> = trash::subtree(&trash, id).into_iter().cloned().collect();
+        let Some(root) = entries.first() else {
+            return Ok(None);
+        };
+
+        let today = Local::now().date_naive();
+        let mut list = match (&root.list, root.date) {
+            (Some(name), _) => self.load_named_list(name)?,
+            (None, Some(date)) if date >= today => self.load_todo_list(date)?,
+            (None, _) => self.load_todo_list(today)?,
+        };
+        let restored = trash::reinsert(&mut list, entries);
+        self.save_todo_list(&mut list)?;
+        Ok(Some((list, restored)))
+    }
+
+    /// Purge every todo deleted before `cutoff`. Returns how many were removed.
+    fn purge_trash_before(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        let expired: Vec<Uuid> = self
+            .load_trash()?
+            .iter()
+            .filter(|entry| entry.deleted_at() < cutoff)
+            .map(|entry| entry.item.id)
+            .collect();
+        self.purge_trash(&expired)
+    }
+
+    /// Purge the todos that have outlived the configured retention window.
+    fn purge_expired_trash(&self) -> Result<usize> {
+        match trash::retention_cutoff() {
+            Some(cutoff) => self.purge_trash_before(cutoff),
+            None => Ok(0),
+        }
+    }
+
+    /// Move the item at `index` with its subtasks from `source` to the end of `target`, then
+    /// save both. The target is saved first, while the items are still stored under the
+    /// source, so the change history records a move rather than a delete and a create.
+    fn move_to_list(
+        &self,
+        source: &mut TodoList,
+        index: usize,
+        target: &mut TodoList,
+    ) -> Result<usize> {
+        let moved = source.take_subtree(index);
+        let count = moved.len();
+        if count == 0 {
+            return Ok(0);
+        }
+
+        target.items.extend(moved);
+        target.recalculate_parent_ids();
+
+        self.save_todo_list(target)?;
+        self.save_todo_list(source)?;
+        Ok(count)
+    }
+}
+
+/// The `todos.db` database in the data directory, with each list mirrored to its markdown
+/// file.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SqliteStore;
+
+impl TodoStore for SqliteStore {
+    fn load_todo_list(&self, date: NaiveDate) -> Result<TodoList> {
+        file::load_todo_list(date)
+    }
+
+    fn load_named_list(&self, name: &str) -> Result<TodoList> {
+        file::load_named_list(name)
+    }
+
+    fn load_todos_for_viewing(&self, date: NaiveDate) -> Result<TodoList> {
+        file::load_todos_for_viewing(date)
+    }
+
+    fn list_names(&self) -> Result<Vec<String>> {
+        file::list_names()
+    }
+
+    fn create_named_list(&self, name: &str) -> Result<()> {
+        file::create_named_list(name)
+    }
+
+    fn save_todo_list(&self, list: &mut TodoList) -> Result<()> {
+        file::save_todo_list(list)
+    }
+
+    /// A day counts as started once it has stored todos or a markdown file, which is
+    /// imported when the day is loaded.
+    fn has_todos_for_date(&self, date: NaiveDate) -> Result<bool> {
+        file::file_exists(date)
+    }
+
+    fn archive_todos_for_date(&self, date: NaiveDate) -> Result<usize> {
+        database::init_database()?;
+        database::archive_todos_for_date(date)
+    }
+
+    fn soft_delete_todos(&self, ids: &[Uuid], date: NaiveDate) -> Result<()> {
+        database::soft_delete_todos(ids, date)
+    }
+
+    fn load_trash(&self) -> Result<Vec<TrashedTodo>> {
+        database::init_database()?;
+        database::load_trash()
+    }
+
+    fn purge_trash(&self, ids: &[Uuid]) -> Result<usize> {
+        database::init_database()?;
+        database::purge_trash(ids)
+    }
+
+    fn schedule_future_items(&self, list: &mut TodoList) -> Result<Vec<TodoItem>> {
+        file::schedule_future_items(list)
+    }
+
+    fn merge_due_scheduled(&self, list: &mut TodoList) -> Result<()> {
+        file::merge_due_scheduled(list)
+    }
+
+    fn load_scheduled_todos(&self, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<Vec<TodoItem>> {
+        database::init_database()?;
+        database::load_scheduled_todos(from, to)
+    }
+
+    fn sync_daily_file(&self, date: NaiveDate) -> Result<SyncOutcome> {
+        sync::sync_daily_file(date)
+    }
+
+    fn load_open_blockers(&self, blocker_ids: &[Uuid]) -> Result<HashSet<Uuid>> {
+        database::load_open_blockers(blocker_ids)
+    }
+
+    fn resolve_origin_id(&self, id: Uuid) -> Result<Option<Uuid>> {
+        database::load_origin_id(id)
+    }
+
+    fn load_todo_history(&self, id: Uuid) -> Result<Vec<(NaiveDate, TodoItem)>> {
+        database::init_database()?;
+        database::load_todo_history(id)
+    }
+
+    fn search_todos(&self, text: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        search::search_todos(text, limit)
+    }
+
+    fn load_comments(&self, todo_id: Uuid) -> Result<Option<Vec<Comment>>> {
+        database::init_database()?;
+        comments::load_comments(todo_id)
+    }
+
+    fn add_comment(&self, todo_id: Uuid, author: &str, body: &str) -> Result<Option<Comment>> {
+        database::init_database()?;
+        comments::add_comment(todo_id, author, body)
+    }
+
+    fn load_tracked_time(&self, origin_ids: &[Uuid]) -> Result<HashMap<Uuid, TrackedTime>> {
+        time_tracking::load_tracked_time(origin_ids)
+    }
+
+    fn load_events(&self, filter: &EventFilter) -> Result<Vec<Event>> {
+        events::load_events(filter)
+    }
+}
@@ -0,0 +1,331 @@
+use super::database::{self, SaveConflict};
+use super::events::{EventSource, event_source, set_event_source};
+use super::file::{SAVE_ATTEMPTS, replace_markdown};
+use super::markdown::{parse_todo_list, serialize_item, serialize_todo_list};
+use crate::todo::rebase::rebase;
+use crate::todo::{TodoItem, TodoList};
+use crate::utils::paths::get_daily_file_path;
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use std::collections::HashMap;
+use std::fs;
+use uuid::Uuid;
+
+/// What `sync_daily_file` did with a daily markdown file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncOutcome {
+    /// The file is as it was last written, or is not kept in sync.
+    Unchanged,
+    /// Edits made to the file were merged into the database.
+    Merged,
+    /// The file and the database changed the same items. Both are left as they are until
+    /// the file is edited to agree with the database, or deleted.
+    Conflict,
+}
+
+/// What to tell the user when the markdown file of `date` conflicts with the database.
+pub fn conflict_message(date: NaiveDate) -> String {
+    format!(
+        "dailies/{}.md and the database changed the same todos; fix the file or delete it to keep the stored list",
+        date.format("%Y-%m-%d")
+    )
+}
+
+/// Merge edits made to the markdown file of `date` in an editor into the database.
+///
+/// The file is compared with what was last written to it: items are matched by `@id` where
+/// the file has one, otherwise by their text and position, and the edits are replayed onto
+/// the stored list like a concurrent save (see `rebase`). The file is only rewritten when
+/// the database had changes of its own.
+pub fn sync_daily_file(date: NaiveDate) -> Result<SyncOutcome> {
+    database::init_database()?;
+    let file_path = get_daily_file_path(date)?;
+    let Ok(on_disk) = fs::read_to_string(&file_path) else {
+        return Ok(SyncOutcome::Unchanged);
+    };
+
+    for _ in 0..SAVE_ATTEMPTS {
+        let mut list = TodoList::new(date, file_path.clone());
+        // Files that were never written by us, or of archived days, are not synced
+        let Some(snapshot) = database::load_markdown_snapshot(&list)? else {
+            return Ok(SyncOutcome::Unchanged);
+        };
+        if snapshot.content == on_disk {
+            return Ok(SyncOutcome::Unchanged);
+        }
+
+        let revision = database::load_revision(&list)?;
+        let theirs = database::load_todos_for_date(date)?;
+        let (their_notes, their_trailing_notes) = database::load_notes(&list)?;
+
+        let written_list = parse_todo_list(&snapshot.content, date, file_path.clone());
+        let mut written = written_list.items;
+        if written.len() != snapshot.item_ids.len() {
+            return Ok(SyncOutcome::Conflict);
+        }
+        for (item, id) in written.iter_mut().zip(&snapshot.item_ids) {
+            item.id = *id;
+        }
+        let edited_list = parse_todo_list(&on_disk, date, file_path.clone());
+        let edited = edited_list.items;
+
+        let notes = merge_notes(&written_list.notes, edited_list.notes, &their_notes);
+        let trailing_notes = merge_notes(
+            &written_list.trailing_notes,
+            edited_list.trailing_notes,
+            &their_trailing_notes,
+        );
+        let (Some(notes), Some(trailing_notes)) = (notes, trailing_notes) else {
+            return Ok(SyncOutcome::Conflict);
+        };
+        list.notes = notes;
+        list.trailing_notes = trailing_notes;
+
+        let (base, mine) = reconcile(&written, &edited, &theirs);
+        let Some(merged) = rebase(&base, &mine, &theirs) else {
+            return Ok(SyncOutcome::Conflict);
+        };
+
+        list.items = merged;
+        list.recalculate_parent_ids();
+        list.revision = revision;
+        if list.items != theirs || list.notes != their_notes || list.trailing_notes != their_trailing_notes {
+            let source = event_source();
+            set_event_source(EventSource::File);
+            let saved = database::save_todo_list(&list);
+            set_event_source(source);
+            match saved {
+                Ok(revision) => list.mark_stored(revision),
+                Err(e) if e.is::<SaveConflict>() => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Keep the file as typed when it already says everything the merge does, metadata
+        // included when the files are lossless
+        let mut as_edited = list.clone();
+        as_edited.items = edited;
+        if serialize_todo_list(&as_edited) == serialize_todo_list(&list) {
+            as_edited.items = mine;
+            database::save_markdown_snapshot(&as_edited, &on_disk)?;
+        } else {
+            replace_markdown(&list, &serialize_todo_list(&list))?;
+        }
+        return Ok(SyncOutcome::Merged);
+    }
+
+    Ok(SyncOutcome::Conflict)
+}
+
+/// Notes as they should be stored after the file went from `written` to `edited` while the
+/// stored notes became `theirs`. `None` when both sides changed them differently.
+fn merge_notes(written: &Option<String>, edited: Option<String>, theirs: &Option<String>) -> Option<Option<String>> {
+    if edited == *written {
+        Some(theirs.clone())
+    } else if theirs == written || *theirs == edited {
+        Some(edited)
+    } else {
+        None
+    }
+}
+
+/// Turn the items last `written` to the file and the items parsed from the `edited` file
+/// into full items with stable ids, taking what markdown does not show (timestamps,
+/// collapsed state, ...) from the `stored` items.
+fn reconcile(written: &[TodoItem], edited: &[TodoItem], stored: &[TodoItem]) -> (Vec<TodoItem>, Vec<TodoItem>) {
+    let stored_by_id: HashMap<Uuid, &TodoItem> = stored.iter().map(|item| (item.id, item)).collect();
+
+    let base: Vec<TodoItem> = written
+        .iter()
+        .map(|item| match stored_by_id.get(&item.id) {
+            Some(stored) if reads_alike(stored, item) => (*stored).clone(),
+            Some(stored) => with_markdown_fields(stored, item),
+            None => item.clone(),
+        })
+        .collect();
+
+    // An edit that makes an item read like the stored one is the same edit on both sides
+    let pairs = match_items(written, edited);
+    let mine: Vec<TodoItem> = edited
+        .iter()
+        .zip(pairs)
+        .map(|(item, pair)| match pair.map(|i| (i, stored_by_id.get(&written[i].id))) {
+            Some((_, Some(stored))) if reads_alike(stored, item) => (*stored).clone(),
+            Some((i, _)) if reads_alike(&written[i], item) => base[i].clone(),
+            Some((i, _)) => with_markdown_fields(&base[i], item),
+            None => item.clone(),
+        })
+        .collect();
+
+    (with_parents(base), with_parents(mine))
+}
+
+/// Whether the file shows `a` and `b` the same way.
+fn reads_alike(a: &TodoItem, b: &TodoItem) -> bool {
+    a.section == b.section && serialize_item(a) == serialize_item(b)
+}
+
+/// `items` with parents resolved again, as the parser linked them by ids it made up.
+fn with_parents(items: Vec<TodoItem>) -> Vec<TodoItem> {
+    let mut list = TodoList::with_items(NaiveDate::MIN, Default::default(), items);
+    list.recalculate_parent_ids();
+    list.items
+}
+
+/// `item` with everything the markdown shows taken from `parsed`.
+fn with_markdown_fields(item: &TodoItem, parsed: &TodoItem) -> TodoItem {
+    let mut merged = item.clone();
+    if merged.state != parsed.state {
+        merged.set_state(parsed.state);
+    }
+    merged.content = parsed.content.clone();
+    merged.tags = parsed.tags.clone();
+    merged.indent_level = parsed.indent_level;
+    merged.due_date = parsed.due_date;
+    merged.start_date = parsed.start_date;
+    merged.description = parsed.description.clone();
+    merged.blocked_by = parsed.blocked_by.clone();
+    if parsed.recurrence.is_none() {
+        merged.series_id = None;
+    } else if merged.series_id.is_none() {
+        merged.series_id = Some(merged.id);
+    }
+    merged.recurrence = parsed.recurrence.clone();
+    merged.section = parsed.section.clone();
+    merged.modified_at = Utc::now();
+    merged
+}
+
+/// For each `edited` item, the index of the `written` item it is, if any.
+///
+/// An `@id` in the file decides. The remaining items are paired when their markdown is
+/// identical, keeping their order, and items left over between two pairs are taken to be
+/// edited in place, first to first.
+fn match_items(written: &[TodoItem], edited: &[TodoItem]) -> Vec<Option<usize>> {
+    let mut pairs: Vec<Option<usize>> = vec![None; edited.len()];
+    let written_index: HashMap<Uuid, usize> =
+        written.iter().enumerate().map(|(i, item)| (item.id, i)).collect();
+    let mut claimed = vec![false; written.len()];
+    for (j, item) in edited.iter().enumerate() {
+        if let Some(&i) = written_index.get(&item.id) {
+            pairs[j] = Some(i);
+            claimed[i] = true;
+        }
+    }
+
+    let left: Vec<usize> = (0..written.len()).filter(|&i| !claimed[i]).collect();
+    let right: Vec<usize> = (0..edited.len()).filter(|&j| pairs[j].is_none()).collect();
+    let left_text: Vec<String> = left.iter().map(|&i| serialize_item(&written[i])).collect();
+    let right_text: Vec<String> = right.iter().map(|&j| serialize_item(&edited[j])).collect();
+
+    // Longest common subsequence of the unmatched items' markdown
+    let (n, m) = (left.len(), right.len());
+    let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+    for a in (0..n).rev() {
+        for b in (0..m).rev() {
+            lengths[a][b] = if left_text[a] == right_text[b] {
+                lengths[a + 1][b + 1] + 1
+            } else {
+                lengths[a + 1][b].max(lengths[a][b + 1])
+            };
+        }
+    }
+    let mut anchors = Vec::new();
+    let (mut a, mut b) = (0, 0);
+    while a < n && b < m {
+        if left_text[a] == right_text[b] {
+            anchors.push((a, b));
+            a += 1;
+            b += 1;
+        } else if lengths[a + 1][b] >= lengths[a][b + 1] {
+            a += 1;
+        } else {
+            b += 1;
+        }
+    }
+    let mut left_paired = vec![false; n];
+    let mut right_paired = vec![false; m];
+    for &(a, b) in &anchors {
+        pairs[right[b]] = Some(left[a]);
+        left_paired[a] = true;
+        right_paired[b] = true;
+    }
+
+    // Unchanged items that moved past others
+    for b in 0..m {
+        if right_paired[b] {
+            continue;
+        }
+        if let Some(a) = (0..n).find(|&a| !left_paired[a] && left_text[a] == right_text[b]) {
+            pairs[right[b]] = Some(left[a]);
+            left_paired[a] = true;
+            right_paired[b] = true;
+        }
+    }
+
+    // Between two anchors, what is left on both sides was edited in place
+    let mut bounds = vec![(0, 0)];
+    bounds.extend(anchors.iter().map(|&(a, b)| (a + 1, b + 1)));
+    let mut ends: Vec<(usize, usize)> = anchors.clone();
+    ends.push((n, m));
+    for (&(start_a, start_b), &(end_a, end_b)) in bounds.iter().zip(&ends) {
+        let gap_a = (start_a..end_a).filter(|&a| !left_paired[a]);
+        let gap_b = (start_b..end_b).filter(|&b| !right_paired[b]);
+        for (a, b) in gap_a.zip(gap_b) {
+            pairs[right[b]] = Some(left[a]);
+        }
+    }
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::TodoState;
+
+    fn items(contents: &[&str]) -> Vec<TodoItem> {
+        contents
+            .iter()
+            .map(|c| TodoItem::new(c.to_string(), 0))
+            .collect()
+    }
+
+    #[test]
+    fn test_match_items_pairs_unchanged_and_edited_items() {
+        let written = items(&["A", "B", "C", "D"]);
+        let mut edited = items(&["A", "B edited", "New", "D"]);
+        // C was removed; an id written into the file always wins
+        edited.push(TodoItem::new("Renamed".to_string(), 0));
+        edited[4].id = written[2].id;
+
+        let pairs = match_items(&written, &edited);
+        assert_eq!(pairs, vec![Some(0), Some(1), None, Some(3), Some(2)]);
+
+        // Moving an item keeps its identity
+        let moved = items(&["D", "A", "B", "C"]);
+        assert_eq!(match_items(&written, &moved), vec![Some(3), Some(0), Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn test_reconcile_keeps_stored_fields_and_ids() {
+        let mut stored = items(&["A", "B"]);
+        stored[0].collapsed = true;
+        let written = stored.clone();
+
+        let mut edited = items(&["A", "B"]);
+        edited[1].state = TodoState::Checked;
+        edited.push(TodoItem::new("C".to_string(), 1));
+
+        let (base, mine) = reconcile(&written, &edited, &stored);
+        assert_eq!(base, stored);
+        assert_eq!(mine[0], stored[0]);
+        assert_eq!(mine[1].id, stored[1].id);
+        assert!(mine[1].completed_at.is_some());
+        assert_eq!(mine[2].parent_id, Some(stored[1].id));
+
+        let merged = rebase(&base, &mine, &stored).unwrap();
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[1].state, TodoState::Checked);
+    }
+}
@@ -0,0 +1,292 @@
+use super::database::get_connection;
+use crate::todo::{TodoList, TodoState};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use rusqlite::{Connection, params};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Time logged against a task across all of its daily occurrences.
+#[derive(Debug, Clone, Default)]
+pub struct TrackedTime {
+    /// Total length of finished intervals.
+    pub closed: Duration,
+    /// Start of the interval still running, if the task is in progress.
+    pub running_since: Option<DateTime<Utc>>,
+}
+
+impl TrackedTime {
+    pub fn total(&self, now: DateTime<Utc>) -> Duration {
+        self.closed + self.running_since.map(|start| now - start).unwrap_or_default()
+    }
+}
+
+/// One `[*]` interval. Entries are keyed by the task's lineage id, so they survive
+/// rollover and archiving.
+#[derive(Debug, Clone)]
+pub struct TimeEntry {
+    pub origin_id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub stopped_at: Option<DateTime<Utc>>,
+}
+
+impl TimeEntry {
+    /// Split the entry at local midnights, clipped to the days `from..=to`.
+    /// A running entry counts up to `now`.
+    pub fn split_by_day(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        now: DateTime<Utc>,
+    ) -> Vec<(NaiveDate, Duration)> {
+        let end = self.stopped_at.unwrap_or(now);
+        let mut result = Vec::new();
+        let mut cursor = self.started_at;
+
+        while cursor < end {
+            let day = cursor.with_timezone(&Local).date_naive();
+            let next_midnight = day
+                .succ_opt()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .and_then(|dt| Local.from_local_datetime(&dt).earliest())
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or(end);
+            let slice_end = next_midnight.min(end);
+            if day >= from && day <= to {
+                result.push((day, slice_end - cursor));
+            }
+            if day > to {
+                break;
+            }
+            cursor = slice_end;
+        }
+
+        result
+    }
+}
+
+/// Format a duration as "2h 05m" or "12m".
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Open or close intervals so they match the in-progress items of a freshly saved list.
+/// `day_origins` holds the lineage ids stored for the list's day before the save, so tasks
+/// that were removed from the day also have their interval closed.
+pub(crate) fn sync_time_entries(
+    conn: &Connection,
+    day_origins: &HashSet<Uuid>,
+    list: &TodoList,
+) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+    let running: HashSet<Uuid> = list
+        .items
+        .iter()
+        .filter(|item| item.state == TodoState::InProgress)
+        .map(|item| item.origin())
+        .collect();
+
+    let mut stop = conn.prepare_cached(
+        "UPDATE time_entries SET stopped_at = ?1 WHERE origin_id = ?2 AND stopped_at IS NULL",
+    )?;
+    let stopped = day_origins
+        .iter()
+        .copied()
+        .chain(list.items.iter().map(|item| item.origin()))
+        .filter(|origin| !running.contains(origin))
+        .collect::<HashSet<_>>();
+    for origin in stopped {
+        stop.execute(params![now, origin.to_string()])?;
+    }
+
+    let mut start = conn.prepare_cached(
+        "INSERT INTO time_entries (origin_id, started_at)
+         SELECT ?1, ?2
+         WHERE NOT EXISTS (SELECT 1 FROM time_entries WHERE origin_id = ?1 AND stopped_at IS NULL)",
+    )?;
+    for origin in running {
+        start.execute(params![origin.to_string(), now])?;
+    }
+
+    Ok(())
+}
+
+/// Tracked time for each of the given lineage ids that has any entries.
+pub fn load_tracked_time(origin_ids: &[Uuid]) -> Result<HashMap<Uuid, TrackedTime>> {
+    let mut result = HashMap::new();
+    if origin_ids.is_empty() {
+        return Ok(result);
+    }
+
+    let conn = get_connection()?;
+    let mut stmt =
+        conn.prepare_cached("SELECT started_at, stopped_at FROM time_entries WHERE origin_id = ?1")?;
+
+    for origin in origin_ids {
+        let rows = stmt.query_map([origin.to_string()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+        })?;
+
+        let mut tracked = TrackedTime::default();
+        let mut found = false;
+        for row in rows {
+            let (started, stopped) = row?;
+            let Some(started) = parse_timestamp(&started) else {
+                continue;
+            };
+            found = true;
+            match stopped.as_deref().and_then(parse_timestamp) {
+                Some(stopped) => tracked.closed += stopped - started,
+                None => tracked.running_since = Some(started),
+            }
+        }
+        if found {
+            result.insert(*origin, tracked);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Entries overlapping the local days `from..=to`, oldest first.
+pub fn load_time_entries(from: NaiveDate, to: NaiveDate) -> Result<Vec<TimeEntry>> {
+    let to_local = |date: NaiveDate| {
+        date.and_hms_opt(0, 0, 0)
+            .and_then(|dt| Local.from_local_datetime(&dt).earliest())
+            .map(|dt| dt.with_timezone(&Utc).to_rfc3339())
+            .unwrap_or_default()
+    };
+    let range_start = to_local(from);
+    let range_end = to_local(to.succ_opt().unwrap_or(to));
+
+    let conn = get_connection()?;
+    let mut stmt = conn.prepare_cached(
+        "SELECT origin_id, started_at, stopped_at FROM time_entries
+         WHERE started_at < ?2 AND (stopped_at IS NULL OR stopped_at > ?1)
+         ORDER BY started_at ASC",
+    )?;
+
+    let rows = stmt.query_map(params![range_start, range_end], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+        ))
+    })?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let (origin, started, stopped) = row?;
+        if let (Ok(origin_id), Some(started_at)) = (Uuid::parse_str(&origin), parse_timestamp(&started)) {
+            entries.push(TimeEntry {
+                origin_id,
+                started_at,
+                stopped_at: stopped.as_deref().and_then(parse_timestamp),
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::TodoItem;
+    use std::path::PathBuf;
+
+    fn setup_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE time_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                origin_id TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                stopped_at TEXT
+            )",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn entry_counts(conn: &Connection) -> (i64, i64) {
+        conn.query_row(
+            "SELECT COUNT(*), COUNT(*) - COUNT(stopped_at) FROM time_entries",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sync_time_entries_opens_and_closes_intervals() {
+        let conn = setup_conn();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+        let mut list = TodoList::new(date, PathBuf::from("/tmp/test.md"));
+        list.items.push(TodoItem::new("Write report".to_string(), 0));
+        let origin = list.items[0].origin();
+
+        list.items[0].state = TodoState::InProgress;
+        sync_time_entries(&conn, &HashSet::new(), &list).unwrap();
+        sync_time_entries(&conn, &HashSet::from([origin]), &list).unwrap();
+        assert_eq!(entry_counts(&conn), (1, 1));
+
+        list.items[0].state = TodoState::Checked;
+        sync_time_entries(&conn, &HashSet::from([origin]), &list).unwrap();
+        assert_eq!(entry_counts(&conn), (1, 0));
+
+        list.items[0].state = TodoState::InProgress;
+        sync_time_entries(&conn, &HashSet::from([origin]), &list).unwrap();
+        assert_eq!(entry_counts(&conn), (2, 1));
+
+        // Removing the item from the day closes its interval
+        list.items.clear();
+        sync_time_entries(&conn, &HashSet::from([origin]), &list).unwrap();
+        assert_eq!(entry_counts(&conn), (2, 0));
+    }
+
+    #[test]
+    fn test_split_by_day_clips_to_range() {
+        let day = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+        let local = |d: NaiveDate, h: u32| {
+            Local
+                .from_local_datetime(&d.and_hms_opt(h, 0, 0).unwrap())
+                .earliest()
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let next = day.succ_opt().unwrap();
+        let entry = TimeEntry {
+            origin_id: Uuid::new_v4(),
+            started_at: local(day, 22),
+            stopped_at: Some(local(next, 1)),
+        };
+
+        let slices = entry.split_by_day(day, next, Utc::now());
+        assert_eq!(slices.len(), 2);
+        assert_eq!(slices[0].0, day);
+        assert_eq!(slices[1], (next, Duration::hours(1)));
+
+        let only_first = entry.split_by_day(day, day, Utc::now());
+        assert_eq!(only_first.len(), 1);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::minutes(7)), "7m");
+        assert_eq!(format_duration(Duration::minutes(125)), "2h 05m");
+        assert_eq!(format_duration(Duration::seconds(-5)), "0m");
+    }
+}
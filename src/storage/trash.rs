@@ -0,0 +1,246 @@
+use crate::todo::{TodoItem, TodoList};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU32, Ordering};
+use uuid::Uuid;
+
+const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// `[trash]` section of the config file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashConfig {
+    /// Days a deleted todo stays in the trash before it is purged for good. 0 keeps it until
+    /// the trash is emptied by hand.
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_retention_days() -> u32 {
+    DEFAULT_RETENTION_DAYS
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: DEFAULT_RETENTION_DAYS,
+        }
+    }
+}
+
+static RETENTION_DAYS: AtomicU32 = AtomicU32::new(DEFAULT_RETENTION_DAYS);
+
+/// Choose how long deleted todos are kept from now on.
+pub fn install_trash_config(config: &TrashConfig) {
+    RETENTION_DAYS.store(config.retention_days, Ordering::Relaxed);
+}
+
+/// Todos deleted before this moment have outlived the retention window. `None` when they
+/// are kept forever.
+pub fn retention_cutoff() -> Option<DateTime<Utc>> {
+    match RETENTION_DAYS.load(Ordering::Relaxed) {
+        0 => None,
+        days => Some(Utc::now() - Duration::days(days.into())),
+    }
+}
+
+/// A deleted todo, with where it was deleted from so it can be put back.
+#[derive(Debug, Clone)]
+pub struct TrashedTodo {
+    /// The todo as it was, with `deleted_at` set and its old `parent_id`.
+    pub item: TodoItem,
+    /// The day it was deleted from, for a daily list.
+    pub date: Option<NaiveDate>,
+    /// The project list it was deleted from.
+    pub list: Option<String>,
+    /// Its index in the list when it was deleted.
+    pub position: usize,
+}
+
+impl TrashedTodo {
+    pub fn deleted_at(&self) -> DateTime<Utc> {
+        self.item.deleted_at.unwrap_or_default()
+    }
+
+    /// The day (YYYY-MM-DD) or project list it was deleted from, for display.
+    pub fn origin_label(&self) -> String {
+        match (&self.list, self.date) {
+            (Some(name), _) => name.clone(),
+            (None, Some(date)) => date.format("%Y-%m-%d").to_string(),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Whether both were removed from the same list by the same delete.
+    fn deleted_with(&self, other: &TrashedTodo) -> bool {
+        self.list == other.list && self.date == other.date && self.item.deleted_at == other.item.deleted_at
+    }
+}
+
+/// The todo `id` and the subtasks deleted along with it, in list order. Empty when `id` is
+/// not in the trash.
+pub fn subtree(trash: &[TrashedTodo], id: Uuid) -> Vec<&TrashedTodo> {
+    let Some(root) = trash.iter().find(|entry| entry.item.id == id) else {
+        return Vec::new();
+    };
+
+    let mut group: Vec<&TrashedTodo> = trash.iter().filter(|entry| entry.deleted_with(root)).collect();
+    group.sort_by_key(|entry| entry.position);
+
+    let mut members = vec![root];
+    for entry in group.into_iter().filter(|entry| entry.position > root.position) {
+        if entry
+            .item
+            .parent_id
+            .is_some_and(|parent| members.iter().any(|member| member.item.id == parent))
+        {
+            members.push(entry);
+        }
+    }
+    members
+}
+
+/// Entries that were deleted on their own or at the top of a deleted subtree, each with the
+/// number of subtasks that went with it.
+pub fn roots(trash: &[TrashedTodo]) -> Vec<(&TrashedTodo, usize)> {
+    trash
+        .iter()
+        .filter(|entry| {
+            !trash.iter().any(|other| {
+                other.deleted_with(entry) && Some(other.item.id) == entry.item.parent_id
+            })
+        })
+        .map(|entry| (entry, subtree(trash, entry.item.id).len() - 1))
+        .collect()
+}
+
+/// Put a deleted subtree, as returned by `subtree`, back into `list`: under its old parent if
+/// that is still there, otherwise at the top level, as close to its old position as the
+/// current hierarchy allows. Returns where the items now are.
+pub fn reinsert(list: &mut TodoList, entries: Vec<TrashedTodo>) -> Range<usize> {
+    let Some(root) = entries.first() else {
+        return 0..0;
+    };
+
+    let parent = root
+        .item
+        .parent_id
+        .and_then(|parent_id| list.items.iter().position(|item| item.id == parent_id));
+    let (level, first, last) = match parent {
+        Some(p) => {
+            let (_, end) = list.get_item_range(p).unwrap_or((p, p + 1));
+            (list.items[p].indent_level + 1, p + 1, end)
+        }
+        None => (0, 0, list.items.len()),
+    };
+
+    let mut index = root.position.clamp(first, last);
+    // Do not adopt the subtasks of the item that now sits at the old position
+    while index < last && list.items[index].indent_level > level {
+        index += 1;
+    }
+
+    let base_indent = root.item.indent_level;
+    let count = entries.len();
+    let items = entries.into_iter().map(|entry| {
+        let mut item = entry.item;
+        item.indent_level = item.indent_level.saturating_sub(base_indent) + level;
+        item.deleted_at = None;
+        item
+    });
+    list.items.splice(index..index, items);
+    list.recalculate_parent_ids();
+
+    index..index + count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn trashed(list: &TodoList, index: usize, deleted_at: DateTime<Utc>) -> TrashedTodo {
+        let mut item = list.items[index].clone();
+        item.deleted_at = Some(deleted_at);
+        TrashedTodo {
+            item,
+            date: Some(list.date),
+            list: None,
+            position: index,
+        }
+    }
+
+    fn sample() -> TodoList {
+        let mut list = TodoList::new(NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(), PathBuf::new());
+        list.add_item_with_indent("Plan".to_string(), 0);
+        list.add_item_with_indent("Draft".to_string(), 1);
+        list.add_item_with_indent("Outline".to_string(), 2);
+        list.add_item_with_indent("Review".to_string(), 1);
+        list.add_item_with_indent("Ship".to_string(), 0);
+        list.recalculate_parent_ids();
+        list
+    }
+
+    #[test]
+    fn test_subtree_and_roots_follow_each_delete() {
+        let list = sample();
+        let now = Utc::now();
+        let earlier = now - Duration::hours(1);
+        // "Review" went first, then "Draft" with "Outline"
+        let trash = vec![
+            trashed(&list, 3, earlier),
+            trashed(&list, 1, now),
+            trashed(&list, 2, now),
+        ];
+
+        let contents: Vec<&str> = subtree(&trash, list.items[1].id)
+            .iter()
+            .map(|entry| entry.item.content.as_str())
+            .collect();
+        assert_eq!(contents, vec!["Draft", "Outline"]);
+        assert_eq!(subtree(&trash, list.items[3].id).len(), 1);
+        assert!(subtree(&trash, list.items[0].id).is_empty());
+
+        let roots: Vec<(&str, usize)> = roots(&trash)
+            .into_iter()
+            .map(|(entry, subtasks)| (entry.item.content.as_str(), subtasks))
+            .collect();
+        assert_eq!(roots, vec![("Review", 0), ("Draft", 1)]);
+    }
+
+    #[test]
+    fn test_reinsert_returns_items_under_their_parent() {
+        let mut list = sample();
+        let now = Utc::now();
+        let entries = vec![trashed(&list, 1, now), trashed(&list, 2, now)];
+        list.items.drain(1..3);
+        list.recalculate_parent_ids();
+
+        assert_eq!(reinsert(&mut list, entries), 1..3);
+        let contents: Vec<&str> = list.items.iter().map(|item| item.content.as_str()).collect();
+        assert_eq!(contents, vec!["Plan", "Draft", "Outline", "Review", "Ship"]);
+        assert_eq!(list.items[1].parent_id, Some(list.items[0].id));
+        assert_eq!(list.items[2].indent_level, 2);
+        assert!(list.items[1].deleted_at.is_none());
+    }
+
+    #[test]
+    fn test_reinsert_without_parent_goes_to_top_level() {
+        let mut list = sample();
+        let entry = trashed(&list, 2, Utc::now());
+        // The whole "Plan" subtree is gone now
+        list.items.drain(0..4);
+
+        assert_eq!(reinsert(&mut list, vec![entry]), 1..2);
+        assert_eq!(list.items[1].content, "Outline");
+        assert_eq!(list.items[1].indent_level, 0);
+        assert_eq!(list.items[1].parent_id, None);
+
+        // An old position inside another subtree moves past its subtasks
+        let mut list = sample();
+        let mut entry = trashed(&list, 4, Utc::now());
+        list.items.remove(4);
+        entry.position = 2;
+        assert_eq!(reinsert(&mut list, vec![entry]), 4..5);
+    }
+}
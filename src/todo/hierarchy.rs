@@ -1,5 +1,4 @@
-use super::TodoList;
-use super::state::TodoState;
+use super::{StateRegistry, TodoList, TodoState};
 use anyhow::{Result, anyhow};
 
 impl TodoList {
@@ -13,13 +12,50 @@ impl TodoList {
 
         let completed = children
             .iter()
-            .filter(|item| item.state == TodoState::Checked)
+            .filter(|item| item.is_complete())
             .count();
         let total = children.len();
 
         (completed, total)
     }
 
+    /// Share of the subtree under `index` that is finished, as a percentage.
+    /// `None` unless the item has children and some, but not all, are done.
+    pub fn subtree_progress(&self, index: usize) -> Option<usize> {
+        let (completed, total) = self.count_children_stats(index);
+        (completed > 0 && completed < total).then(|| completed * 100 / total)
+    }
+
+    fn parent_index(&self, index: usize) -> Option<usize> {
+        let indent_level = self.items.get(index)?.indent_level;
+        self.items[..index]
+            .iter()
+            .rposition(|item| item.indent_level < indent_level)
+    }
+
+    /// Apply the `auto_complete_parents` rule after the item at `index` changed state.
+    pub fn sync_parent_states(&mut self, index: usize) {
+        if StateRegistry::global().auto_complete_parents() {
+            self.complete_parents_from(index);
+        }
+    }
+
+    /// Walk up from `index`: an ancestor whose whole subtree is finished is checked off,
+    /// and a finished ancestor with an open descendant is reopened.
+    pub fn complete_parents_from(&mut self, index: usize) {
+        let mut current = index;
+        while let Some(parent) = self.parent_index(current) {
+            let (completed, total) = self.count_children_stats(parent);
+            let item = &mut self.items[parent];
+            if completed == total && !item.is_complete() {
+                item.set_state(TodoState::Checked);
+            } else if completed < total && item.is_complete() {
+                item.set_state(TodoState::Empty);
+            }
+            current = parent;
+        }
+    }
+
     pub fn has_children(&self, index: usize) -> bool {
         if index >= self.items.len() {
             return false;
@@ -287,6 +323,40 @@ mod tests {
         assert_eq!(end, 5); // No children
     }
 
+    #[test]
+    fn test_complete_parents_from() {
+        let mut list = create_test_list();
+        list.add_item_with_indent("Project".to_string(), 0);
+        list.add_item_with_indent("Phase".to_string(), 1);
+        list.add_item_with_indent("Step 1".to_string(), 2);
+        list.add_item_with_indent("Step 2".to_string(), 2);
+        list.add_item_with_indent("Wrap up".to_string(), 1);
+
+        list.items[2].set_state(TodoState::Checked);
+        list.complete_parents_from(2);
+        assert_eq!(list.items[1].state, TodoState::Empty);
+        assert_eq!(list.subtree_progress(1), Some(50));
+        assert_eq!(list.subtree_progress(0), Some(25));
+
+        list.items[3].set_state(TodoState::Checked);
+        list.complete_parents_from(3);
+        assert_eq!(list.items[1].state, TodoState::Checked);
+        assert!(list.items[1].completed_at.is_some());
+        assert_eq!(list.items[0].state, TodoState::Empty);
+
+        list.items[4].set_state(TodoState::Checked);
+        list.complete_parents_from(4);
+        assert_eq!(list.items[0].state, TodoState::Checked);
+        assert_eq!(list.subtree_progress(0), None);
+
+        // Reopening a leaf reopens every finished ancestor
+        list.items[2].set_state(TodoState::Empty);
+        list.complete_parents_from(2);
+        assert_eq!(list.items[1].state, TodoState::Empty);
+        assert_eq!(list.items[0].state, TodoState::Empty);
+        assert!(list.items[0].completed_at.is_none());
+    }
+
     #[test]
     fn test_indent_outdent() {
         let mut list = create_test_list();
@@ -1,8 +1,9 @@
+use super::recurrence::{Recurrence, split_recurrence};
 use super::state::TodoState;
 use chrono::{DateTime, NaiveDate, Utc};
 use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TodoItem {
     pub id: Uuid,
     pub content: String,
@@ -11,6 +12,20 @@ pub struct TodoItem {
     pub parent_id: Option<Uuid>,
     pub due_date: Option<NaiveDate>,
     pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub recurrence: Option<Recurrence>,
+    /// Id shared by every instance of a recurring todo, kept across rollover.
+    pub series_id: Option<Uuid>,
+    /// Id of the first daily occurrence of this task; `None` until it is rolled over.
+    pub origin_id: Option<Uuid>,
+    /// Number of days this task has been carried forward unfinished.
+    pub carry_count: u32,
+    /// Lineage ids (see `origin`) of todos that must be finished before this one.
+    pub blocked_by: Vec<Uuid>,
+    /// Day the todo becomes actionable; until then it waits in the scheduled area.
+    pub start_date: Option<NaiveDate>,
+    /// Heading of the section the todo is filed under, written as `## <heading>` above it.
+    pub section: Option<String>,
     pub collapsed: bool,
     pub created_at: DateTime<Utc>,
     pub modified_at: DateTime<Utc>,
@@ -29,6 +44,14 @@ impl TodoItem {
             parent_id: None,
             due_date: None,
             description: None,
+            tags: Vec::new(),
+            recurrence: None,
+            series_id: None,
+            origin_id: None,
+            carry_count: 0,
+            blocked_by: Vec::new(),
+            start_date: None,
+            section: None,
             collapsed: false,
             created_at: now,
             modified_at: now,
@@ -40,7 +63,7 @@ impl TodoItem {
     #[cfg(test)]
     pub fn with_state(content: String, state: TodoState, indent_level: usize) -> Self {
         let now = Utc::now();
-        let completed_at = if state == TodoState::Checked {
+        let completed_at = if state.is_complete() {
             Some(now)
         } else {
             None
@@ -53,6 +76,14 @@ impl TodoItem {
             parent_id: None,
             due_date: None,
             description: None,
+            tags: Vec::new(),
+            recurrence: None,
+            series_id: None,
+            origin_id: None,
+            carry_count: 0,
+            blocked_by: Vec::new(),
+            start_date: None,
+            section: None,
             collapsed: false,
             created_at: now,
             modified_at: now,
@@ -71,7 +102,7 @@ impl TodoItem {
         collapsed: bool,
     ) -> Self {
         let now = Utc::now();
-        let completed_at = if state == TodoState::Checked {
+        let completed_at = if state.is_complete() {
             Some(now)
         } else {
             None
@@ -84,6 +115,14 @@ impl TodoItem {
             parent_id,
             due_date,
             description,
+            tags: Vec::new(),
+            recurrence: None,
+            series_id: None,
+            origin_id: None,
+            carry_count: 0,
+            blocked_by: Vec::new(),
+            start_date: None,
+            section: None,
             collapsed,
             created_at: now,
             modified_at: now,
@@ -104,6 +143,12 @@ impl TodoItem {
         self.update_completed_at(was_complete);
     }
 
+    pub fn set_state(&mut self, state: TodoState) {
+        let was_complete = self.state.is_complete();
+        self.state = state;
+        self.update_completed_at(was_complete);
+    }
+
     fn update_completed_at(&mut self, was_complete: bool) {
         let is_complete = self.state.is_complete();
         self.modified_at = Utc::now();
@@ -118,6 +163,113 @@ impl TodoItem {
         self.state.is_complete()
     }
 
+    /// Id shared by every daily occurrence of this task.
+    pub fn origin(&self) -> Uuid {
+        self.origin_id.unwrap_or(self.id)
+    }
+
+    /// Whether the todo should wait in the scheduled area instead of appearing on `date`.
+    pub fn starts_after(&self, date: NaiveDate) -> bool {
+        self.start_date.is_some_and(|start| start > date)
+    }
+
+    /// Replace the content, moving any `#tags`, `@every(...)` rule, `@start(...)` date and
+    /// `@after(...)` blockers found in it into `tags`, `recurrence`, `start_date` and
+    /// `blocked_by`.
+    pub fn set_content(&mut self, raw: &str) {
+        let (content, mut blocked_by) = split_after(raw);
+        let (content, recurrence) = split_recurrence(&content);
+        let (content, start_date) = split_start_date(&content);
+        let (content, tags) = split_tags(&content);
+        blocked_by.retain(|id| *id != self.id);
+        self.content = content;
+        self.tags = tags;
+        self.start_date = start_date;
+        self.blocked_by = blocked_by;
+        if recurrence.is_none() {
+            self.series_id = None;
+        } else if self.series_id.is_none() {
+            self.series_id = Some(self.id);
+        }
+        self.recurrence = recurrence;
+        self.modified_at = Utc::now();
+    }
+
+    /// Like `set_content`, but keeps existing tags, recurrence, start date and blockers that
+    /// `raw` does not mention. Used by the API and MCP server, where clients send plain content.
+    pub fn merge_content(&mut self, raw: &str) {
+        let mut tags = std::mem::take(&mut self.tags);
+        let recurrence = self.recurrence.take();
+        let series_id = self.series_id;
+        let start_date = self.start_date;
+        let blocked_by = std::mem::take(&mut self.blocked_by);
+
+        self.set_content(raw);
+
+        if self.start_date.is_none() {
+            self.start_date = start_date;
+        }
+        if self.blocked_by.is_empty() {
+            self.blocked_by = blocked_by;
+        }
+
+        for tag in self.tags.drain(..) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                tags.push(tag);
+            }
+        }
+        self.tags = tags;
+        if self.recurrence.is_none() {
+            self.recurrence = recurrence;
+            self.series_id = series_id;
+        }
+    }
+
+    /// Content with tags, the recurrence rule, start date and blockers, as shown when editing
+    /// the item.
+    pub fn editable_content(&self) -> String {
+        let mut text = self.content_with_tags();
+        if let Some(ref rule) = self.recurrence {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(&format!("@every({rule})"));
+        }
+        if let Some(start) = self.start_date {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(&format!("@start({})", start.format("%Y-%m-%d")));
+        }
+        if !self.blocked_by.is_empty() {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            let ids: Vec<String> = self.blocked_by.iter().map(Uuid::to_string).collect();
+            text.push_str(&format!("@after({})", ids.join(",")));
+        }
+        text
+    }
+
+    /// Content with tags appended.
+    pub fn content_with_tags(&self) -> String {
+        let mut text = self.content.clone();
+        for tag in &self.tags {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push('#');
+            text.push_str(tag);
+        }
+        text
+    }
+
+    /// Case-insensitive tag match. Accepts the tag with or without a leading `#`.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim_start_matches('#');
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
     #[cfg(test)]
     pub fn can_indent(&self, prev_indent: Option<usize>) -> bool {
         match prev_indent {
@@ -144,6 +296,119 @@ impl TodoItem {
     }
 }
 
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | '/')
+}
+
+/// Split `#tag` tokens out of `content`.
+/// A tag must start at a word boundary and its first character must be a letter,
+/// so `C#` and issue references like `#123` are left alone.
+/// Returns the content with tags removed and the de-duplicated tags without `#`.
+/// Each tag is removed together with one adjacent space; the rest of the content is kept as is.
+pub fn split_tags(content: &str) -> (String, Vec<String>) {
+    let mut cleaned = String::with_capacity(content.len());
+    let mut tags: Vec<String> = Vec::new();
+    let mut skip_separator = false;
+    let mut rest = content;
+
+    while !rest.is_empty() {
+        let split = if rest.starts_with(char::is_whitespace) {
+            rest.find(|c: char| !c.is_whitespace())
+        } else {
+            rest.find(char::is_whitespace)
+        };
+        let (run, tail) = rest.split_at(split.unwrap_or(rest.len()));
+        rest = tail;
+
+        if run.starts_with(char::is_whitespace) {
+            let mut chars = run.chars();
+            if std::mem::take(&mut skip_separator) {
+                chars.next();
+            }
+            cleaned.push_str(chars.as_str());
+            continue;
+        }
+
+        let tag = run
+            .strip_prefix('#')
+            .filter(|t| t.chars().next().is_some_and(char::is_alphabetic))
+            .filter(|t| t.chars().all(is_tag_char));
+
+        match tag {
+            Some(tag) => {
+                if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                    tags.push(tag.to_string());
+                }
+                if cleaned.ends_with(char::is_whitespace) {
+                    cleaned.pop();
+                } else {
+                    skip_separator = true;
+                }
+            }
+            None => cleaned.push_str(run),
+        }
+    }
+
+    (cleaned, tags)
+}
+
+/// Normalize tags supplied by a client: strip a leading `#`, drop blanks and duplicates.
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().trim_start_matches('#');
+        if !tag.is_empty() && !result.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            result.push(tag.to_string());
+        }
+    }
+    result
+}
+
+/// Split an `@after(<id>,...)` list of blocking todo ids out of `content`.
+/// An annotation without a valid id is left in the content so it stays visible to the user.
+pub fn split_after(content: &str) -> (String, Vec<Uuid>) {
+    if let Some(start) = content.find("@after(")
+        && let Some(end) = content[start..].find(')')
+    {
+        let mut ids: Vec<Uuid> = Vec::new();
+        for id in content[start + 7..start + end].split(',') {
+            if let Ok(id) = Uuid::parse_str(id.trim())
+                && !ids.contains(&id)
+            {
+                ids.push(id);
+            }
+        }
+        if !ids.is_empty() {
+            let before = content[..start].trim_end();
+            let after = content[start + end + 1..].trim_start();
+            let cleaned = match (before.is_empty(), after.is_empty()) {
+                (false, false) => format!("{before} {after}"),
+                _ => format!("{before}{after}"),
+            };
+            return (cleaned, ids);
+        }
+    }
+    (content.to_string(), Vec::new())
+}
+
+/// Split an `@start(YYYY-MM-DD)` date out of `content`.
+/// An unparseable date is left in the content so it stays visible to the user.
+pub fn split_start_date(content: &str) -> (String, Option<NaiveDate>) {
+    if let Some(start) = content.find("@start(")
+        && let Some(end) = content[start..].find(')')
+        && let Ok(date) = NaiveDate::parse_from_str(&content[start + 7..start + end], "%Y-%m-%d")
+    {
+        let before = content[..start].trim_end();
+        let after = content[start + end + 1..].trim_start();
+        let cleaned = match (before.is_empty(), after.is_empty()) {
+            (false, false) => format!("{before} {after}"),
+            _ => format!("{before}{after}"),
+        };
+        return (cleaned, Some(date));
+    }
+    (content.to_string(), None)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -247,4 +512,99 @@ mod tests {
         item.outdent(); // Should not go negative
         assert_eq!(item.indent_level, 0);
     }
+
+    #[test]
+    fn test_split_tags() {
+        let (content, tags) = split_tags("Review PR #review for #Team-A");
+        assert_eq!(content, "Review PR for");
+        assert_eq!(tags, vec!["review", "Team-A"]);
+
+        let (content, tags) = split_tags("Fix #123 in C# code");
+        assert_eq!(content, "Fix #123 in C# code");
+        assert!(tags.is_empty());
+
+        let (_, tags) = split_tags("#a #A #b");
+        assert_eq!(tags, vec!["a", "b"]);
+
+        let (content, tags) = split_tags("#urgent Call  Bob,\tthen  email #work team");
+        assert_eq!(content, "Call  Bob,\tthen  email team");
+        assert_eq!(tags, vec!["urgent", "work"]);
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = normalize_tags(vec![" #Work".into(), "".into(), "work".into(), "#".into(), "home".into()]);
+        assert_eq!(tags, vec!["Work", "home"]);
+    }
+
+    #[test]
+    fn test_set_content_with_after() {
+        let blocker = Uuid::new_v4();
+        let mut item = TodoItem::new("Deploy".to_string(), 0);
+        item.set_content(&format!("{} @after({blocker}) #ops", item.editable_content()));
+        assert_eq!(item.content, "Deploy");
+        assert_eq!(item.blocked_by, vec![blocker]);
+        assert!(item.has_tag("ops"));
+        assert_eq!(item.editable_content(), format!("Deploy #ops @after({blocker})"));
+
+        // Taking the annotation out of the editor clears the blockers
+        item.set_content("Deploy #ops");
+        assert!(item.blocked_by.is_empty());
+
+        item.set_content("Deploy @after(not-an-id)");
+        assert_eq!(item.content, "Deploy @after(not-an-id)");
+        assert!(item.blocked_by.is_empty());
+
+        item.blocked_by = vec![blocker];
+        item.merge_content("Deploy to prod");
+        assert_eq!(item.blocked_by, vec![blocker]);
+    }
+
+    #[test]
+    fn test_set_content_and_has_tag() {
+        let mut item = TodoItem::new("Task".to_string(), 0);
+        item.set_content("Write docs #docs");
+        assert_eq!(item.content, "Write docs");
+        assert!(item.has_tag("docs"));
+        assert!(item.has_tag("#DOCS"));
+        assert!(!item.has_tag("review"));
+        assert_eq!(item.content_with_tags(), "Write docs #docs");
+    }
+
+    #[test]
+    fn test_recurrence_in_content() {
+        let mut item = TodoItem::new(String::new(), 0);
+        item.set_content("Standup @every(weekday) #team");
+        assert_eq!(item.content, "Standup");
+        assert_eq!(item.series_id, Some(item.id));
+        assert_eq!(item.editable_content(), "Standup #team @every(weekday)");
+
+        item.merge_content("Daily standup");
+        assert_eq!(item.content, "Daily standup");
+        assert_eq!(item.tags, vec!["team"]);
+        assert!(item.recurrence.is_some());
+
+        item.set_content("Daily standup");
+        assert!(item.recurrence.is_none());
+        assert!(item.series_id.is_none());
+    }
+
+    #[test]
+    fn test_start_date_in_content() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        let mut item = TodoItem::new(String::new(), 0);
+        item.set_content("Book flights @start(2026-03-02) #travel");
+        assert_eq!(item.content, "Book flights");
+        assert_eq!(item.start_date, Some(date));
+        assert!(item.starts_after(date.pred_opt().unwrap()));
+        assert!(!item.starts_after(date));
+        assert_eq!(item.editable_content(), "Book flights #travel @start(2026-03-02)");
+
+        item.merge_content("Book cheap flights");
+        assert_eq!(item.start_date, Some(date));
+
+        let (content, start) = split_start_date("Odd @start(next week)");
+        assert_eq!(content, "Odd @start(next week)");
+        assert_eq!(start, None);
+    }
 }
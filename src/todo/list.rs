@@ -1,4 +1,4 @@
-use super::TodoItem;
+use super::{TodoItem, TodoState};
 use anyhow::{anyhow, Result};
 use chrono::NaiveDate;
 use std::collections::HashSet;
@@ -10,6 +10,24 @@ pub struct TodoList {
     pub date: NaiveDate,
     pub items: Vec<TodoItem>,
     pub file_path: PathBuf,
+    /// Name of a project list such as "backlog"; `None` for a daily list.
+    /// A project list's `date` is the day it was loaded, used for due and start dates.
+    pub name: Option<String>,
+    /// Revision of the stored list that `base_items` came from; a save is refused if the
+    /// stored list has moved on since.
+    pub revision: u64,
+    /// Items as last loaded or saved, used to replay local edits onto a concurrent save.
+    pub base_items: Vec<TodoItem>,
+    /// Free-form text written above the todos in the markdown file, such as a journal entry.
+    pub notes: Option<String>,
+    /// Free-form text written below the todos. Text between todos ends up here too.
+    pub trailing_notes: Option<String>,
+    /// Scheduled items merged into `items` since the list was last stored. One that is gone
+    /// from `items` by the next save was deleted, and leaves the scheduled area for the trash.
+    pub merged_scheduled: Vec<TodoItem>,
+    /// Set when the list's markdown file was edited in a way that conflicts with the stored
+    /// list. The stored list is what was loaded; the file is left alone until it is fixed.
+    pub file_conflict: bool,
 }
 
 impl TodoList {
@@ -18,6 +36,13 @@ impl TodoList {
             date,
             items: Vec::new(),
             file_path,
+            name: None,
+            revision: 0,
+            base_items: Vec::new(),
+            notes: None,
+            trailing_notes: None,
+            merged_scheduled: Vec::new(),
+            file_conflict: false,
         }
     }
 
@@ -26,18 +51,111 @@ impl TodoList {
             date,
             items,
             file_path,
+            name: None,
+            revision: 0,
+            base_items: Vec::new(),
+            notes: None,
+            trailing_notes: None,
+            merged_scheduled: Vec::new(),
+            file_conflict: false,
+        }
+    }
+
+    pub fn named(name: &str, date: NaiveDate, file_path: PathBuf, items: Vec<TodoItem>) -> Self {
+        Self {
+            date,
+            items,
+            file_path,
+            name: Some(name.to_string()),
+            revision: 0,
+            base_items: Vec::new(),
+            notes: None,
+            trailing_notes: None,
+            merged_scheduled: Vec::new(),
+            file_conflict: false,
+        }
+    }
+
+    /// Record that `items` are what revision `revision` of the stored list holds.
+    pub fn mark_stored(&mut self, revision: u64) {
+        self.revision = revision;
+        self.base_items = self.items.clone();
+        self.merged_scheduled.clear();
+    }
+
+    /// Human-readable title: the project name, or the date for a daily list.
+    pub fn title(&self) -> String {
+        match self.name {
+            Some(ref name) => name.clone(),
+            None => self.date.format("%B %d, %Y").to_string(),
         }
     }
 
     pub fn add_item(&mut self, content: String) {
-        self.items.push(TodoItem::new(content, 0));
+        let mut item = TodoItem::new(String::new(), 0);
+        item.set_content(&content);
+        self.items.push(item);
     }
 
     pub fn add_item_with_indent(&mut self, content: String, indent_level: usize) {
         self.items.push(TodoItem::new(content, indent_level));
     }
 
+    #[cfg(test)]
     pub fn get_incomplete_items(&self) -> Vec<TodoItem> {
+        self.collect_with_ancestors(|item| !item.is_complete())
+    }
+
+    /// Items to carry onto `target`: every item whose state rolls over, plus a fresh (reset)
+    /// instance of each finished recurring item whose rule matched on any day after this
+    /// list's date up to `target`, with ancestors for context.
+    /// A finished recurring item that is not due by `target` yields a fresh top-level instance
+    /// with `start_date` set to its next matching day, to wait in the scheduled area.
+    /// Items that roll over have `carry_count` bumped by the number of days skipped; ancestors
+    /// carried only for context keep theirs.
+    pub fn get_rollover_items(&self, target: NaiveDate) -> Vec<TodoItem> {
+        let next_due = |item: &TodoItem| {
+            if item.state.rolls_over() {
+                return None;
+            }
+            item.recurrence.as_ref()?.next_after(self.date)
+        };
+        let is_renewed = |item: &TodoItem| next_due(item).is_some_and(|due| due <= target);
+
+        let days_carried = (target - self.date).num_days().max(1) as u32;
+
+        let mut items: Vec<TodoItem> = self
+            .collect_with_ancestors(|item| item.state.rolls_over() || is_renewed(item))
+            .into_iter()
+            .map(|mut item| {
+                if is_renewed(&item) {
+                    renew(&mut item);
+                } else if item.state.rolls_over() {
+                    item.carry_count += days_carried;
+                }
+                item
+            })
+            .collect();
+
+        // Items carried as context keep their rule and are looked at again on the next rollover
+        let carried: HashSet<Uuid> = items.iter().map(|item| item.id).collect();
+        for item in &self.items {
+            if let Some(due) = next_due(item).filter(|&due| due > target)
+                && !carried.contains(&item.id)
+            {
+                let mut instance = item.clone();
+                renew(&mut instance);
+                instance.indent_level = 0;
+                instance.parent_id = None;
+                instance.start_date = Some(due);
+                items.push(instance);
+            }
+        }
+
+        items
+    }
+
+    fn collect_with_ancestors(&self, include: impl Fn(&TodoItem) -> bool) -> Vec<TodoItem> {
         if self.items.is_empty() {
             return Vec::new();
         }
@@ -48,7 +166,7 @@ impl TodoList {
         let mut include_ids: HashSet<Uuid> = HashSet::new();
 
         for item in &self.items {
-            if !item.is_complete() {
+            if include(item) {
                 include_ids.insert(item.id);
                 self.collect_ancestor_ids(item, &id_to_item, &mut include_ids);
             }
@@ -78,6 +196,62 @@ impl TodoList {
         }
     }
 
+    /// Remove items that start after this list's date, with their subtasks, so they can wait
+    /// in the scheduled area. Each moved subtree is re-rooted at indent level 0.
+    pub fn take_scheduled(&mut self) -> Vec<TodoItem> {
+        let mut taken = Vec::new();
+        let mut index = 0;
+
+        while index < self.items.len() {
+            if !self.items[index].starts_after(self.date) {
+                index += 1;
+                continue;
+            }
+            taken.extend(self.drain_subtree(index));
+        }
+
+        if !taken.is_empty() {
+            self.recalculate_parent_ids();
+        }
+        taken
+    }
+
+    /// Remove the item at `index` with its subtasks, re-rooted at indent level 0.
+    pub fn take_subtree(&mut self, index: usize) -> Vec<TodoItem> {
+        if index >= self.items.len() {
+            return Vec::new();
+        }
+        let taken = self.drain_subtree(index);
+        self.recalculate_parent_ids();
+        taken
+    }
+
+    fn drain_subtree(&mut self, index: usize) -> Vec<TodoItem> {
+        let (_, end) = self.get_item_range(index).unwrap_or((index, index + 1));
+        let base_indent = self.items[index].indent_level;
+        let mut taken: Vec<TodoItem> = self
+            .items
+            .drain(index..end)
+            .map(|mut item| {
+                item.indent_level -= base_indent;
+                item
+            })
+            .collect();
+        taken[0].parent_id = None;
+        taken
+    }
+
+    /// Append scheduled items that have become actionable on this list's date.
+    pub fn merge_scheduled(&mut self, items: Vec<TodoItem>) {
+        for mut item in items {
+            if item.start_date.is_some_and(|start| start <= self.date) {
+                item.start_date = None;
+            }
+            self.merged_scheduled.push(item.clone());
+            self.items.push(item);
+        }
+    }
+
     #[cfg(test)]
     pub fn toggle_item_state(&mut self, index: usize) -> Result<()> {
         if index >= self.items.len() {
@@ -95,6 +269,39 @@ impl TodoList {
         Ok(self.items.remove(index))
     }
 
+    /// Whether the item at `index` waits on an unfinished blocker. Blockers in this list are
+    /// checked directly; `open_external` holds blockers from other days that are still open.
+    pub fn is_blocked(&self, index: usize, open_external: &HashSet<Uuid>) -> bool {
+        let Some(item) = self.items.get(index) else {
+            return false;
+        };
+        item.blocked_by.iter().any(|blocker_id| {
+            match self
+                .items
+                .iter()
+                .find(|other| other.id == *blocker_id || other.origin() == *blocker_id)
+            {
+                Some(blocker) => !blocker.is_complete(),
+                None => open_external.contains(blocker_id),
+            }
+        })
+    }
+
+    /// Blocker ids that do not refer to an item in this list.
+    pub fn external_blocker_ids(&self) -> Vec<Uuid> {
+        let mut ids: Vec<Uuid> = Vec::new();
+        for blocker_id in self.items.iter().flat_map(|item| &item.blocked_by) {
+            let in_list = self
+                .items
+                .iter()
+                .any(|other| other.id == *blocker_id || other.origin() == *blocker_id);
+            if !in_list && !ids.contains(blocker_id) {
+                ids.push(*blocker_id);
+            }
+        }
+        ids
+    }
+
     /// Returns the set of indices that should be hidden due to collapsed parents
     pub fn build_hidden_indices(&self) -> HashSet<usize> {
         let mut hidden = HashSet::new();
@@ -115,6 +322,53 @@ impl TodoList {
         hidden
     }
 
+    /// Returns true if the item at `index`, one of its ancestors or one of its
+    /// descendants carries `tag`. Used to keep hierarchy context when filtering.
+    pub fn matches_tag_filter(&self, index: usize, tag: &str) -> bool {
+        let Some(item) = self.items.get(index) else {
+            return false;
+        };
+        if item.has_tag(tag) {
+            return true;
+        }
+
+        let mut current_indent = item.indent_level;
+        for ancestor in self.items[..index].iter().rev() {
+            if current_indent == 0 {
+                break;
+            }
+            if ancestor.indent_level < current_indent {
+                if ancestor.has_tag(tag) {
+                    return true;
+                }
+                current_indent = ancestor.indent_level;
+            }
+        }
+
+        self.items[index + 1..]
+            .iter()
+            .take_while(|child| child.indent_level > item.indent_level)
+            .any(|child| child.has_tag(tag))
+    }
+
+    /// Returns the set of indices hidden by a tag filter
+    pub fn build_tag_hidden_indices(&self, tag: &str) -> HashSet<usize> {
+        (0..self.items.len())
+            .filter(|&i| !self.matches_tag_filter(i, tag))
+            .collect()
+    }
+
+    /// All distinct tags in list order, compared case-insensitively
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in self.items.iter().flat_map(|item| item.tags.iter()) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.clone());
+            }
+        }
+        tags
+    }
+
     pub fn remove_item_range(&mut self, start: usize, end: usize) -> Result<Vec<TodoItem>> {
         if start >= self.items.len() || end > self.items.len() || start >= end {
             return Err(anyhow!("Invalid range"));
@@ -136,6 +390,50 @@ impl TodoList {
         Ok(())
     }
 
+    /// Heading to show above the item at `index` while the items in `hidden` are not shown:
+    /// its section, when the closest shown item before it is in another one.
+    pub fn section_heading(&self, index: usize, hidden: &HashSet<usize>) -> Option<&str> {
+        let section = self.items.get(index)?.section.as_deref()?;
+        let previous = (0..index)
+            .rev()
+            .find(|i| !hidden.contains(i))
+            .and_then(|i| self.items[i].section.as_deref());
+        (previous != Some(section)).then_some(section)
+    }
+
+    /// File the item at `index` and the items after it in the same section under `heading`.
+    /// From the first item of a section this renames the section, further down it splits
+    /// it. `None` merges them into the section above.
+    pub fn set_section(&mut self, index: usize, heading: Option<String>) {
+        let Some(item) = self.items.get(index) else {
+            return;
+        };
+        let old = item.section.clone();
+        let heading = heading.or_else(|| {
+            index
+                .checked_sub(1)
+                .and_then(|i| self.items[i].section.clone())
+        });
+        for item in self.items[index..].iter_mut() {
+            if item.section != old {
+                break;
+            }
+            item.section = heading.clone();
+        }
+    }
+
+    /// Put items without a section that follow a section into it, which is where they end
+    /// up once the markdown file is read back.
+    pub fn normalize_sections(&mut self) {
+        let mut current: Option<String> = None;
+        for item in &mut self.items {
+            match item.section {
+                Some(ref section) => current = Some(section.clone()),
+                None => item.section = current.clone(),
+            }
+        }
+    }
+
     #[cfg(test)]
     pub fn is_empty(&self) -> bool {
         self.items.is_empty()
@@ -147,6 +445,15 @@ impl TodoList {
     }
 }
 
+/// Reset a finished recurring item into a fresh instance.
+fn renew(item: &mut TodoItem) {
+    item.state = TodoState::Empty;
+    item.completed_at = None;
+    item.created_at = chrono::Utc::now();
+    item.modified_at = item.created_at;
+    item.carry_count = 0;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -202,6 +509,149 @@ mod tests {
         assert_eq!(incomplete[1].content, "Task 3");
     }
 
+    #[test]
+    fn test_get_rollover_items_renews_completed_recurring() {
+        // 2026-01-05 is a Monday
+        let monday = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+        let mut list = TodoList::new(monday.pred_opt().unwrap(), PathBuf::new());
+        list.add_item("Standup @every(weekday)".to_string());
+        list.add_item("Weekly report @every(fri)".to_string());
+        list.add_item("One-off".to_string());
+        for item in &mut list.items {
+            item.toggle_state();
+        }
+
+        let items = list.get_rollover_items(monday);
+        let due: Vec<&TodoItem> = items.iter().filter(|item| !item.starts_after(monday)).collect();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].content, "Standup");
+        assert_eq!(due[0].state, TodoState::Empty);
+        assert!(due[0].completed_at.is_none());
+        assert_eq!(items[1].content, "Weekly report");
+        assert_eq!(items[1].start_date, NaiveDate::from_ymd_opt(2026, 1, 9));
+    }
+
+    #[test]
+    fn test_get_rollover_items_keeps_recurring_series_across_skipped_days() {
+        // 2026-01-05 is a Monday
+        let day = |d: u32| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+        let mut list = TodoList::new(day(5), PathBuf::new());
+        list.add_item("Weekly report @every(fri)".to_string());
+        list.add_item("Pay rent @every(15th)".to_string());
+        for item in &mut list.items {
+            item.toggle_state();
+        }
+
+        // Neither rule matches Tuesday, so both wait for their next day
+        let items = list.get_rollover_items(day(6));
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|item| item.state == TodoState::Empty));
+        assert_eq!(items[0].start_date, Some(day(9)));
+        assert_eq!(items[1].start_date, Some(day(15)));
+
+        // Not run again until the next Monday: the Friday was skipped, so the report is due
+        let items = list.get_rollover_items(day(12));
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].content, "Weekly report");
+        assert_eq!(items[0].start_date, None);
+        assert_eq!(items[1].start_date, Some(day(15)));
+
+        // A month later both have come round
+        let items = list.get_rollover_items(NaiveDate::from_ymd_opt(2026, 2, 3).unwrap());
+        assert!(items.iter().all(|item| item.start_date.is_none()));
+        assert_eq!(items.len(), 2);
+    }
+
+    #[test]
+    fn test_get_rollover_items_counts_days_carried() {
+        let mut list = create_test_list();
+        list.add_item("Write migration".to_string());
+        list.items[0].carry_count = 3;
+
+        let target = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        let items = list.get_rollover_items(target);
+        assert_eq!(items[0].carry_count, 5);
+    }
+
+    #[test]
+    fn test_get_rollover_items_leaves_context_carry_count() {
+        let mut list = create_test_list();
+        list.add_item_with_indent("Release".to_string(), 0);
+        list.add_item_with_indent("Tag the build".to_string(), 1);
+        list.recalculate_parent_ids();
+        list.items[0].state = TodoState::Checked;
+
+        let target = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        let items = list.get_rollover_items(target);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].carry_count, 0);
+        assert_eq!(items[1].carry_count, 1);
+    }
+
+    #[test]
+    fn test_take_and_merge_scheduled() {
+        let mut list = create_test_list();
+        list.add_item("Today".to_string());
+        list.add_item_with_indent("Later".to_string(), 0);
+        list.add_item_with_indent("Later child".to_string(), 1);
+        list.add_item_with_indent("Also today".to_string(), 0);
+        list.recalculate_parent_ids();
+        list.items[1].start_date = list.date.succ_opt();
+
+        let scheduled = list.take_scheduled();
+        assert_eq!(scheduled.len(), 2);
+        assert_eq!(scheduled[0].content, "Later");
+        assert_eq!(scheduled[0].parent_id, None);
+        assert_eq!(scheduled[1].parent_id, Some(scheduled[0].id));
+        assert_eq!(list.items.len(), 2);
+        assert!(list.take_scheduled().is_empty());
+
+        let mut next_day = TodoList::new(list.date.succ_opt().unwrap(), PathBuf::new());
+        next_day.merge_scheduled(scheduled);
+        assert_eq!(next_day.items.len(), 2);
+        assert_eq!(next_day.items[0].start_date, None);
+    }
+
+    #[test]
+    fn test_take_subtree() {
+        let mut list = create_test_list();
+        list.add_item_with_indent("Project".to_string(), 0);
+        list.add_item_with_indent("Feature".to_string(), 1);
+        list.add_item_with_indent("Step".to_string(), 2);
+        list.add_item_with_indent("Other".to_string(), 1);
+        list.recalculate_parent_ids();
+
+        let taken = list.take_subtree(1);
+        assert_eq!(taken.len(), 2);
+        assert_eq!(taken[0].indent_level, 0);
+        assert_eq!(taken[0].parent_id, None);
+        assert_eq!(taken[1].indent_level, 1);
+        assert_eq!(list.items.len(), 2);
+        assert_eq!(list.items[1].parent_id, Some(list.items[0].id));
+        assert!(list.take_subtree(5).is_empty());
+    }
+
+    #[test]
+    fn test_is_blocked() {
+        let mut list = create_test_list();
+        list.add_item("Write migration".to_string());
+        list.add_item("Deploy".to_string());
+        list.add_item("Announce".to_string());
+        let blocker_origin = list.items[0].origin();
+        let external = Uuid::new_v4();
+        list.items[1].blocked_by = vec![blocker_origin];
+        list.items[2].blocked_by = vec![external];
+
+        let open_external = HashSet::from([external]);
+        assert!(list.is_blocked(1, &open_external));
+        assert!(list.is_blocked(2, &open_external));
+        assert_eq!(list.external_blocker_ids(), vec![external]);
+
+        list.items[0].toggle_state();
+        assert!(!list.is_blocked(1, &open_external));
+        assert!(!list.is_blocked(2, &HashSet::new()));
+    }
+
     #[test]
     fn test_get_incomplete_items_includes_complete_parent_with_incomplete_child() {
         let mut list = create_test_list();
@@ -312,4 +762,72 @@ mod tests {
         list.add_item("Task 2".to_string());
         assert_eq!(list.len(), 2);
     }
+
+    #[test]
+    fn test_tag_filter_keeps_hierarchy_context() {
+        let mut list = create_test_list();
+        list.add_item_with_indent("Parent".to_string(), 0);
+        list.add_item_with_indent("Tagged child".to_string(), 1);
+        list.add_item_with_indent("Grandchild".to_string(), 2);
+        list.add_item_with_indent("Other".to_string(), 0);
+        list.items[1].tags = vec!["review".to_string()];
+
+        let hidden = list.build_tag_hidden_indices("review");
+        assert!(!hidden.contains(&0));
+        assert!(!hidden.contains(&1));
+        assert!(!hidden.contains(&2));
+        assert!(hidden.contains(&3));
+    }
+
+    #[test]
+    fn test_all_tags() {
+        let mut list = create_test_list();
+        list.add_item("A".to_string());
+        list.add_item("B".to_string());
+        list.items[0].tags = vec!["work".to_string(), "review".to_string()];
+        list.items[1].tags = vec!["Work".to_string(), "home".to_string()];
+
+        assert_eq!(list.all_tags(), vec!["work", "review", "home"]);
+    }
+
+    #[test]
+    fn test_set_section_renames_splits_and_removes() {
+        let mut list = create_test_list();
+        for content in ["A", "B", "C", "D"] {
+            list.add_item(content.to_string());
+        }
+        let sections = |list: &TodoList| -> Vec<Option<String>> {
+            list.items.iter().map(|item| item.section.clone()).collect()
+        };
+        let work = Some("Work".to_string());
+        let home = Some("Home".to_string());
+
+        list.set_section(0, work.clone());
+        assert_eq!(sections(&list), vec![work.clone(); 4]);
+        list.set_section(2, home.clone());
+        assert_eq!(sections(&list), vec![work.clone(), work.clone(), home.clone(), home.clone()]);
+
+        let hidden = HashSet::new();
+        assert_eq!(list.section_heading(0, &hidden), Some("Work"));
+        assert_eq!(list.section_heading(1, &hidden), None);
+        assert_eq!(list.section_heading(2, &hidden), Some("Home"));
+
+        // Removing a heading merges its items into the section above
+        list.set_section(2, None);
+        assert_eq!(sections(&list), vec![work.clone(); 4]);
+        list.set_section(0, None);
+        assert_eq!(sections(&list), vec![None; 4]);
+    }
+
+    #[test]
+    fn test_normalize_sections() {
+        let mut list = create_test_list();
+        for content in ["A", "B", "C"] {
+            list.add_item(content.to_string());
+        }
+        list.items[1].section = Some("Later".to_string());
+        list.normalize_sections();
+        assert_eq!(list.items[0].section, None);
+        assert_eq!(list.items[2].section.as_deref(), Some("Later"));
+    }
 }
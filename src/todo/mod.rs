@@ -1,8 +1,11 @@
 pub mod hierarchy;
 pub mod item;
 pub mod list;
+pub mod rebase;
+pub mod recurrence;
 pub mod state;
 
-pub use item::TodoItem;
+pub use item::{TodoItem, normalize_tags, split_start_date, split_tags};
 pub use list::TodoList;
-pub use state::TodoState;
+pub use recurrence::{Recurrence, split_recurrence};
+pub use state::{StateRegistry, StatesConfig, TodoState};
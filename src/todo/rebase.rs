@@ -0,0 +1,188 @@
+use super::TodoItem;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Replay the edits in `mine`, made on top of `base`, onto `theirs`, a newer copy of the same
+/// list saved by another writer.
+///
+/// Edits combine when they touch different items. Returns `None` when both sides changed the
+/// same item differently, when one side removed an item the other side changed or nested
+/// something under, or when both sides reordered the list differently.
+pub fn rebase(base: &[TodoItem], mine: &[TodoItem], theirs: &[TodoItem]) -> Option<Vec<TodoItem>> {
+    let base_by_id = by_id(base);
+    let mine_by_id = by_id(mine);
+    let theirs_by_id = by_id(theirs);
+
+    let changed = |side: &HashMap<Uuid, &TodoItem>, id: &Uuid| base_by_id.get(id) != side.get(id);
+
+    let all_ids: HashSet<Uuid> = base_by_id
+        .keys()
+        .chain(mine_by_id.keys())
+        .chain(theirs_by_id.keys())
+        .copied()
+        .collect();
+    for id in &all_ids {
+        if changed(&mine_by_id, id)
+            && changed(&theirs_by_id, id)
+            && mine_by_id.get(id) != theirs_by_id.get(id)
+        {
+            return None;
+        }
+    }
+
+    // An item removed on one side must not have gained subtasks or edited subtasks on the other
+    let removes_edited_parent = |removed_by: &HashMap<Uuid, &TodoItem>, other: &HashMap<Uuid, &TodoItem>| {
+        other.iter().any(|(id, item)| {
+            changed(other, id)
+                && item
+                    .parent_id
+                    .is_some_and(|parent| base_by_id.contains_key(&parent) && !removed_by.contains_key(&parent))
+        })
+    };
+    if removes_edited_parent(&mine_by_id, &theirs_by_id) || removes_edited_parent(&theirs_by_id, &mine_by_id) {
+        return None;
+    }
+
+    let mine_reordered = shared_order(base, &mine_by_id) != shared_order(mine, &base_by_id);
+    let theirs_reordered = shared_order(base, &theirs_by_id) != shared_order(theirs, &base_by_id);
+    if mine_reordered && theirs_reordered {
+        let in_all = |id: &Uuid| {
+            base_by_id.contains_key(id) && mine_by_id.contains_key(id) && theirs_by_id.contains_key(id)
+        };
+        let mine_order: Vec<Uuid> = mine.iter().map(|item| item.id).filter(in_all).collect();
+        let theirs_order: Vec<Uuid> = theirs.iter().map(|item| item.id).filter(in_all).collect();
+        if mine_order != theirs_order {
+            return None;
+        }
+    }
+
+    // Keep the order of whichever side moved items and replay the other side's edits on it
+    let (primary, primary_by_id, secondary, secondary_by_id) = if mine_reordered {
+        (mine, &mine_by_id, theirs, &theirs_by_id)
+    } else {
+        (theirs, &theirs_by_id, mine, &mine_by_id)
+    };
+
+    let mut merged: Vec<TodoItem> = primary
+        .iter()
+        .filter(|item| !base_by_id.contains_key(&item.id) || secondary_by_id.contains_key(&item.id))
+        .map(|item| match secondary_by_id.get(&item.id) {
+            Some(edited) if changed(secondary_by_id, &item.id) => (*edited).clone(),
+            _ => item.clone(),
+        })
+        .collect();
+
+    // Items added by the other side go right after the item they followed there
+    for (i, item) in secondary.iter().enumerate() {
+        if base_by_id.contains_key(&item.id) || primary_by_id.contains_key(&item.id) {
+            continue;
+        }
+        let position = secondary[..i]
+            .iter()
+            .rev()
+            .find_map(|prev| merged.iter().position(|m| m.id == prev.id))
+            .map(|p| p + 1)
+            .unwrap_or(0);
+        merged.insert(position, item.clone());
+    }
+
+    Some(merged)
+}
+
+fn by_id(items: &[TodoItem]) -> HashMap<Uuid, &TodoItem> {
+    items.iter().map(|item| (item.id, item)).collect()
+}
+
+/// Ids of `items` that also appear in `other`, in the order of `items`.
+fn shared_order(items: &[TodoItem], other: &HashMap<Uuid, &TodoItem>) -> Vec<Uuid> {
+    items
+        .iter()
+        .map(|item| item.id)
+        .filter(|id| other.contains_key(id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todo::TodoState;
+
+    fn items(contents: &[&str]) -> Vec<TodoItem> {
+        contents
+            .iter()
+            .map(|c| TodoItem::new(c.to_string(), 0))
+            .collect()
+    }
+
+    fn contents(items: &[TodoItem]) -> Vec<&str> {
+        items.iter().map(|item| item.content.as_str()).collect()
+    }
+
+    #[test]
+    fn test_rebase_combines_edits_to_different_items() {
+        let base = items(&["A", "B", "C"]);
+
+        let mut mine = base.clone();
+        mine[0].state = TodoState::Checked;
+        mine.insert(2, TodoItem::new("Mine".to_string(), 0));
+
+        let mut theirs = base.clone();
+        theirs[2].content = "C edited".to_string();
+        theirs.remove(1);
+        theirs.push(TodoItem::new("Theirs".to_string(), 0));
+
+        let merged = rebase(&base, &mine, &theirs).unwrap();
+        assert_eq!(contents(&merged), vec!["A", "Mine", "C edited", "Theirs"]);
+        assert_eq!(merged[0].state, TodoState::Checked);
+    }
+
+    #[test]
+    fn test_rebase_refuses_overlapping_edits() {
+        let base = items(&["A", "B"]);
+
+        let mut mine = base.clone();
+        mine[1].content = "B mine".to_string();
+        let mut theirs = base.clone();
+        theirs[1].content = "B theirs".to_string();
+        assert!(rebase(&base, &mine, &theirs).is_none());
+
+        // Removing an item the other side edited is also a conflict
+        let mut theirs = base.clone();
+        theirs.remove(1);
+        assert!(rebase(&base, &mine, &theirs).is_none());
+
+        // The same edit on both sides is not
+        assert!(rebase(&base, &mine, &mine).is_some());
+    }
+
+    #[test]
+    fn test_rebase_keeps_local_reorder() {
+        let base = items(&["A", "B", "C"]);
+
+        let mut mine = base.clone();
+        mine.swap(0, 2);
+        let mut theirs = base.clone();
+        theirs[1].state = TodoState::Checked;
+
+        let merged = rebase(&base, &mine, &theirs).unwrap();
+        assert_eq!(contents(&merged), vec!["C", "B", "A"]);
+        assert_eq!(merged[1].state, TodoState::Checked);
+
+        let mut theirs = base.clone();
+        theirs.swap(0, 1);
+        assert!(rebase(&base, &mine, &theirs).is_none());
+    }
+
+    #[test]
+    fn test_rebase_refuses_subtask_under_removed_parent() {
+        let base = items(&["Parent", "Other"]);
+
+        let mut mine = base.clone();
+        let mut child = TodoItem::new("Child".to_string(), 1);
+        child.parent_id = Some(base[0].id);
+        mine.insert(1, child);
+
+        let theirs = vec![base[1].clone()];
+        assert!(rebase(&base, &mine, &theirs).is_none());
+    }
+}
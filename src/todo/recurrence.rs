@@ -0,0 +1,248 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use std::fmt;
+
+/// Day-of-month value used for `@every(last)`; clamped to the month's last day.
+const LAST_DAY: u32 = 31;
+
+/// Recurrence rule attached to a todo with `@every(...)`.
+///
+/// Supported forms: `day`, `weekday`, `weekend`, a list of weekdays such as
+/// `mon,thu`, or a list of days of the month such as `1st,15th` or `last`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recurrence {
+    Daily,
+    Weekdays,
+    Weekends,
+    Weekly(Vec<Weekday>),
+    Monthly(Vec<u32>),
+}
+
+impl Recurrence {
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim().to_lowercase();
+        match spec.as_str() {
+            "day" | "daily" => return Some(Self::Daily),
+            "weekday" | "weekdays" => return Some(Self::Weekdays),
+            "weekend" | "weekends" => return Some(Self::Weekends),
+            _ => {}
+        }
+
+        let tokens: Vec<&str> = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .collect();
+        if tokens.is_empty() {
+            return None;
+        }
+
+        if let Some(mut days) = tokens
+            .iter()
+            .map(|t| parse_weekday(t))
+            .collect::<Option<Vec<_>>>()
+        {
+            days.sort_by_key(|d| d.num_days_from_monday());
+            days.dedup();
+            return Some(Self::Weekly(days));
+        }
+
+        if let Some(mut days) = tokens
+            .iter()
+            .map(|t| parse_month_day(t))
+            .collect::<Option<Vec<_>>>()
+        {
+            days.sort_unstable();
+            days.dedup();
+            return Some(Self::Monthly(days));
+        }
+
+        None
+    }
+
+    /// Whether a fresh instance is due on `date`.
+    pub fn matches(&self, date: NaiveDate) -> bool {
+        let weekday = date.weekday();
+        match self {
+            Self::Daily => true,
+            Self::Weekdays => !matches!(weekday, Weekday::Sat | Weekday::Sun),
+            Self::Weekends => matches!(weekday, Weekday::Sat | Weekday::Sun),
+            Self::Weekly(days) => days.contains(&weekday),
+            Self::Monthly(days) => {
+                let last = last_day_of_month(date);
+                days.iter().any(|&d| d.min(last) == date.day())
+            }
+        }
+    }
+
+    /// The first day after `date` on which a fresh instance is due.
+    pub fn next_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        // Every rule matches at least once a month, so a year is plenty.
+        date.iter_days().skip(1).take(366).find(|&day| self.matches(day))
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Daily => write!(f, "day"),
+            Self::Weekdays => write!(f, "weekday"),
+            Self::Weekends => write!(f, "weekend"),
+            Self::Weekly(days) => {
+                let names: Vec<String> = days
+                    .iter()
+                    .map(|d| d.to_string().to_lowercase())
+                    .collect();
+                write!(f, "{}", names.join(","))
+            }
+            Self::Monthly(days) => {
+                let names: Vec<String> = days.iter().map(|&d| format_month_day(d)).collect();
+                write!(f, "{}", names.join(","))
+            }
+        }
+    }
+}
+
+fn parse_weekday(token: &str) -> Option<Weekday> {
+    match token {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_month_day(token: &str) -> Option<u32> {
+    if token == "last" {
+        return Some(LAST_DAY);
+    }
+    let digits = token
+        .strip_suffix("st")
+        .or_else(|| token.strip_suffix("nd"))
+        .or_else(|| token.strip_suffix("rd"))
+        .or_else(|| token.strip_suffix("th"))
+        .unwrap_or(token);
+    digits.parse().ok().filter(|d| (1..=LAST_DAY).contains(d))
+}
+
+fn format_month_day(day: u32) -> String {
+    if day == LAST_DAY {
+        return "last".to_string();
+    }
+    let suffix = match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{day}{suffix}")
+}
+
+fn last_day_of_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(LAST_DAY)
+}
+
+/// Split an `@every(...)` annotation out of `content`.
+/// An unrecognised rule is left in the content so it stays visible to the user.
+pub fn split_recurrence(content: &str) -> (String, Option<Recurrence>) {
+    if let Some(start) = content.find("@every(")
+        && let Some(end) = content[start..].find(')')
+        && let Some(recurrence) = Recurrence::parse(&content[start + 7..start + end])
+    {
+        let mut cleaned = String::new();
+        cleaned.push_str(content[..start].trim());
+        let suffix = content[start + end + 1..].trim();
+        if !suffix.is_empty() {
+            if !cleaned.is_empty() {
+                cleaned.push(' ');
+            }
+            cleaned.push_str(suffix);
+        }
+        return (cleaned, Some(recurrence));
+    }
+    (content.to_string(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(Recurrence::parse("weekday"), Some(Recurrence::Weekdays));
+        assert_eq!(
+            Recurrence::parse("thu, Mon"),
+            Some(Recurrence::Weekly(vec![Weekday::Mon, Weekday::Thu]))
+        );
+        assert_eq!(
+            Recurrence::parse("15th,1st"),
+            Some(Recurrence::Monthly(vec![1, 15]))
+        );
+        assert_eq!(Recurrence::parse("mon,1st"), None);
+        assert_eq!(Recurrence::parse("32nd"), None);
+
+        for spec in ["day", "weekday", "weekend", "mon,thu", "1st,22nd,last"] {
+            assert_eq!(Recurrence::parse(spec).unwrap().to_string(), spec);
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        // 2026-01-05 is a Monday
+        assert!(Recurrence::Weekdays.matches(date(2026, 1, 5)));
+        assert!(!Recurrence::Weekdays.matches(date(2026, 1, 3)));
+        assert!(Recurrence::Weekends.matches(date(2026, 1, 4)));
+
+        let mon_thu = Recurrence::parse("mon,thu").unwrap();
+        assert!(mon_thu.matches(date(2026, 1, 8)));
+        assert!(!mon_thu.matches(date(2026, 1, 7)));
+
+        let first = Recurrence::parse("1st").unwrap();
+        assert!(first.matches(date(2026, 2, 1)));
+        assert!(!first.matches(date(2026, 2, 2)));
+
+        let last = Recurrence::parse("last").unwrap();
+        assert!(last.matches(date(2026, 2, 28)));
+        assert!(last.matches(date(2028, 2, 29)));
+        assert!(!last.matches(date(2028, 2, 28)));
+    }
+
+    #[test]
+    fn test_next_after() {
+        // 2026-01-09 is a Friday
+        let fri = Recurrence::parse("fri").unwrap();
+        assert_eq!(fri.next_after(date(2026, 1, 9)), Some(date(2026, 1, 16)));
+        assert_eq!(fri.next_after(date(2026, 1, 5)), Some(date(2026, 1, 9)));
+
+        let fifteenth = Recurrence::parse("15th").unwrap();
+        assert_eq!(fifteenth.next_after(date(2026, 1, 15)), Some(date(2026, 2, 15)));
+        assert_eq!(Recurrence::Daily.next_after(date(2026, 1, 31)), Some(date(2026, 2, 1)));
+    }
+
+    #[test]
+    fn test_split_recurrence() {
+        let (content, rule) = split_recurrence("Standup @every(weekday) notes");
+        assert_eq!(content, "Standup notes");
+        assert_eq!(rule, Some(Recurrence::Weekdays));
+
+        let (content, rule) = split_recurrence("Odd @every(fortnight)");
+        assert_eq!(content, "Odd @every(fortnight)");
+        assert_eq!(rule, None);
+    }
+}
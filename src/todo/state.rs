@@ -1,12 +1,16 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::sync::OnceLock;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TodoState {
-    Empty,       // [ ]
-    Checked,     // [x]
-    Question,    // [?]
-    Exclamation, // [!]
-    InProgress,  // [*]
+    Empty,        // [ ]
+    Checked,      // [x]
+    Question,     // [?]
+    Exclamation,  // [!]
+    InProgress,   // [*]
+    Custom(char), // user-defined in the `[states]` config section
 }
 
 impl TodoState {
@@ -17,6 +21,7 @@ impl TodoState {
             Self::Question => '?',
             Self::Exclamation => '!',
             Self::InProgress => '*',
+            Self::Custom(c) => c,
         }
     }
 
@@ -27,18 +32,14 @@ impl TodoState {
             '?' => Some(Self::Question),
             '!' => Some(Self::Exclamation),
             '*' => Some(Self::InProgress),
-            _ => None,
+            _ => StateRegistry::global()
+                .get(c)
+                .map(|_| Self::Custom(c)),
         }
     }
 
     pub fn cycle(&self) -> Self {
-        match self {
-            Self::Empty => Self::Checked,
-            Self::Checked => Self::InProgress,
-            Self::InProgress => Self::Question,
-            Self::Question => Self::Exclamation,
-            Self::Exclamation => Self::Empty,
-        }
+        StateRegistry::global().next_in_cycle(*self)
     }
 
     pub fn toggle(&self) -> Self {
@@ -49,22 +50,35 @@ impl TodoState {
     }
 
     pub fn is_complete(&self) -> bool {
-        matches!(self, Self::Checked)
+        StateRegistry::global().definition(*self).complete
+    }
+
+    /// Whether an item in this state is carried over to the next day.
+    pub fn rolls_over(&self) -> bool {
+        StateRegistry::global().definition(*self).rolls_over()
+    }
+
+    /// Human-readable name, e.g. "pending", "done" or a configured label such as "cancelled".
+    pub fn label(&self) -> String {
+        StateRegistry::global().definition(*self).label
     }
 
     /// Parse a state from a string representation.
-    /// Accepts: " " or "" for Empty, "x"/"X" for Checked, "?" for Question, "!" for Exclamation, "*" for InProgress
+    /// Accepts: " " or "" for Empty, "x"/"X" for Checked, "?" for Question, "!" for Exclamation,
+    /// "*" for InProgress, any configured custom marker, or a state label such as "done".
     pub fn parse(s: &str) -> Option<Self> {
-        match s.trim() {
-            " " | "" => Some(Self::Empty),
-            "x" | "X" => Some(Self::Checked),
-            "?" => Some(Self::Question),
-            "!" => Some(Self::Exclamation),
-            "*" => Some(Self::InProgress),
-            _ => None,
+        let trimmed = s.trim();
+        let mut chars = trimmed.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => Some(Self::Empty),
+            (Some(c), None) => Self::from_char(c),
+            _ => StateRegistry::global()
+                .definitions()
+                .iter()
+                .find(|d| d.label.eq_ignore_ascii_case(trimmed))
+                .and_then(|d| Self::from_char(d.marker)),
         }
     }
-
 }
 
 impl fmt::Display for TodoState {
@@ -73,6 +87,201 @@ impl fmt::Display for TodoState {
     }
 }
 
+/// A custom state as written in the `[states]` config section.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StateConfig {
+    pub marker: char,
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub complete: bool,
+    /// Defaults to the opposite of `complete`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollover: Option<bool>,
+}
+
+/// The `[states]` config section: extra states and the order `cycle_state` walks through.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct StatesConfig {
+    /// Markers in cycle order, e.g. `[" ", "x", "*", "-"]`. Defaults to the built-in order
+    /// followed by any custom states.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cycle: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom: Vec<StateConfig>,
+    /// Finish a parent when its last open subtask is finished, and reopen it when a
+    /// subtask is reopened.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_complete_parents: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateDefinition {
+    pub marker: char,
+    pub label: String,
+    pub color: Option<String>,
+    pub complete: bool,
+    pub rollover: Option<bool>,
+}
+
+impl StateDefinition {
+    fn builtin(marker: char, label: &str, complete: bool) -> Self {
+        Self {
+            marker,
+            label: label.to_string(),
+            color: None,
+            complete,
+            rollover: None,
+        }
+    }
+
+    pub fn rolls_over(&self) -> bool {
+        self.rollover.unwrap_or(!self.complete)
+    }
+}
+
+/// Every known state (built-in and configured) plus the cycle order.
+#[derive(Debug, Clone)]
+pub struct StateRegistry {
+    definitions: Vec<StateDefinition>,
+    cycle: Vec<TodoState>,
+    auto_complete_parents: bool,
+}
+
+static REGISTRY: OnceLock<StateRegistry> = OnceLock::new();
+
+impl Default for StateRegistry {
+    fn default() -> Self {
+        Self {
+            definitions: vec![
+                StateDefinition::builtin(' ', "pending", false),
+                StateDefinition::builtin('x', "done", true),
+                StateDefinition::builtin('*', "in_progress", false),
+                StateDefinition::builtin('?', "question", false),
+                StateDefinition::builtin('!', "important", false),
+            ],
+            cycle: vec![
+                TodoState::Empty,
+                TodoState::Checked,
+                TodoState::InProgress,
+                TodoState::Question,
+                TodoState::Exclamation,
+            ],
+            auto_complete_parents: false,
+        }
+    }
+}
+
+impl StateRegistry {
+    pub fn from_config(config: &StatesConfig) -> Result<Self> {
+        let mut registry = Self::default();
+
+        for custom in &config.custom {
+            if matches!(custom.marker, '[' | ']' | 'X') || custom.marker.is_control() {
+                return Err(anyhow!("Invalid state marker '{}'", custom.marker));
+            }
+            let definition = StateDefinition {
+                marker: custom.marker,
+                label: custom.label.clone(),
+                color: custom.color.clone(),
+                complete: custom.complete,
+                rollover: custom.rollover,
+            };
+            // A custom entry may also restyle or relabel a built-in state
+            match registry
+                .definitions
+                .iter_mut()
+                .find(|d| d.marker == custom.marker)
+            {
+                Some(existing) => *existing = definition,
+                None => {
+                    registry.definitions.push(definition);
+                    registry.cycle.push(TodoState::Custom(custom.marker));
+                }
+            }
+        }
+
+        if let Some(ref order) = config.cycle {
+            let mut cycle = Vec::new();
+            for marker in order {
+                let mut chars = marker.chars();
+                let (Some(c), None) = (chars.next(), chars.next()) else {
+                    return Err(anyhow!("Cycle entries must be single characters, got '{marker}'"));
+                };
+                let state = registry
+                    .state_for(c)
+                    .ok_or_else(|| anyhow!("Unknown state '{c}' in cycle order"))?;
+                cycle.push(state);
+            }
+            if cycle.is_empty() {
+                return Err(anyhow!("Cycle order cannot be empty"));
+            }
+            registry.cycle = cycle;
+        }
+
+        registry.auto_complete_parents = config.auto_complete_parents;
+
+        Ok(registry)
+    }
+
+    /// Make this registry the one used by `TodoState`. Only the first call takes effect.
+    pub fn install(self) {
+        let _ = REGISTRY.set(self);
+    }
+
+    pub fn global() -> &'static StateRegistry {
+        REGISTRY.get_or_init(StateRegistry::default)
+    }
+
+    pub fn definitions(&self) -> &[StateDefinition] {
+        &self.definitions
+    }
+
+    /// Valid states for error messages, e.g. "' ' (pending), 'x' (done), '-' (cancelled)".
+    pub fn describe(&self) -> String {
+        self.definitions
+            .iter()
+            .map(|d| format!("'{}' ({})", d.marker, d.label))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn get(&self, marker: char) -> Option<&StateDefinition> {
+        self.definitions.iter().find(|d| d.marker == marker)
+    }
+
+    /// Definition for `state`; unknown custom markers (e.g. from a removed config entry)
+    /// behave like an open item so they keep rolling over.
+    pub fn definition(&self, state: TodoState) -> StateDefinition {
+        self.get(state.to_char()).cloned().unwrap_or_else(|| {
+            StateDefinition::builtin(state.to_char(), "custom", false)
+        })
+    }
+
+    pub fn auto_complete_parents(&self) -> bool {
+        self.auto_complete_parents
+    }
+
+    pub fn next_in_cycle(&self, state: TodoState) -> TodoState {
+        match self.cycle.iter().position(|s| *s == state) {
+            Some(i) => self.cycle[(i + 1) % self.cycle.len()],
+            None => self.cycle[0],
+        }
+    }
+
+    fn state_for(&self, c: char) -> Option<TodoState> {
+        match c {
+            ' ' => Some(TodoState::Empty),
+            'x' => Some(TodoState::Checked),
+            '?' => Some(TodoState::Question),
+            '!' => Some(TodoState::Exclamation),
+            '*' => Some(TodoState::InProgress),
+            _ => self.get(c).map(|_| TodoState::Custom(c)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,6 +324,58 @@ mod tests {
         assert!(!TodoState::InProgress.is_complete());
     }
 
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(TodoState::parse("done"), Some(TodoState::Checked));
+        assert_eq!(TodoState::parse("In_Progress"), Some(TodoState::InProgress));
+        assert_eq!(TodoState::parse("nope"), None);
+    }
+
+    #[test]
+    fn test_registry_from_config() {
+        let config: StatesConfig = toml::from_str(
+            r#"
+            cycle = [" ", "x", "-", ">"]
+
+            [[custom]]
+            marker = "-"
+            label = "cancelled"
+            color = "darkgray"
+            complete = true
+
+            [[custom]]
+            marker = ">"
+            label = "delegated"
+            rollover = false
+            "#,
+        )
+        .unwrap();
+        let registry = StateRegistry::from_config(&config).unwrap();
+
+        let cancelled = registry.definition(TodoState::Custom('-'));
+        assert_eq!(cancelled.label, "cancelled");
+        assert!(cancelled.complete);
+        assert!(!cancelled.rolls_over());
+
+        let delegated = registry.definition(TodoState::Custom('>'));
+        assert!(!delegated.complete);
+        assert!(!delegated.rolls_over());
+
+        assert_eq!(registry.next_in_cycle(TodoState::Checked), TodoState::Custom('-'));
+        assert_eq!(registry.next_in_cycle(TodoState::Custom('>')), TodoState::Empty);
+        assert_eq!(registry.next_in_cycle(TodoState::Question), TodoState::Empty);
+    }
+
+    #[test]
+    fn test_registry_rejects_unknown_cycle_state() {
+        let config = StatesConfig {
+            cycle: Some(vec![" ".to_string(), "~".to_string()]),
+            custom: Vec::new(),
+            auto_complete_parents: false,
+        };
+        assert!(StateRegistry::from_config(&config).is_err());
+    }
+
     #[test]
     fn test_display() {
         assert_eq!(format!("{}", TodoState::Empty), "[ ]");
@@ -44,6 +44,22 @@ pub fn render(f: &mut Frame, state: &mut AppState) {
     if state.mode == Mode::Rollover {
         render_rollover_overlay(f, state);
     }
+
+    if state.mode == Mode::Scheduled {
+        render_scheduled_overlay(f, state);
+    }
+
+    if state.mode == Mode::Detail {
+        render_detail_overlay(f, state);
+    }
+
+    if state.mode == Mode::ListPicker {
+        render_list_picker_overlay(f, state);
+    }
+
+    if state.mode == Mode::Trash {
+        render_trash_overlay(f, state);
+    }
 }
 
 fn render_help_overlay(f: &mut Frame, state: &AppState) {
@@ -52,13 +68,13 @@ fn render_help_overlay(f: &mut Frame, state: &AppState) {
     let section_style = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
     let dim_style = Style::default().fg(Color::DarkGray);
 
-    let mut lines: Vec<Line> = vec![];
-
-    // Title
-    lines.push(Line::from(vec![
-        Span::styled("  TO-TUI Help", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
-    ]));
-    lines.push(Line::from(""));
+    let mut lines: Vec<Line> = vec![
+        // Title
+        Line::from(vec![
+            Span::styled("  TO-TUI Help", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(""),
+    ];
 
     // Navigation section
     lines.push(Line::from(Span::styled("  ── Navigation ──", section_style)));
@@ -126,6 +142,10 @@ fn render_help_overlay(f: &mut Frame, state: &AppState) {
         Span::styled("    u               ", key_style),
         Span::styled("Undo last action", desc_style),
     ]));
+    lines.push(Line::from(vec![
+        Span::styled("    H               ", key_style),
+        Span::styled("Add or rename the section heading above", desc_style),
+    ]));
     lines.push(Line::from(""));
 
     // Indentation section
@@ -198,6 +218,22 @@ fn render_help_overlay(f: &mut Frame, state: &AppState) {
         Span::styled("    R               ", key_style),
         Span::styled("Open rollover modal", desc_style),
     ]));
+    lines.push(Line::from(vec![
+        Span::styled("    S               ", key_style),
+        Span::styled("Show scheduled todos", desc_style),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled("    X               ", key_style),
+        Span::styled("Show deleted todos to restore or purge", desc_style),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled("    L               ", key_style),
+        Span::styled("Switch to today or a project list", desc_style),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled("    m               ", key_style),
+        Span::styled("Move item (with children) to another list", desc_style),
+    ]));
     lines.push(Line::from(""));
 
     // Other section
@@ -206,6 +242,14 @@ fn render_help_overlay(f: &mut Frame, state: &AppState) {
         Span::styled("    p               ", key_style),
         Span::styled("Open plugins menu", desc_style),
     ]));
+    lines.push(Line::from(vec![
+        Span::styled("    #               ", key_style),
+        Span::styled("Cycle tag filter", desc_style),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled("    K               ", key_style),
+        Span::styled("Show details, comments and history", desc_style),
+    ]));
     lines.push(Line::from(vec![
         Span::styled("    ?               ", key_style),
         Span::styled("Toggle this help", desc_style),
@@ -556,14 +600,16 @@ fn render_rollover_overlay(f: &mut Frame, state: &AppState) {
 
     let area = centered_rect(60, 50, f.area());
 
+    let today = Local::now().date_naive();
     let date_desc = format_date_description(pending.source_date);
     let title = format!(
         " Rollover {} incomplete item(s) from {} ",
-        pending.items.len(),
+        pending.items.iter().filter(|item| !item.starts_after(today)).count(),
         date_desc
     );
 
-    // Build list items from pending rollover
+    // Build list items from pending rollover; recurring items that are not due yet are
+    // shown with the day they will be scheduled for
     let list_items: Vec<ListItem> = pending
         .items
         .iter()
@@ -571,10 +617,14 @@ fn render_rollover_overlay(f: &mut Frame, state: &AppState) {
             let indent = "  ".repeat(item.indent_level);
             let state_char = item.state.to_char();
             let line = format!("{}[{}] {}", indent, state_char, item.content);
-            ListItem::new(Line::from(Span::styled(
-                line,
-                Style::default().fg(state.theme.foreground),
-            )))
+            let mut spans = vec![Span::styled(line, Style::default().fg(state.theme.foreground))];
+            if let Some(start) = item.start_date.filter(|&start| start > today) {
+                spans.push(Span::styled(
+                    format!("  (next on {})", start.format("%a %b %d")),
+                    Style::default().fg(state.theme.foreground).add_modifier(Modifier::DIM),
+                ));
+            }
+            ListItem::new(Line::from(spans))
         })
         .collect();
 
@@ -607,3 +657,253 @@ fn render_rollover_overlay(f: &mut Frame, state: &AppState) {
 
     f.render_widget(footer, footer_area);
 }
+
+fn render_scheduled_overlay(f: &mut Frame, state: &AppState) {
+    let area = centered_rect(60, 50, f.area());
+
+    let title = format!(" Scheduled ({}) - Esc to close ", state.scheduled_items.len());
+
+    let list_items: Vec<ListItem> = state
+        .scheduled_items
+        .iter()
+        .map(|item| {
+            let indent = "  ".repeat(item.indent_level);
+            // Subtasks wait with their parent, so only top-level items show a date
+            let start = match item.start_date {
+                Some(date) if item.indent_level == 0 => date.format("%a %b %d").to_string(),
+                _ => String::new(),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{start:<11} "),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(
+                    format!("{}[{}] {}", indent, item.state.to_char(), item.content_with_tags()),
+                    Style::default().fg(state.theme.foreground),
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(list_items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .style(Style::default().bg(state.theme.background)),
+        )
+        .style(Style::default().fg(state.theme.foreground));
+
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
+
+fn render_detail_overlay(f: &mut Frame, state: &AppState) {
+    let Some(item) = state.selected_item() else {
+        return;
+    };
+    let area = centered_rect(60, 60, f.area());
+
+    let label_style = Style::default().fg(Color::Cyan);
+    let dim_style = Style::default().fg(Color::DarkGray);
+    let text_style = Style::default().fg(state.theme.foreground);
+
+    let mut lines: Vec<Line> = vec![
+        Line::from(Span::styled(
+            format!("{} {}", item.state, item.content_with_tags()),
+            text_style.add_modifier(Modifier::BOLD),
+        )),
+    ];
+
+    let mut facts = Vec::new();
+    if let Some(due) = item.due_date {
+        facts.push(format!("due {}", due.format("%Y-%m-%d")));
+    }
+    if let Some(start) = item.start_date {
+        facts.push(format!("starts {}", start.format("%Y-%m-%d")));
+    }
+    if let Some(ref recurrence) = item.recurrence {
+        facts.push(format!("every {recurrence}"));
+    }
+    if !facts.is_empty() {
+        lines.push(Line::from(Span::styled(facts.join(" · "), label_style)));
+    }
+
+    if let Some(ref description) = item.description {
+        lines.push(Line::from(""));
+        lines.extend(description.lines().map(|l| Line::from(Span::styled(l.to_string(), text_style))));
+    }
+
+    lines.push(Line::from(""));
+    let footer = match state.detail_history {
+        Some(ref events) => {
+            lines.push(Line::from(Span::styled(
+                format!("── History ({}) ──", events.len()),
+                label_style.add_modifier(Modifier::BOLD),
+            )));
+            if events.is_empty() {
+                lines.push(Line::from(Span::styled("No changes recorded", dim_style)));
+            }
+            for event in events {
+                let when = event.occurred_at.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{when} "), dim_style),
+                    Span::styled(format!("{:<6} ", event.source), Style::default().fg(Color::Yellow)),
+                    Span::styled(event.describe(), text_style),
+                ]));
+            }
+            " h for comments, Esc to close "
+        }
+        None => {
+            lines.push(Line::from(Span::styled(
+                format!("── Comments ({}) ──", state.detail_comments.len()),
+                label_style.add_modifier(Modifier::BOLD),
+            )));
+            if state.detail_comments.is_empty() && state.comment_input.is_none() {
+                lines.push(Line::from(Span::styled("No comments yet", dim_style)));
+            }
+            for comment in &state.detail_comments {
+                let when = comment.created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+                lines.push(Line::from(vec![
+                    Span::styled(comment.author.clone(), Style::default().fg(Color::Yellow)),
+                    Span::styled(format!("  {when}"), dim_style),
+                ]));
+                lines.extend(comment.body.lines().map(|l| Line::from(Span::styled(format!("  {l}"), text_style))));
+            }
+
+            match state.comment_input {
+                Some(ref input) => {
+                    lines.push(Line::from(vec![
+                        Span::styled("> ", Style::default().fg(Color::Yellow)),
+                        Span::styled(format!("{input}▏"), text_style),
+                    ]));
+                    " Enter to post, Esc to cancel "
+                }
+                None => " a to comment, h for history, Esc to close ",
+            }
+        }
+    };
+
+    // Keep the newest comments or changes in view when they are longer than the overlay
+    let overflow = lines.len().saturating_sub(area.height.saturating_sub(2) as usize);
+
+    let paragraph = Paragraph::new(lines)
+        .scroll((overflow as u16, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Details ")
+                .title_bottom(footer)
+                .style(Style::default().bg(state.theme.background)),
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn render_list_picker_overlay(f: &mut Frame, state: &AppState) {
+    let Some(ref picker) = state.list_picker else {
+        return;
+    };
+    let area = centered_rect(40, 50, f.area());
+
+    let title = if picker.moving {
+        " Move to list "
+    } else {
+        " Switch list "
+    };
+    let footer = if picker.input.is_some() {
+        " Enter to create, Esc to cancel "
+    } else {
+        " Enter to pick, n new list, Esc to close "
+    };
+
+    let current = state.todo_list.name.as_deref();
+    let entries = std::iter::once(("Today".to_string(), current.is_none()))
+        .chain(
+            picker
+                .names
+                .iter()
+                .map(|name| (name.clone(), current == Some(name.as_str()))),
+        );
+
+    let mut list_items: Vec<ListItem> = entries
+        .enumerate()
+        .map(|(i, (label, is_current))| {
+            let marker = if is_current { "● " } else { "  " };
+            let style = if i == picker.selected && picker.input.is_none() {
+                Style::default().fg(state.theme.foreground).add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default().fg(state.theme.foreground)
+            };
+            ListItem::new(Line::from(Span::styled(format!("{marker}{label}"), style)))
+        })
+        .collect();
+
+    if let Some(ref input) = picker.input {
+        list_items.push(ListItem::new(Line::from(vec![
+            Span::styled("  New list: ", Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{input}▏"), Style::default().fg(state.theme.foreground)),
+        ])));
+    }
+
+    let list = List::new(list_items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_bottom(footer)
+            .style(Style::default().bg(state.theme.background)),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
+
+fn render_trash_overlay(f: &mut Frame, state: &AppState) {
+    let Some(ref view) = state.trash_view else {
+        return;
+    };
+    let area = centered_rect(70, 50, f.area());
+
+    let rows = view.rows();
+    let title = format!(" Trash ({}) ", rows.len());
+    let dim_style = Style::default().fg(Color::DarkGray);
+
+    let list_items: Vec<ListItem> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, (entry, subtasks))| {
+            let style = if i == view.selected {
+                Style::default().fg(state.theme.foreground).add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default().fg(state.theme.foreground)
+            };
+            let deleted = entry.deleted_at().with_timezone(&Local).format("%b %d %H:%M");
+            let mut spans = vec![
+                Span::styled(format!("{deleted}  "), dim_style),
+                Span::styled(format!("{:<12} ", entry.origin_label()), Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    format!("[{}] {}", entry.item.state.to_char(), entry.item.content_with_tags()),
+                    style,
+                ),
+            ];
+            if *subtasks > 0 {
+                spans.push(Span::styled(format!(" +{subtasks}"), dim_style));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(list_items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_bottom(" Enter restore, D purge, Esc to close ")
+            .style(Style::default().bg(state.theme.background)),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
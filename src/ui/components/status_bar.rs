@@ -35,7 +35,9 @@ pub fn render(f: &mut Frame, state: &AppState, area: Rect) {
     };
 
     let date_str = state.viewing_date.format("%Y-%m-%d").to_string();
-    let date_label = if state.viewing_date == state.today {
+    let date_label = if let Some(ref name) = state.todo_list.name {
+        format!("{name} (list)")
+    } else if state.viewing_date == state.today {
         format!("{date_str} (today)")
     } else {
         format!("{date_str} (archived)")
@@ -1,4 +1,5 @@
 use crate::app::{AppState, Mode};
+use crate::storage::time_tracking::format_duration;
 use crate::todo::TodoState;
 use crate::ui::theme::Theme;
 use crate::utils::unicode::{after_first_char, first_char_as_str};
@@ -13,17 +14,29 @@ use unicode_width::UnicodeWidthStr;
 
 /// Compute the style for a todo item based on its state and selection
 /// Note: Cursor highlighting is now handled by ListState's highlight_style
-fn compute_item_style(state: TodoState, theme: &Theme, is_in_selection: bool) -> Style {
+fn compute_item_style(
+    state: TodoState,
+    theme: &Theme,
+    is_in_selection: bool,
+    is_blocked: bool,
+) -> Style {
     if is_in_selection {
         Style::default()
             .bg(Color::DarkGray)
             .fg(theme.foreground)
+    } else if is_blocked {
+        Style::default()
+            .fg(Color::DarkGray)
+            .add_modifier(Modifier::ITALIC)
+    } else if let Some(color) = theme.state_color(state) {
+        Style::default().fg(color)
     } else {
         match state {
             TodoState::Checked => Style::default().fg(Color::DarkGray),
             TodoState::Question => Style::default().fg(theme.question),
             TodoState::Exclamation => Style::default().fg(theme.exclamation),
             TodoState::InProgress => Style::default().fg(theme.in_progress),
+            TodoState::Custom(_) if state.is_complete() => Style::default().fg(Color::DarkGray),
             _ => Style::default().fg(theme.foreground),
         }
     }
@@ -31,7 +44,7 @@ fn compute_item_style(state: TodoState, theme: &Theme, is_in_selection: bool) ->
 
 pub fn render(f: &mut Frame, state: &mut AppState, area: Rect) {
     let mut items: Vec<ListItem> = Vec::new();
-    let hidden_indices = state.todo_list.build_hidden_indices();
+    let hidden_indices = state.hidden_indices();
     let available_width = area.width.saturating_sub(2) as usize;
 
     for (idx, item) in state.todo_list.items.iter().enumerate() {
@@ -39,6 +52,17 @@ pub fn render(f: &mut Frame, state: &mut AppState, area: Rect) {
             continue;
         }
 
+        if let Some(heading) = state.section_header(idx, &hidden_indices) {
+            let editing = state.mode == Mode::Section && idx == state.cursor_position;
+            let cursor = if editing { "▏" } else { "" };
+            items.push(ListItem::new(Line::from(Span::styled(
+                format!("## {heading}{cursor}"),
+                Style::default()
+                    .fg(state.theme.foreground)
+                    .add_modifier(Modifier::BOLD),
+            ))));
+        }
+
         let indent = "  ".repeat(item.indent_level);
         let has_children = state.todo_list.has_children(idx);
 
@@ -73,23 +97,52 @@ pub fn render(f: &mut Frame, state: &mut AppState, area: Rect) {
             .map(|d| format!(" [{}]", d.format("%Y-%m-%d")))
             .unwrap_or_default();
 
+        let tags_str: String = item.tags.iter().map(|t| format!(" #{t}")).collect();
+
+        let recurrence_str = item
+            .recurrence
+            .as_ref()
+            .map(|r| format!(" ↻ {r}"))
+            .unwrap_or_default();
+
+        let carried_str = carried_badge(item.carry_count);
+        let elapsed_str = state.elapsed(item).map(elapsed_badge).unwrap_or_default();
+
+        let is_blocked = state.is_blocked(idx);
+        let blocked_str = if is_blocked { BLOCKED_BADGE } else { "" };
+
         let collapse_indicator = if item.collapsed && has_children {
             let (completed, total) = state.todo_list.count_children_stats(idx);
             format!(" ({completed}/{total})")
         } else {
-            String::new()
+            state
+                .todo_list
+                .subtree_progress(idx)
+                .map(progress_badge)
+                .unwrap_or_default()
         };
 
         let prefix = format!("{indent}{fold_icon}");
         let prefix_width = prefix.width();
         let checkbox_with_space = format!("{checkbox} ");
         let checkbox_width = checkbox_with_space.width();
-        let content_with_extras = format!("{}{}{}", item.content, due_date_str, collapse_indicator);
+        let content_with_extras = format!(
+            "{}{}{}{}{}{}{}{}",
+            elapsed_str,
+            item.content,
+            tags_str,
+            recurrence_str,
+            due_date_str,
+            carried_str,
+            blocked_str,
+            collapse_indicator
+        );
 
         let is_in_selection = state.is_selected(idx) && state.mode == Mode::Visual;
 
         // Use same style for entire line so highlight is uniform
-        let content_style = compute_item_style(item.state, &state.theme, is_in_selection);
+        let content_style =
+            compute_item_style(item.state, &state.theme, is_in_selection, is_blocked);
 
         let content_max_width = available_width.saturating_sub(prefix_width + checkbox_width);
 
@@ -114,7 +167,16 @@ pub fn render(f: &mut Frame, state: &mut AppState, area: Rect) {
             let should_truncate = item.collapsed && has_description;
 
             if should_truncate {
-                let content_with_due = format!("{}{}", item.content, due_date_str);
+                let content_with_due = format!(
+                    "{}{}{}{}{}{}{}",
+                    elapsed_str,
+                    item.content,
+                    tags_str,
+                    recurrence_str,
+                    due_date_str,
+                    carried_str,
+                    blocked_str
+                );
                 let indicator_width = collapse_indicator.width();
                 let available_for_content = content_max_width.saturating_sub(indicator_width);
                 let truncated_content =
@@ -292,6 +354,11 @@ pub fn render(f: &mut Frame, state: &mut AppState, area: Rect) {
     } else {
         ""
     };
+    let filter_suffix = state
+        .tag_filter
+        .as_ref()
+        .map(|tag| format!(" #{tag}"))
+        .unwrap_or_default();
 
     // Calculate scroll position indicator
     let total_visible_items = state.visible_item_count();
@@ -309,9 +376,10 @@ pub fn render(f: &mut Frame, state: &mut AppState, area: Rect) {
     };
 
     let title = format!(
-        " Todo List - {}{}{} ",
-        state.viewing_date.format("%B %d, %Y"),
+        " Todo List - {}{}{}{} ",
+        state.todo_list.title(),
         title_suffix,
+        filter_suffix,
         scroll_info
     );
 
@@ -345,6 +413,36 @@ pub fn render(f: &mut Frame, state: &mut AppState, area: Rect) {
     }
 }
 
+/// Badge for todos waiting on an unfinished blocker.
+pub const BLOCKED_BADGE: &str = " (blocked)";
+
+/// Time logged on an in-progress item, shown right after its spinner.
+pub fn elapsed_badge(elapsed: chrono::Duration) -> String {
+    format!("{} · ", format_duration(elapsed))
+}
+
+/// Cells in the subtree progress bar.
+const PROGRESS_CELLS: usize = 5;
+
+/// Progress of a partly finished subtree, e.g. " ▰▰▱▱▱ 40%".
+pub fn progress_badge(percent: usize) -> String {
+    let filled = (percent * PROGRESS_CELLS).div_ceil(100).min(PROGRESS_CELLS);
+    format!(
+        " {}{} {percent}%",
+        "▰".repeat(filled),
+        "▱".repeat(PROGRESS_CELLS - filled)
+    )
+}
+
+/// Badge for todos that have been rolled over unfinished, e.g. " (carried 6 days)".
+pub fn carried_badge(carry_count: u32) -> String {
+    match carry_count {
+        0 => String::new(),
+        1 => " (carried 1 day)".to_string(),
+        n => format!(" (carried {n} days)"),
+    }
+}
+
 fn build_wrapped_edit_lines(state: &AppState, available_width: usize) -> Vec<Line<'static>> {
     build_wrapped_edit_lines_with_indent(state, available_width, state.pending_indent_level)
 }
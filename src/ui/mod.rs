@@ -3,7 +3,8 @@ pub mod theme;
 
 use crate::app::{event::handle_key_event, event::handle_mouse_event, AppState};
 use crate::storage::UiCache;
-use crate::utils::paths::get_database_path;
+use crate::utils::paths::{get_dailies_dir, get_database_path};
+use chrono::NaiveDate;
 use anyhow::Result;
 use crossterm::{
     event::{
@@ -62,38 +63,60 @@ pub fn run_tui(mut state: AppState) -> Result<()> {
     result
 }
 
-fn setup_database_watcher(tx: mpsc::Sender<()>) -> Option<RecommendedWatcher> {
-    let db_path = match get_database_path() {
-        Ok(path) => path,
-        Err(_) => return None,
-    };
+/// Something changed on disk behind the app's back.
+enum WatchEvent {
+    Database,
+    /// A daily markdown file, possibly edited in another program.
+    DailyFile(NaiveDate),
+}
 
+/// Watch the database and the dailies directory, so saves made by the API, the MCP server
+/// or an editor show up while the TUI is open.
+fn setup_database_watcher(tx: mpsc::Sender<WatchEvent>) -> Option<RecommendedWatcher> {
+    let db_path = get_database_path().ok()?;
+    let data_dir = db_path.parent()?.to_path_buf();
+    let dailies_dir = get_dailies_dir().ok()?;
+
+    // In WAL mode other processes' writes land in `todos.db-wal` until a checkpoint copies
+    // them into `todos.db`, so the directory is watched for changes to either file
+    let mut wal_name = db_path.file_name()?.to_os_string();
+    wal_name.push("-wal");
+    let db_files = [db_path.clone(), data_dir.join(wal_name)];
     let watcher = RecommendedWatcher::new(
         move |res: Result<notify::Event, notify::Error>| {
-            if let Ok(event) = res
-                && event.kind.is_modify() {
-                    let _ = tx.send(());
+            let Ok(event) = res else {
+                return;
+            };
+            if !(event.kind.is_modify() || event.kind.is_create()) {
+                return;
+            }
+            for path in &event.paths {
+                if db_files.contains(path) {
+                    let _ = tx.send(WatchEvent::Database);
+                } else if path.extension().is_some_and(|ext| ext == "md")
+                    && let Some(date) = path
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .and_then(|stem| NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok())
+                {
+                    let _ = tx.send(WatchEvent::DailyFile(date));
                 }
+            }
         },
         Config::default(),
     );
 
-    match watcher {
-        Ok(mut w) => {
-            if w.watch(&db_path, RecursiveMode::NonRecursive).is_ok() {
-                Some(w)
-            } else {
-                None
-            }
-        }
-        Err(_) => None,
-    }
+    let mut watcher = watcher.ok()?;
+    watcher.watch(&data_dir, RecursiveMode::NonRecursive).ok()?;
+    // The database is still watched if the dailies directory cannot be watched
+    let _ = watcher.watch(&dailies_dir, RecursiveMode::NonRecursive);
+    Some(watcher)
 }
 
 fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     state: &mut AppState,
-    db_rx: mpsc::Receiver<()>,
+    db_rx: mpsc::Receiver<WatchEvent>,
 ) -> Result<()> {
     loop {
         state.clear_expired_status_message();
@@ -107,18 +130,28 @@ fn run_app(
         if event::poll(Duration::from_millis(100))? {
             match event::read()? {
                 Event::Key(key) if key.kind == KeyEventKind::Press => {
-                    handle_key_event(key, state)?;
+                    let result = handle_key_event(key, state);
+                    state.recover_from_save_conflict(result)?;
                 }
                 Event::Mouse(mouse) => {
-                    handle_mouse_event(mouse, state)?;
+                    let result = handle_mouse_event(mouse, state);
+                    state.recover_from_save_conflict(result)?;
                 }
                 _ => {}
             }
         }
 
         let mut should_reload = false;
-        while db_rx.try_recv().is_ok() {
-            should_reload = true;
+        let mut edited_files = Vec::new();
+        while let Ok(event) = db_rx.try_recv() {
+            match event {
+                WatchEvent::Database => should_reload = true,
+                WatchEvent::DailyFile(date) if !edited_files.contains(&date) => edited_files.push(date),
+                WatchEvent::DailyFile(_) => {}
+            }
+        }
+        for date in edited_files {
+            should_reload |= state.sync_daily_file(date);
         }
         if should_reload {
             let _ = state.reload_from_database();
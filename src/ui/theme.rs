@@ -1,5 +1,7 @@
 use crate::config::Config;
+use crate::todo::{StateRegistry, TodoState};
 use ratatui::style::Color;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct Theme {
@@ -10,6 +12,8 @@ pub struct Theme {
     pub in_progress: Color,
     pub status_bar_bg: Color,
     pub status_bar_fg: Color,
+    /// Per-state colours from the `[states]` config section, keyed by marker.
+    pub state_colors: HashMap<char, Color>,
 }
 
 impl Theme {
@@ -22,6 +26,7 @@ impl Theme {
             in_progress: Color::Cyan,
             status_bar_bg: Color::DarkGray,
             status_bar_fg: Color::White,
+            state_colors: HashMap::new(),
         }
     }
 
@@ -34,6 +39,7 @@ impl Theme {
             in_progress: Color::Cyan,
             status_bar_bg: Color::DarkGray,
             status_bar_fg: Color::White,
+            state_colors: HashMap::new(),
         }
     }
 
@@ -46,15 +52,27 @@ impl Theme {
             in_progress: Color::Blue,
             status_bar_bg: Color::LightBlue,
             status_bar_fg: Color::Black,
+            state_colors: HashMap::new(),
         }
     }
 
     pub fn from_config(config: &Config) -> Self {
-        match config.theme.as_str() {
+        let mut theme = match config.theme.as_str() {
             "dark" => Self::dark(),
             "light" => Self::light(),
             _ => Self::default_theme(),
-        }
+        };
+        theme.state_colors = StateRegistry::global()
+            .definitions()
+            .iter()
+            .filter_map(|d| Some((d.marker, d.color.as_deref()?.parse().ok()?)))
+            .collect();
+        theme
+    }
+
+    /// Configured colour for `state`, if the `[states]` section sets one.
+    pub fn state_color(&self, state: TodoState) -> Option<Color> {
+        self.state_colors.get(&state.to_char()).copied()
     }
 }
 
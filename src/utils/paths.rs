@@ -1,11 +1,81 @@
 use anyhow::{Result, anyhow};
 use chrono::NaiveDate;
+use std::ffi::OsString;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::RwLock;
 
+/// Environment variable naming the data directory, overridden by `--data-dir`.
+pub const HOME_ENV: &str = "TOTUI_HOME";
+
+/// Data directory and profile chosen on the command line.
+struct DataLocation {
+    data_dir: Option<PathBuf>,
+    profile: Option<String>,
+}
+
+static DATA_LOCATION: RwLock<DataLocation> = RwLock::new(DataLocation {
+    data_dir: None,
+    profile: None,
+});
+
+/// Use `data_dir` instead of `TOTUI_HOME` or the default directory, and keep everything in
+/// the `profile` subdirectory of it. Call before anything touches the data directory.
+pub fn set_data_location(data_dir: Option<PathBuf>, profile: Option<&str>) -> Result<()> {
+    let profile = profile.map(validate_profile_name).transpose()?;
+    let mut location = DATA_LOCATION.write().unwrap_or_else(|e| e.into_inner());
+    location.data_dir = data_dir;
+    location.profile = profile.map(String::from);
+    Ok(())
+}
+
+fn validate_profile_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err(anyhow!(
+            "Invalid profile name '{name}'. Use letters, digits, '-' and '_'"
+        ));
+    }
+    Ok(name)
+}
+
+/// Pick the data directory: an explicit `--data-dir`, then `TOTUI_HOME`, then `~/.to-tui`
+/// if it already exists, then the XDG data directory (`$XDG_DATA_HOME/to-tui`).
+fn resolve_base_dir(
+    explicit: Option<PathBuf>,
+    env_home: Option<OsString>,
+    home: Option<PathBuf>,
+    xdg_data: Option<PathBuf>,
+) -> Result<PathBuf> {
+    if let Some(dir) = explicit {
+        return Ok(dir);
+    }
+    if let Some(dir) = env_home.filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    let legacy = home.map(|home| home.join(".to-tui"));
+    match (legacy, xdg_data) {
+        (Some(legacy), _) if legacy.is_dir() => Ok(legacy),
+        (_, Some(data)) => Ok(data.join("to-tui")),
+        (Some(legacy), None) => Ok(legacy),
+        (None, None) => Err(anyhow!("Could not find home directory")),
+    }
+}
+
+/// Directory holding the database, daily files, config, PID file and UI cache of the
+/// current profile.
 pub fn get_to_tui_dir() -> Result<PathBuf> {
-    let home = dirs::home_dir().ok_or_else(|| anyhow!("Could not find home directory"))?;
-    Ok(home.join(".to-tui"))
+    let location = DATA_LOCATION.read().unwrap_or_else(|e| e.into_inner());
+    let base = resolve_base_dir(
+        location.data_dir.clone(),
+        std::env::var_os(HOME_ENV),
+        dirs::home_dir(),
+        dirs::data_dir(),
+    )?;
+    Ok(match location.profile {
+        Some(ref profile) => base.join("profiles").join(profile),
+        None => base,
+    })
 }
 
 pub fn get_dailies_dir() -> Result<PathBuf> {
@@ -13,6 +83,11 @@ pub fn get_dailies_dir() -> Result<PathBuf> {
     Ok(todo_dir.join("dailies"))
 }
 
+pub fn get_lists_dir() -> Result<PathBuf> {
+    let todo_dir = get_to_tui_dir()?;
+    Ok(todo_dir.join("lists"))
+}
+
 pub fn get_config_path() -> Result<PathBuf> {
     let todo_dir = get_to_tui_dir()?;
     Ok(todo_dir.join("config.toml"))
@@ -28,6 +103,12 @@ pub fn get_pid_file_path() -> Result<PathBuf> {
     Ok(todo_dir.join("server.pid"))
 }
 
+/// File every running TUI holds a shared lock on, so 'restore' can tell one is open.
+pub fn get_tui_lock_path() -> Result<PathBuf> {
+    let todo_dir = get_to_tui_dir()?;
+    Ok(todo_dir.join("tui.lock"))
+}
+
 pub fn get_ui_cache_path() -> Result<PathBuf> {
     let todo_dir = get_to_tui_dir()?;
     Ok(todo_dir.join("ui_cache.json"))
@@ -39,11 +120,38 @@ pub fn get_daily_file_path(date: NaiveDate) -> Result<PathBuf> {
     Ok(dailies_dir.join(filename))
 }
 
-pub fn ensure_directories_exist() -> Result<()> {
-    let dailies_dir = get_dailies_dir()?;
+/// Check that `name` can be used for a project list and its file: letters, digits, `-`
+/// and `_` only. "today" is reserved for the daily list.
+pub fn validate_list_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(anyhow!("List name cannot be empty"));
+    }
+    if name.eq_ignore_ascii_case("today") {
+        return Err(anyhow!("'today' is reserved for the daily list"));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(anyhow!(
+            "Invalid list name '{name}'. Use letters, digits, '-' and '_'"
+        ));
+    }
+    Ok(name)
+}
 
-    if !dailies_dir.exists() {
-        fs::create_dir_all(&dailies_dir)?;
+pub fn get_list_file_path(name: &str) -> Result<PathBuf> {
+    let name = validate_list_name(name)?;
+    let lists_dir = get_lists_dir()?;
+    Ok(lists_dir.join(format!("{name}.md")))
+}
+
+pub fn ensure_directories_exist() -> Result<()> {
+    for dir in [get_dailies_dir()?, get_lists_dir()?] {
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+        }
     }
 
     Ok(())
@@ -57,20 +165,48 @@ mod tests {
     #[test]
     fn test_get_to_tui_dir() {
         let dir = get_to_tui_dir().unwrap();
-        assert!(dir.to_string_lossy().contains(".to-tui"));
+        if std::env::var_os(HOME_ENV).is_none() {
+            let name = dir.file_name().unwrap();
+            assert!(name == ".to-tui" || name == "to-tui");
+        }
+    }
+
+    #[test]
+    fn test_resolve_base_dir() {
+        let home = tempfile::TempDir::new().unwrap();
+        let xdg = Some(PathBuf::from("/xdg/data"));
+        let explicit = Some(PathBuf::from("/explicit"));
+        let env_home = Some(OsString::from("/from-env"));
+
+        let resolve = |explicit: &Option<PathBuf>, env: &Option<OsString>| {
+            resolve_base_dir(explicit.clone(), env.clone(), Some(home.path().to_path_buf()), xdg.clone())
+                .unwrap()
+        };
+        assert_eq!(resolve(&explicit, &env_home), PathBuf::from("/explicit"));
+        assert_eq!(resolve(&None, &env_home), PathBuf::from("/from-env"));
+        assert_eq!(resolve(&None, &Some(OsString::new())), PathBuf::from("/xdg/data/to-tui"));
+
+        // An existing ~/.to-tui keeps being used
+        fs::create_dir(home.path().join(".to-tui")).unwrap();
+        assert_eq!(resolve(&None, &None), home.path().join(".to-tui"));
+    }
+
+    #[test]
+    fn test_validate_profile_name() {
+        assert_eq!(validate_profile_name(" work ").unwrap(), "work");
+        assert!(validate_profile_name("../personal").is_err());
+        assert!(validate_profile_name("").is_err());
     }
 
     #[test]
     fn test_get_dailies_dir() {
         let dir = get_dailies_dir().unwrap();
-        assert!(dir.to_string_lossy().contains(".to-tui"));
         assert!(dir.to_string_lossy().ends_with("dailies"));
     }
 
     #[test]
     fn test_get_config_path() {
         let path = get_config_path().unwrap();
-        assert!(path.to_string_lossy().contains(".to-tui"));
         assert!(path.to_string_lossy().ends_with("config.toml"));
     }
 
@@ -83,17 +219,26 @@ mod tests {
         assert!(path.to_string_lossy().ends_with("2025-12-31.md"));
     }
 
+    #[test]
+    fn test_get_list_file_path() {
+        let path = get_list_file_path("backlog").unwrap();
+        assert!(path.to_string_lossy().contains("lists"));
+        assert!(path.to_string_lossy().ends_with("backlog.md"));
+
+        assert!(get_list_file_path("../escape").is_err());
+        assert!(get_list_file_path("Today").is_err());
+        assert!(get_list_file_path(" ").is_err());
+    }
+
     #[test]
     fn test_get_database_path() {
         let path = get_database_path().unwrap();
-        assert!(path.to_string_lossy().contains(".to-tui"));
         assert!(path.to_string_lossy().ends_with("todos.db"));
     }
 
     #[test]
     fn test_get_pid_file_path() {
         let path = get_pid_file_path().unwrap();
-        assert!(path.to_string_lossy().contains(".to-tui"));
         assert!(path.to_string_lossy().ends_with("server.pid"));
     }
 }